@@ -0,0 +1,379 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use super::exec_signal::ExecSignal;
+
+use super::interpreter::Interpreter;
+
+use super::rei_generator::ReiGenerator;
+
+use crate::crux::output;
+use crate::crux::token::{Object, Token};
+
+use crate::backend::stack_trace::ExecContext;
+
+/// What a blocking job sends back: a way to build its result on the
+/// interpreter thread (objects aren't `Send`), or an error message.
+pub type JobResult = Result<Box<dyn FnOnce() -> Object + Send>, String>;
+
+/// Body of a task written in Rust. It's resumed with what the task it
+/// awaited gave, like an async function is at its `await`.
+pub type NativeBody =
+    dyn FnMut(&mut Interpreter, Result<Object, ExecSignal>) -> Result<Step, ExecSignal>;
+
+/// What a task's body does once resumed.
+pub enum Step {
+    /// Wait for this, a value that isn't a task is given back right away
+    Await(Object),
+    Return(Object),
+}
+
+/// How long the loop sleeps when every task is waiting on a timer or a job.
+const IDLE_WAIT: Duration = Duration::from_millis(1);
+
+/// Tasks that haven't finished yet. Everything runs on the interpreter
+/// thread, only the blocking part of a job (reading a file, accepting a
+/// connection...) runs on a thread of its own.
+#[derive(Debug, Default)]
+pub struct EventLoop {
+    tasks: Vec<Rc<ReiTask>>,
+    /// Failed tasks that are still referenced, so could be awaited later
+    unobserved: Vec<Rc<ReiTask>>,
+    /// Set once a thrown error nothing awaited was reported. The script
+    /// keeps going, then fails when the loop is done.
+    failed: bool,
+}
+
+impl EventLoop {
+    /// Schedules `task` and returns the object scripts await it through.
+    pub fn spawn(&mut self, task: ReiTask) -> Object {
+        let task = Rc::new(task);
+        self.tasks.push(task.clone());
+        Object::Task(task)
+    }
+}
+
+/// What `await` waits on: the call of an `async fn`, a timer or a job.
+#[derive(Debug)]
+pub struct ReiTask {
+    pub name: String,
+    state: RefCell<TaskState>,
+    /// Something awaited the task, so a failure doesn't go unreported
+    observed: Cell<bool>,
+    context: Rc<RefCell<ExecContext>>,
+}
+
+#[derive(Debug)]
+enum TaskState {
+    /// Body of an async function, resumed once what it awaits is ready
+    Running {
+        body: Body,
+        waiting: Waiting,
+    },
+    Timer(Instant),
+    Job(Receiver<JobResult>),
+    Done(Object),
+    Failed(RuntimeError<Token>),
+}
+
+#[derive(Clone)]
+enum Body {
+    Script(Rc<ReiGenerator>),
+    Native(Rc<RefCell<NativeBody>>),
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Script(body) => body.fmt(f),
+            Body::Native(_) => f.write_str("<native>"),
+        }
+    }
+}
+
+impl Body {
+    fn resume(
+        &self,
+        interpreter: &mut Interpreter,
+        sent: Result<Object, ExecSignal>,
+    ) -> Result<Step, ExecSignal> {
+        match self {
+            Body::Script(body) => Ok(match body.resume_with(interpreter, sent)? {
+                Some(awaited) => Step::Await(awaited),
+                None => Step::Return(body.returned()),
+            }),
+            Body::Native(body) => (body.borrow_mut())(interpreter, sent),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Waiting {
+    /// Resumed with this value on the next tick. Awaiting something that
+    /// isn't a task gives the value back.
+    Ready(Object),
+    Task(Rc<ReiTask>),
+}
+
+impl ReiTask {
+    pub fn coroutine(body: ReiGenerator, context: Rc<RefCell<ExecContext>>) -> Self {
+        let name = body.name.clone();
+        let state = TaskState::Running {
+            body: Body::Script(Rc::new(body)),
+            waiting: Waiting::Ready(Object::Null),
+        };
+        Self::with_state(name, state, context)
+    }
+
+    /// Runs `body` the way the loop runs an async function, so native code
+    /// can await what scripts return without blocking the other tasks. It
+    /// first gets null.
+    pub fn native<F>(name: &str, body: F, context: Rc<RefCell<ExecContext>>) -> Self
+    where
+        F: FnMut(&mut Interpreter, Result<Object, ExecSignal>) -> Result<Step, ExecSignal>
+            + 'static,
+    {
+        let state = TaskState::Running {
+            body: Body::Native(Rc::new(RefCell::new(body))),
+            waiting: Waiting::Ready(Object::Null),
+        };
+        Self::with_state(name.to_string(), state, context)
+    }
+
+    /// Finishes with null once `duration` has passed.
+    pub fn timer(duration: Duration, context: Rc<RefCell<ExecContext>>) -> Self {
+        let state = TaskState::Timer(Instant::now() + duration);
+        Self::with_state("timer".to_string(), state, context)
+    }
+
+    /// Runs `work` on its own thread. Failing with a message raises an error
+    /// `do/fail` can catch in whoever awaits the task.
+    pub fn job<F>(name: &str, work: F, context: Rc<RefCell<ExecContext>>) -> Self
+    where
+        F: FnOnce() -> JobResult + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The loop may be gone if the script already ended
+            let _ = sender.send(work());
+        });
+        Self::with_state(name.to_string(), TaskState::Job(receiver), context)
+    }
+
+    fn with_state(name: String, state: TaskState, context: Rc<RefCell<ExecContext>>) -> Self {
+        ReiTask {
+            name,
+            state: RefCell::new(state),
+            observed: Cell::new(false),
+            context,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            *self.state.borrow(),
+            TaskState::Done(_) | TaskState::Failed(_)
+        )
+    }
+
+    /// The result once the task has finished.
+    fn outcome(&self) -> Option<Result<Object, ExecSignal>> {
+        match &*self.state.borrow() {
+            TaskState::Done(value) => Some(Ok(value.clone())),
+            TaskState::Failed(err) => {
+                self.observed.set(true);
+                Some(Err(ExecSignal::RuntimeError(err.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    fn unhandled_error(&self) -> Option<RuntimeError<Token>> {
+        match &*self.state.borrow() {
+            TaskState::Failed(err) if !self.observed.get() => Some(err.clone()),
+            _ => None,
+        }
+    }
+
+    /// Timers and jobs finish on their own, the other tasks need one of them.
+    fn waits_on_outside(&self) -> bool {
+        matches!(
+            *self.state.borrow(),
+            TaskState::Timer(_) | TaskState::Job(_)
+        )
+    }
+
+    /// Moves the task forward if it can. True when it did.
+    fn poll(&self, interpreter: &mut Interpreter) -> bool {
+        // Only async functions and native bodies have something to resume, timers and jobs finish
+        // with what they were waiting for
+        let (body, sent) = match &*self.state.borrow() {
+            TaskState::Running { body, waiting } => {
+                let sent = match waiting {
+                    Waiting::Ready(value) => Ok(value.clone()),
+                    Waiting::Task(task) => match task.outcome() {
+                        Some(outcome) => outcome,
+                        None => return false,
+                    },
+                };
+                (Some(body.clone()), sent)
+            }
+            TaskState::Timer(deadline) if Instant::now() < *deadline => return false,
+            TaskState::Timer(_) => (None, Ok(Object::Null)),
+            TaskState::Job(receiver) => match receiver.try_recv() {
+                Ok(Ok(build)) => (None, Ok(build())),
+                Ok(Err(msg)) => (None, Err(self.error(msg))),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    (None, Err(self.error("Job ended without a result".into())))
+                }
+            },
+            TaskState::Done(_) | TaskState::Failed(_) => return false,
+        };
+
+        // No borrow is held while the body runs, it may look at this task
+        let state = match body {
+            Some(body) => match body.resume(interpreter, sent) {
+                Ok(Step::Await(Object::Task(task))) => TaskState::Running {
+                    body,
+                    waiting: Waiting::Task(task),
+                },
+                Ok(Step::Await(value)) => TaskState::Running {
+                    body,
+                    waiting: Waiting::Ready(value),
+                },
+                Ok(Step::Return(value)) => TaskState::Done(value),
+                Err(signal) => TaskState::failed(signal),
+            },
+            None => match sent {
+                Ok(value) => TaskState::Done(value),
+                Err(signal) => TaskState::failed(signal),
+            },
+        };
+        *self.state.borrow_mut() = state;
+        true
+    }
+
+    fn error(&self, msg: String) -> ExecSignal {
+        let err_type = RuntimeErrorType::CustomMsg { msg };
+        ExecSignal::RuntimeError(RuntimeError::new(err_type, self.context.clone()))
+    }
+}
+
+impl TaskState {
+    fn failed(signal: ExecSignal) -> Self {
+        match signal {
+            ExecSignal::RuntimeError(err) => TaskState::Failed(err),
+            // break and continue can't leave a function
+            ExecSignal::ControlFlow(_) => TaskState::Done(Object::Null),
+        }
+    }
+}
+
+/// Polls every pending task once, sleeping a little when none of them could
+/// move. False when nothing is left that could ever finish them.
+fn tick(interpreter: &mut Interpreter) -> bool {
+    let tasks = std::mem::take(&mut interpreter.event_loop.tasks);
+    let mut progressed = false;
+    for task in &tasks {
+        progressed |= task.poll(interpreter);
+    }
+
+    // Tasks spawned while polling go after the ones that were already there
+    let spawned = std::mem::take(&mut interpreter.event_loop.tasks);
+    for task in tasks.into_iter().chain(spawned) {
+        if !task.is_finished() {
+            interpreter.event_loop.tasks.push(task);
+        } else if let Some(err) = task.unhandled_error() {
+            // Nothing else holds the task, so nothing can await it anymore
+            if Rc::strong_count(&task) == 1 {
+                report(interpreter, err);
+            } else {
+                interpreter.event_loop.unobserved.push(task);
+            }
+        }
+    }
+
+    if progressed {
+        return true;
+    }
+    let pending = &interpreter.event_loop.tasks;
+    if pending.iter().any(|task| task.waits_on_outside()) {
+        thread::sleep(IDLE_WAIT);
+        return true;
+    }
+    false
+}
+
+/// Runs the loop until `task` finishes, for an `await` outside of any
+/// async function.
+pub fn block_on(interpreter: &mut Interpreter, task: &Rc<ReiTask>) -> Result<Object, ExecSignal> {
+    loop {
+        if let Some(outcome) = task.outcome() {
+            return outcome;
+        }
+        if !tick(interpreter) {
+            let err_type = RuntimeErrorType::TaskError {
+                msg: deadlock_message(&task.name),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                task.context.clone(),
+            )));
+        }
+    }
+}
+
+/// Runs the loop until every task has finished, once the script is over.
+pub fn run(interpreter: &mut Interpreter) {
+    while !interpreter.event_loop.tasks.is_empty() {
+        if !tick(interpreter) {
+            let task = interpreter.event_loop.tasks[0].clone();
+            let err_type = RuntimeErrorType::TaskError {
+                msg: deadlock_message(&task.name),
+            };
+            interpreter.event_loop.tasks.clear();
+            report(
+                interpreter,
+                RuntimeError::new(err_type, task.context.clone()),
+            );
+        }
+    }
+
+    for task in std::mem::take(&mut interpreter.event_loop.unobserved) {
+        if let Some(err) = task.unhandled_error() {
+            report(interpreter, err);
+        }
+    }
+    if std::mem::take(&mut interpreter.event_loop.failed) {
+        output::exit(1);
+    }
+}
+
+fn deadlock_message(name: &str) -> String {
+    format!(
+        "Task '{}' can never finish, every pending task is waiting on another one",
+        name
+    )
+}
+
+/// A failure nothing awaited ends the script, the way it would at top level.
+/// A thrown one lets the script go on like it does there, but still fails
+/// it in the end since nothing else would ever notice it.
+fn report(interpreter: &mut Interpreter, err: RuntimeError<Token>) {
+    let code = match err.err_type {
+        RuntimeErrorType::CustomMsg { .. } => None,
+        RuntimeErrorType::Interrupted => Some(130),
+        _ => Some(1),
+    };
+    output::err(&ExecSignal::RuntimeError(err).to_string());
+    match code {
+        Some(code) => output::exit(code),
+        None => interpreter.event_loop.failed = true,
+    }
+}
@@ -37,7 +37,12 @@ where
             TypeMismatch { .. }
             | UndefinedVariable { .. }
             | UndefinedProperty { .. }
+            | ConstantReassignment { .. }
+            | PrivateAccess { .. }
+            | ReadOnlyProperty { .. }
+            | ReadOnlyVariant { .. }
             | DividedByZero { .. }
+            | ZeroStep { .. }
             | OperandMustBeNumber { .. }
             | UnexpectedBinaryOperation { .. }
             | InvalidOperator { .. }
@@ -89,7 +94,13 @@ pub enum RuntimeErrorType<T> {
     TypeMismatch { token: T },
     UndefinedVariable { token: T },
     UndefinedProperty { token: T },
+    ConstantReassignment { token: T },
+    PrivateAccess { token: T },
+    ReadOnlyProperty { token: T },
+    ReadOnlyVariant { token: T },
+    NotIterable { token: T },
     DividedByZero { token: T },
+    ZeroStep { token: T },
     OperandMustBeNumber { token: T },
     UnexpectedBinaryOperation { token: T },
     InvalidOperator { token: T },
@@ -101,8 +112,24 @@ pub enum RuntimeErrorType<T> {
     ErrorInNativeFn { msg: String },
     ErrorInReflection { msg: String },
     ParentClassError { msg: String },
+    TraitError { msg: String },
+    EnumError { msg: String },
+    GeneratorError { msg: String },
+    TaskError { msg: String },
     CustomMsg { msg: String },
     CustomMsgFatal { msg: String },
+    Interrupted,
+}
+
+impl<T> RuntimeErrorType<T> {
+    /// Errors `do/fail` can catch, the others end the script. Interrupted
+    /// is Ctrl-C, raised between two statements.
+    pub fn is_catchable(&self) -> bool {
+        matches!(
+            self,
+            RuntimeErrorType::CustomMsg { .. } | RuntimeErrorType::Interrupted
+        )
+    }
 }
 
 impl<T> fmt::Display for RuntimeErrorType<T>
@@ -123,11 +150,22 @@ where
             RuntimeErrorType::TypeMismatch { token } => write!(f, "{} {}", util::red_colored("Type Mismatch | Both operands must be same type"), token),
             RuntimeErrorType::UndefinedVariable { token } => write!(f, "{} {}", util::red_colored("Undefined Variable"), token),
             RuntimeErrorType::UndefinedProperty { token } => write!(f, "{} {}", util::red_colored("Undefined Property"), token),
+            RuntimeErrorType::ConstantReassignment { token } => write!(f, "{} {}", util::red_colored("Constant Reassignment | Constants can't be assigned after their declaration"), token),
+            RuntimeErrorType::PrivateAccess { token } => write!(f, "{} {}", util::red_colored("Private Access | Private members can only be used inside their class"), token),
+            RuntimeErrorType::ReadOnlyProperty { token } => write!(f, "{} {}", util::red_colored("Read-only Property | The property has a getter but no setter"), token),
+            RuntimeErrorType::ReadOnlyVariant { token } => write!(f, "{} {}", util::red_colored("Read-only Variant | Enum variant fields are read-only"), token),
+            RuntimeErrorType::NotIterable { token } => write!(f, "{} {}", util::red_colored("Not Iterable | Only generators and vectors can be looped over"), token),
             RuntimeErrorType::DividedByZero { token } => write!(f, "{} {}", util::red_colored("Divided By Zero"), token),
+            RuntimeErrorType::ZeroStep { token } => write!(f, "{} {}", util::red_colored("Zero Step | A loop counting by 0 would never end"), token),
             RuntimeErrorType::OperandMustBeNumber { token } => write!(f, "{} {}", util::red_colored("Operand must be a number"), token),
             RuntimeErrorType::ParentClassError { msg } => write!(f, "{}", util::red_colored(msg)),
+            RuntimeErrorType::TraitError { msg } => write!(f, "{}", util::red_colored(msg)),
+            RuntimeErrorType::EnumError { msg } => write!(f, "{}", util::red_colored(msg)),
+            RuntimeErrorType::GeneratorError { msg } => write!(f, "{}", util::red_colored(msg)),
+            RuntimeErrorType::TaskError { msg } => write!(f, "{}", util::red_colored(msg)),
             RuntimeErrorType::CustomMsg { msg } => write!(f, "{}", util::red_colored(msg)),
-            RuntimeErrorType::CustomMsgFatal { msg } => write!(f, "{}", util::red_colored(msg))
+            RuntimeErrorType::CustomMsgFatal { msg } => write!(f, "{}", util::red_colored(msg)),
+            RuntimeErrorType::Interrupted => write!(f, "{}", util::red_colored("Interrupted | Ctrl-C stopped the script"))
         }
     }
 }
@@ -2,14 +2,16 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use crate::crux::output;
 use crate::crux::token::{Object, Token, TokenType};
 
 use crate::frontend::expr;
 use crate::frontend::expr::ExprId;
 
 use crate::backend::environment::{EnvRef, Environment};
+use crate::backend::event_loop::{self, EventLoop};
 use crate::backend::rei_callable::ReiCallable;
-use crate::backend::rei_class::ReiClass;
+use crate::backend::rei_class::{ReiClass, ReiField, StaticField};
 use crate::backend::stack_trace::{CallFrame, ExecContext};
 use crate::backend::stmt;
 
@@ -18,13 +20,28 @@ use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use super::exec_signal::ExecSignal;
 
 use super::native;
+use super::native::process::signal;
+use super::rei_enum::ReiEnum;
 use super::rei_function::ReiFunction;
+use super::rei_generator::ReiIterator;
+use super::rei_trait::ReiTrait;
+
+/// Methods understood by `@meta(...)` expressions.
+pub const META_METHODS: &[&str] = &["typeof", "destroy", "exist", "mutate", "mro"];
 
 pub struct Interpreter {
     pub environment: EnvRef,
     locals: HashMap<ExprId, usize>,
+    /// How far the class body lexically containing a member access is, for
+    /// `private` checks
+    enclosing_classes: HashMap<ExprId, usize>,
     exposed_value: Option<Object>,
     context: Rc<RefCell<ExecContext>>,
+    /// Tasks started by async functions, timers and I/O
+    pub event_loop: EventLoop,
+    /// Top-level declarations of the script, which threads it spawns
+    /// declare again
+    declarations: Vec<stmt::Stmt>,
 }
 
 impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
@@ -72,6 +89,40 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
         self.look_up_variable(id, keyword)
     }
 
+    fn visit_base_expr(
+        &mut self,
+        id: ExprId,
+        _keyword: &Token,
+        method: &Token,
+    ) -> Result<Object, ExecSignal> {
+        // `base` holds the class whose method is running and `this` lives
+        // one environment closer
+        let distance = self.locals.get(&id).copied().unwrap_or(0);
+        let class = Environment::get_at(&self.environment, distance, "base")?;
+        let this = Environment::get_at(&self.environment, distance.saturating_sub(1), "this")?;
+        let class = match &class {
+            Object::Callable(class) => class.as_any().downcast_ref::<ReiClass>(),
+            _ => None,
+        };
+
+        if let (Some(class), Object::Instance(instance)) = (class, &this) {
+            let instance = instance.borrow();
+            if let Some(found) = instance.class.find_method_after(class, &method.lexeme) {
+                let bound = found.bind(instance.clone(), self.context.clone())?;
+                let bound: Rc<dyn ReiCallable> = Rc::new(bound);
+                return Ok(Object::Callable(bound));
+            }
+        }
+
+        let err_type = RuntimeErrorType::UndefinedProperty {
+            token: method.clone(),
+        };
+        Err(ExecSignal::RuntimeError(RuntimeError::new(
+            err_type,
+            self.context.clone(),
+        )))
+    }
+
     fn visit_binary_expr(
         &mut self,
         left: &expr::Expr,
@@ -172,6 +223,19 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
         self.evaluate(right)
     }
 
+    // Inside async functions `await` suspends the task, see `ReiGenerator`.
+    // Anywhere else it runs the event loop until the task has finished.
+    fn visit_await_expr(
+        &mut self,
+        _keyword: &Token,
+        value: &expr::Expr,
+    ) -> Result<Object, ExecSignal> {
+        match self.evaluate(value)? {
+            Object::Task(task) => event_loop::block_on(self, &task),
+            value => Ok(value),
+        }
+    }
+
     fn visit_range_expr(
         &mut self,
         start: &expr::Expr,
@@ -248,26 +312,53 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
 
     fn visit_get_expr(
         &mut self,
-        object: &Box<expr::Expr>,
+        id: ExprId,
+        object: &expr::Expr,
         name: &Token,
     ) -> Result<Object, ExecSignal> {
         let object = self.evaluate(object)?;
 
         match object {
-            Object::Instance(ref instance) => instance.borrow().get(name),
+            Object::Instance(ref instance) => {
+                // Getters run arbitrary code, so don't hold the borrow
+                let instance = instance.borrow().clone();
+                self.check_access(id, &instance.class, name)?;
+                instance.get(self, name)
+            }
             Object::Callable(ref callable) => {
                 if let Some(class) = callable.as_any().downcast_ref::<ReiClass>() {
+                    self.check_access(id, class, name)?;
+                    if let Some(field) = class.static_field(&name.lexeme) {
+                        return Ok(field.value);
+                    }
                     if let Some(method) = class.find_static_method(&name.lexeme) {
                         let method: Rc<dyn ReiCallable> = Rc::new(method);
                         return Ok(Object::Callable(method));
                     }
                 }
+                if let Some(found) = callable.as_any().downcast_ref::<ReiEnum>() {
+                    if let Some(variant) = found.variant(&name.lexeme) {
+                        return Ok(variant);
+                    }
+                }
                 let err_type = RuntimeErrorType::UndefinedProperty {
                     token: name.clone(),
                 };
                 let stack_trace = RuntimeError::new(err_type, self.context.clone());
                 Err(ExecSignal::RuntimeError(stack_trace))
             }
+            Object::Generator(ref generator) => generator.method(&name.lexeme).ok_or_else(|| {
+                let err_type = RuntimeErrorType::UndefinedProperty {
+                    token: name.clone(),
+                };
+                ExecSignal::RuntimeError(RuntimeError::new(err_type, self.context.clone()))
+            }),
+            Object::Variant(ref variant) => variant.get(&name.lexeme).ok_or_else(|| {
+                let err_type = RuntimeErrorType::UndefinedProperty {
+                    token: name.clone(),
+                };
+                ExecSignal::RuntimeError(RuntimeError::new(err_type, self.context.clone()))
+            }),
             _ => {
                 let err_type = RuntimeErrorType::UndefinedProperty {
                     token: name.clone(),
@@ -280,6 +371,7 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
 
     fn visit_set_expr(
         &mut self,
+        id: ExprId,
         object: &expr::Expr,
         name: &Token,
         value: &expr::Expr,
@@ -287,10 +379,49 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
         let object = self.evaluate(object)?;
         match object {
             Object::Instance(ref instance) => {
+                self.check_access(id, &instance.borrow().class, name)?;
+                if instance.borrow().class.is_constant(&name.lexeme) {
+                    let err_type = RuntimeErrorType::ConstantReassignment {
+                        token: name.clone(),
+                    };
+                    let stack_trace = RuntimeError::new(err_type, self.context.clone());
+                    return Err(ExecSignal::RuntimeError(stack_trace));
+                }
+
                 let value = self.evaluate(value)?;
-                instance.borrow_mut().set(&name.lexeme, value.clone());
+                let mut instance = instance.borrow().clone();
+                instance.set(self, name, value.clone())?;
                 Ok(value)
             }
+            Object::Callable(ref callable)
+                if callable.as_any().downcast_ref::<ReiClass>().is_some() =>
+            {
+                let class = callable.as_any().downcast_ref::<ReiClass>().unwrap();
+                self.check_access(id, class, name)?;
+                let err_type = match class.static_field(&name.lexeme) {
+                    Some(field) if field.constant => RuntimeErrorType::ConstantReassignment {
+                        token: name.clone(),
+                    },
+                    Some(_) => {
+                        let value = self.evaluate(value)?;
+                        class.set_static_field(&name.lexeme, value.clone());
+                        return Ok(value);
+                    }
+                    None => RuntimeErrorType::UndefinedProperty {
+                        token: name.clone(),
+                    },
+                };
+                let stack_trace = RuntimeError::new(err_type, self.context.clone());
+                Err(ExecSignal::RuntimeError(stack_trace))
+            }
+            // Variants are values, their payload can't change
+            Object::Variant(_) => {
+                let err_type = RuntimeErrorType::ReadOnlyVariant {
+                    token: name.clone(),
+                };
+                let stack_trace = RuntimeError::new(err_type, self.context.clone());
+                Err(ExecSignal::RuntimeError(stack_trace))
+            }
             _ => {
                 let err_type = RuntimeErrorType::PropertyError;
                 let stack_trace = RuntimeError::new(err_type, self.context.clone());
@@ -326,7 +457,7 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
                 {
                     let mut visited = vec![inst.borrow().class.clone()];
                     while let Some(klass) = visited.pop() {
-                        if klass.name == *class_name {
+                        if klass.name == *class_name || klass.traits.contains(class_name) {
                             return Ok(Object::Bool(true));
                         }
                         for parent in &klass.superclass_refs {
@@ -334,6 +465,12 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
                         }
                     }
                     Ok(Object::Bool(false))
+                } else if let (Object::Variant(variant), Object::Str(type_name)) =
+                    (&instance_obj, &class_name_obj)
+                {
+                    Ok(Object::Bool(
+                        variant.enum_name == *type_name || variant.qualified_name() == *type_name,
+                    ))
                 } else {
                     let err_type = RuntimeErrorType::ErrorInReflection {
                         msg: "@typeof() expects (instance, string)".into(),
@@ -476,21 +613,23 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
                 };
 
                 let inst_ref = instance.borrow();
-                let mut klass = inst_ref.class.clone();
-                let mut exists = inst_ref.fields.borrow().contains_key(&name);
-
-                while !exists {
-                    exists = klass.methods.contains_key(&name);
-                    if exists {
-                        break;
-                    }
-                    if let Some(super_ref) = klass.superclass_refs.first() {
-                        klass = super_ref.clone();
-                    } else {
-                        break;
-                    }
+                // Point the error at the meta call, naming the field
+                let mut field = method.clone();
+                field.lexeme = name.clone();
+                self.check_access(id, &inst_ref.class, &field)?;
+                if inst_ref.class.is_constant(&name) {
+                    let err_type = RuntimeErrorType::ErrorInReflection {
+                        msg: format!("@mutate() failed: '{}' is a constant", name),
+                    };
+                    return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                        err_type,
+                        self.context.clone(),
+                    )));
                 }
 
+                let exists = inst_ref.fields.borrow().contains_key(&name)
+                    || inst_ref.class.find_method(&name).is_some();
+
                 if exists {
                     drop(inst_ref);
                     instance
@@ -513,6 +652,43 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
                 }
             }
 
+            "mro" => {
+                if args.len() != 1 {
+                    let err_type = RuntimeErrorType::ErrorInReflection {
+                        msg: "@mro() expects 1 argument".into(),
+                    };
+                    return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                        err_type,
+                        self.context.clone(),
+                    )));
+                }
+
+                let class = match self.evaluate(&args[0])? {
+                    Object::Instance(inst) => Some(inst.borrow().class.as_ref().clone()),
+                    Object::Callable(c) => c.as_any().downcast_ref::<ReiClass>().cloned(),
+                    _ => None,
+                };
+
+                match class {
+                    Some(class) => {
+                        let names = class
+                            .resolution_order()
+                            .map(|c| Object::Str(c.name.clone()))
+                            .collect();
+                        Ok(Object::Vec(Rc::new(RefCell::new(names))))
+                    }
+                    None => {
+                        let err_type = RuntimeErrorType::ErrorInReflection {
+                            msg: "@mro() expects a class or an instance".into(),
+                        };
+                        Err(ExecSignal::RuntimeError(RuntimeError::new(
+                            err_type,
+                            self.context.clone(),
+                        )))
+                    }
+                }
+            }
+
             _ => {
                 let err_type = RuntimeErrorType::ErrorInReflection {
                     msg: format!("Unknown meta method '@{}'", method.lexeme),
@@ -529,6 +705,7 @@ impl expr::Visitor<Result<Object, ExecSignal>> for Interpreter {
 impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
     fn visit_exception_stmt(
         &mut self,
+        _keyword: &Token,
         do_stmts: &stmt::Stmt,
         fail_stmts: &stmt::Stmt,
         fail_binding: &Option<Box<stmt::Stmt>>,
@@ -539,10 +716,7 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         if let Err(signal) = result {
             match signal {
                 ExecSignal::RuntimeError(err_obj) => {
-                    let is_custom_msg =
-                        matches!(&err_obj.err_type, RuntimeErrorType::CustomMsg { .. });
-
-                    if is_custom_msg {
+                    if err_obj.err_type.is_catchable() {
                         // Normal recoverable
                         if let Some(binding_stmt) = fail_binding {
                             let exception = Object::Exception(Box::new(err_obj));
@@ -554,9 +728,9 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
                         if let Some(finish_block) = finish_stmts {
                             let _ = self.execute(finish_block);
                         }
-                        eprintln!("{}", err_obj);
+                        output::err(&format!("{}\n", err_obj));
                         self.context.borrow_mut().pop_call();
-                        std::process::exit(1);
+                        output::exit(1);
                     }
                 }
                 other => return Err(other),
@@ -578,6 +752,10 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         superclasses: &Vec<expr::Expr>,
         methods: &Vec<stmt::Stmt>,
         static_methods: &Vec<stmt::Stmt>,
+        fields: &[stmt::Stmt],
+        accessors: &[stmt::Stmt],
+        private_members: &[Token],
+        traits: &[expr::Expr],
         expose: &bool,
     ) -> Result<(), ExecSignal> {
         let mut superclass_objs = Vec::new();
@@ -611,19 +789,50 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
             }
         }
 
+        let mut trait_refs: Vec<ReiTrait> = Vec::with_capacity(traits.len());
+        for trait_expr in traits {
+            let evaluated = self.evaluate(trait_expr)?;
+            let found = match &evaluated {
+                Object::Callable(c) => c.as_any().downcast_ref::<ReiTrait>().cloned(),
+                _ => None,
+            };
+            let Some(found) = found else {
+                let msg = format!("{} is not a trait", evaluated);
+                let err_type = RuntimeErrorType::TraitError { msg };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type,
+                    self.context.clone(),
+                )));
+            };
+            trait_refs.push(found);
+        }
+
+        let Some(mro) = ReiClass::linearize(&superclass_refs) else {
+            let bases: Vec<&str> = superclass_refs.iter().map(|c| c.name.as_str()).collect();
+            let msg = format!(
+                "Cannot create a consistent method resolution order for class '{}' with bases {}",
+                name.lexeme,
+                bases.join(", ")
+            );
+            let err_type = RuntimeErrorType::ParentClassError { msg };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        };
+
         self.environment
             .borrow_mut()
             .define(name.lexeme.clone(), Object::Null)?;
 
-        let mut temp_env = None;
-        if !superclass_objs.is_empty() {
-            let env = Environment::from_enclosing(self.environment.clone(), self.context.clone());
-            for base_obj in superclass_objs.into_iter() {
-                env.borrow_mut().define(base_obj.to_string(), base_obj)?;
-            }
-            temp_env = Some(self.environment.clone()); // save old env
-            self.environment = env;
+        let env = Environment::from_enclosing(self.environment.clone(), self.context.clone());
+        for base_obj in superclass_objs.into_iter() {
+            env.borrow_mut().define(base_obj.to_string(), base_obj)?;
         }
+        // `base` lookups continue the MRO after the class being declared, and
+        // private access is checked against it. Set once the class exists.
+        env.borrow_mut().define("base".to_string(), Object::Null)?;
+        let enclosing = std::mem::replace(&mut self.environment, env);
 
         let mut klass_methods = HashMap::new();
         for method in methods {
@@ -631,6 +840,7 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
                 name: method_name,
                 params,
                 body,
+                is_async,
             } = method
             {
                 let is_init = method_name.lexeme == "init";
@@ -640,6 +850,7 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
                     body.clone(),
                     self.environment.clone(),
                     is_init,
+                    *is_async,
                 );
                 klass_methods.insert(method_name.lexeme.clone(), func);
             }
@@ -651,6 +862,7 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
                 name: method_name,
                 params,
                 body,
+                is_async,
             } = static_method
             {
                 let func = ReiFunction::new(
@@ -659,29 +871,219 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
                     body.clone(),
                     self.environment.clone(),
                     false,
+                    *is_async,
                 );
                 static_klass_methods.insert(method_name.lexeme.clone(), func);
             }
         }
 
-        let klass = ReiClass::new(
+        let (mut getters, mut setters) = (HashMap::new(), HashMap::new());
+        for accessor in accessors {
+            if let stmt::Stmt::Accessor {
+                kind,
+                name: property,
+                params,
+                body,
+            } = accessor
+            {
+                let func = ReiFunction::new(
+                    property.clone(),
+                    params.clone(),
+                    body.clone(),
+                    self.environment.clone(),
+                    false,
+                    false,
+                );
+                let table = if kind.lexeme == "get" {
+                    &mut getters
+                } else {
+                    &mut setters
+                };
+                table.insert(property.lexeme.clone(), func);
+            }
+        }
+
+        // Static fields are evaluated once, now; instance fields per instance
+        let mut klass_fields = Vec::new();
+        let mut static_fields = HashMap::new();
+        let mut failed = None;
+        for field in fields {
+            if let stmt::Stmt::Field {
+                name: field_name,
+                initializer,
+                constant,
+                is_static,
+            } = field
+            {
+                if !*is_static {
+                    klass_fields.push(ReiField {
+                        name: field_name.lexeme.clone(),
+                        initializer: initializer.as_deref().cloned(),
+                        constant: *constant,
+                        closure: self.environment.clone(),
+                    });
+                    continue;
+                }
+
+                let value = match initializer {
+                    Some(expr) => self.evaluate(expr),
+                    None => Ok(Object::Null),
+                };
+                match value {
+                    Ok(value) => {
+                        let field = StaticField {
+                            value,
+                            constant: *constant,
+                        };
+                        static_fields.insert(field_name.lexeme.clone(), field);
+                    }
+                    // Later initializers don't run, the class environment
+                    // is still left below
+                    Err(err) => {
+                        failed = Some(err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let base_env = std::mem::replace(&mut self.environment, enclosing);
+        if let Some(err) = failed {
+            return Err(err);
+        }
+
+        let mut klass = ReiClass::new(
             name.lexeme.clone(),
             superclass_refs,
+            mro,
             klass_methods,
             static_klass_methods,
-        );
+            klass_fields,
+            static_fields,
+        )
+        .with_accessors(getters, setters)
+        .with_private_members(private_members.iter().map(|m| m.lexeme.clone()).collect());
+
+        for found in &trait_refs {
+            if let Err(msg) = found.apply(&mut klass) {
+                let err_type = RuntimeErrorType::TraitError { msg };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type,
+                    self.context.clone(),
+                )));
+            }
+            klass.traits.push(found.name.clone());
+        }
+
         let callable: Rc<dyn ReiCallable> = Rc::new(klass);
+        base_env
+            .borrow_mut()
+            .define("base".to_string(), Object::Callable(callable.clone()))?;
         if *expose {
             self.exposed_value = Some(Object::Callable(callable.clone()));
         }
 
-        if let Some(env) = temp_env {
-            self.environment = env;
+        self.environment
+            .borrow_mut()
+            .assign(name, Object::Callable(callable))
+    }
+
+    fn visit_trait_stmt(
+        &mut self,
+        name: &Token,
+        required: &[stmt::Stmt],
+        methods: &[stmt::Stmt],
+        expose: bool,
+    ) -> Result<(), ExecSignal> {
+        let mut signatures = HashMap::new();
+        for signature in required {
+            if let stmt::Stmt::Function {
+                name: method_name,
+                params,
+                ..
+            } = signature
+            {
+                signatures.insert(method_name.lexeme.clone(), params.len());
+            }
+        }
+
+        let mut defaults = HashMap::new();
+        for method in methods {
+            if let stmt::Stmt::Function {
+                name: method_name,
+                params,
+                body,
+                is_async,
+            } = method
+            {
+                let func = ReiFunction::new(
+                    method_name.clone(),
+                    params.clone(),
+                    body.clone(),
+                    self.environment.clone(),
+                    false,
+                    *is_async,
+                );
+                defaults.insert(method_name.lexeme.clone(), func);
+            }
+        }
+
+        let found = ReiTrait::new(name.lexeme.clone(), signatures, defaults);
+        let callable: Rc<dyn ReiCallable> = Rc::new(found);
+        if expose {
+            self.exposed_value = Some(Object::Callable(callable.clone()));
         }
 
         self.environment
             .borrow_mut()
-            .assign(name, Object::Callable(callable))
+            .define(name.lexeme.clone(), Object::Callable(callable))
+    }
+
+    fn visit_enum_stmt(
+        &mut self,
+        name: &Token,
+        variants: &[(Token, Vec<Token>)],
+        expose: bool,
+    ) -> Result<(), ExecSignal> {
+        let variants = variants
+            .iter()
+            .map(|(variant, fields)| {
+                let fields = fields.iter().map(|f| f.lexeme.clone()).collect();
+                (variant.lexeme.clone(), fields)
+            })
+            .collect();
+
+        let found = ReiEnum::new(name.lexeme.clone(), variants);
+        let callable: Rc<dyn ReiCallable> = Rc::new(found);
+        if expose {
+            self.exposed_value = Some(Object::Callable(callable.clone()));
+        }
+
+        self.environment
+            .borrow_mut()
+            .define(name.lexeme.clone(), Object::Callable(callable))
+    }
+
+    fn visit_accessor_stmt(
+        &mut self,
+        _kind: &Token,
+        _name: &Token,
+        _params: &[Token],
+        _body: &[stmt::Stmt],
+    ) -> Result<(), ExecSignal> {
+        // Accessors only mean something inside a class body, see visit_class_stmt
+        Ok(())
+    }
+
+    fn visit_field_stmt(
+        &mut self,
+        _name: &Token,
+        _initializer: &Option<Box<expr::Expr>>,
+        _constant: bool,
+        _is_static: bool,
+    ) -> Result<(), ExecSignal> {
+        // Fields only mean something inside a class body, see visit_class_stmt
+        Ok(())
     }
 
     fn visit_expression_stmt(&mut self, expression: &expr::Expr) -> Result<(), ExecSignal> {
@@ -689,13 +1091,22 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         Ok(())
     }
 
-    fn visit_print_stmt(&mut self, expression: &expr::Expr) -> Result<(), ExecSignal> {
+    fn visit_print_stmt(
+        &mut self,
+        _keyword: &Token,
+        expression: &expr::Expr,
+    ) -> Result<(), ExecSignal> {
         let value = self.evaluate(expression)?;
-        print!("{}", self.stringify(&value));
+        let text = self.stringify(&value);
+        output::out(&text);
         Ok(())
     }
 
-    fn visit_throw_stmt(&mut self, expression: &Box<expr::Expr>) -> Result<(), ExecSignal> {
+    fn visit_throw_stmt(
+        &mut self,
+        _keyword: &Token,
+        expression: &expr::Expr,
+    ) -> Result<(), ExecSignal> {
         let obj = self.evaluate(expression)?;
         let value = format!("{}", self.stringify(&obj));
 
@@ -706,7 +1117,11 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         )));
     }
 
-    fn visit_fatal_stmt(&mut self, expression: &Box<expr::Expr>) -> Result<(), ExecSignal> {
+    fn visit_fatal_stmt(
+        &mut self,
+        _keyword: &Token,
+        expression: &expr::Expr,
+    ) -> Result<(), ExecSignal> {
         let obj = self.evaluate(expression)?;
         let value = format!("{}", self.stringify(&obj));
 
@@ -717,9 +1132,14 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         )));
     }
 
-    fn visit_println_stmt(&mut self, expression: &expr::Expr) -> Result<(), ExecSignal> {
+    fn visit_println_stmt(
+        &mut self,
+        _keyword: &Token,
+        expression: &expr::Expr,
+    ) -> Result<(), ExecSignal> {
         let value = self.evaluate(expression)?;
-        println!("{}", self.stringify(&value));
+        let text = self.stringify(&value);
+        output::out(&format!("{}\n", text));
         Ok(())
     }
 
@@ -775,26 +1195,152 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         Ok(())
     }
 
-    fn visit_break_stmt(&mut self) -> Result<(), ExecSignal> {
+    fn visit_for_stmt(
+        &mut self,
+        _keyword: &Token,
+        initializer: &Option<Box<stmt::Stmt>>,
+        condition: &Option<Box<expr::Expr>>,
+        increment: &Option<Box<expr::Expr>>,
+        body: &stmt::Stmt,
+    ) -> Result<(), ExecSignal> {
+        let env = Environment::from_enclosing(self.environment.clone(), self.context.clone());
+        self.with_env(env, |interpreter| {
+            if let Some(init) = initializer {
+                interpreter.execute(init)?;
+            }
+            loop {
+                if let Some(cond) = condition {
+                    let cond = interpreter.evaluate(cond)?;
+                    if !interpreter.is_truthy(&cond) {
+                        break;
+                    }
+                }
+                match interpreter.execute(body) {
+                    Ok(_) => {}
+                    Err(ExecSignal::ControlFlow(ControlFlow::Break)) => break,
+                    // `continue` still has to run the increment clause
+                    Err(ExecSignal::ControlFlow(ControlFlow::Continue)) => {}
+                    Err(e) => return Err(e),
+                }
+                if let Some(inc) = increment {
+                    interpreter.evaluate(inc)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_loop_stmt(
+        &mut self,
+        keyword: &Token,
+        name: &Token,
+        start: &expr::Expr,
+        end: &expr::Expr,
+        step: &expr::Expr,
+        body: &stmt::Stmt,
+    ) -> Result<(), ExecSignal> {
+        let env = Environment::from_enclosing(self.environment.clone(), self.context.clone());
+        self.with_env(env, |interpreter| {
+            let start = interpreter.evaluate(start)?;
+            interpreter
+                .environment
+                .borrow_mut()
+                .define(name.lexeme.clone(), start)?;
+
+            loop {
+                let current = interpreter.environment.borrow().get(name)?;
+                let end = interpreter.evaluate(end)?;
+                let step_value = interpreter.evaluate(step)?;
+                let Some(step) = interpreter.range_step(keyword, current, end, step, step_value)?
+                else {
+                    break;
+                };
+
+                match interpreter.execute(body) {
+                    Ok(_) => {}
+                    Err(ExecSignal::ControlFlow(ControlFlow::Break)) => break,
+                    Err(ExecSignal::ControlFlow(ControlFlow::Continue)) => {}
+                    Err(e) => return Err(e),
+                }
+
+                let current = interpreter.environment.borrow().get(name)?;
+                let next = interpreter.binary_number_operation(
+                    current,
+                    Object::Number(step),
+                    keyword.clone(),
+                    |a, b| a + b,
+                )?;
+                interpreter.environment.borrow_mut().assign(name, next)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_each_stmt(
+        &mut self,
+        keyword: &Token,
+        name: &Token,
+        iterable: &expr::Expr,
+        body: &stmt::Stmt,
+    ) -> Result<(), ExecSignal> {
+        let iterable = self.evaluate(iterable)?;
+        let mut items = ReiIterator::from_object(iterable, keyword, &self.context)?;
+
+        let env = Environment::from_enclosing(self.environment.clone(), self.context.clone());
+        self.with_env(env, |interpreter| {
+            interpreter
+                .environment
+                .borrow_mut()
+                .define(name.lexeme.clone(), Object::Null)?;
+
+            while let Some(item) = items.next(interpreter)? {
+                interpreter.environment.borrow_mut().assign(name, item)?;
+                match interpreter.execute(body) {
+                    Ok(_) => {}
+                    Err(ExecSignal::ControlFlow(ControlFlow::Break)) => break,
+                    Err(ExecSignal::ControlFlow(ControlFlow::Continue)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_use_stmt(
+        &mut self,
+        _keyword: &Token,
+        _path: &str,
+        _alias: &Token,
+        class: &Option<Box<stmt::Stmt>>,
+    ) -> Result<(), ExecSignal> {
+        match class {
+            Some(class) => self.execute(class),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), ExecSignal> {
         Err(ExecSignal::ControlFlow(ControlFlow::Break))
     }
 
-    fn visit_continue_stmt(&mut self) -> Result<(), ExecSignal> {
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), ExecSignal> {
         Err(ExecSignal::ControlFlow(ControlFlow::Continue))
     }
 
     fn visit_function_stmt(
         &mut self,
         name: &Token,
-        params: &Vec<Token>,
-        body: &Vec<stmt::Stmt>,
+        params: &[Token],
+        body: &[stmt::Stmt],
+        is_async: bool,
     ) -> Result<(), ExecSignal> {
         let function = ReiFunction::new(
             name.clone(),
-            params.clone(),
-            body.clone(),
+            params.to_vec(),
+            body.to_vec(),
             self.environment.clone(),
             false,
+            is_async,
         );
         let callable: Rc<dyn ReiCallable> = Rc::new(function);
         self.environment
@@ -814,6 +1360,21 @@ impl stmt::Visitor<Result<(), ExecSignal>> for Interpreter {
         };
         Err(ExecSignal::ControlFlow(ControlFlow::Return(value)))
     }
+
+    // Generators step through their own `yield`s, see `ReiGenerator`
+    fn visit_yield_stmt(
+        &mut self,
+        _keyword: &Token,
+        _value: &Option<Box<expr::Expr>>,
+    ) -> Result<(), ExecSignal> {
+        let err_type = RuntimeErrorType::GeneratorError {
+            msg: "'yield' can only be used inside a function".into(),
+        };
+        Err(ExecSignal::RuntimeError(RuntimeError::new(
+            err_type,
+            self.context.clone(),
+        )))
+    }
 }
 
 impl Interpreter {
@@ -825,37 +1386,144 @@ impl Interpreter {
         Ok(Interpreter {
             environment,
             locals,
+            enclosing_classes: HashMap::new(),
             exposed_value: None,
+            event_loop: EventLoop::default(),
             context,
+            declarations: vec![],
         })
     }
 
     pub fn interpret(&mut self, statements: Vec<stmt::Stmt>) {
+        let declarations = statements.iter().filter(|stmt| stmt.is_declaration());
+        self.declarations.extend(declarations.cloned());
         for stmt in statements {
             match self.execute(&stmt) {
                 Ok(()) => {}
                 Err(e) => {
-                    eprint!("{}", e);
+                    output::err(&e.to_string());
                     if let ExecSignal::RuntimeError(runtime_error) = &e {
                         match runtime_error.err_type {
                             RuntimeErrorType::CustomMsg { .. } => {}
-                            _ => std::process::exit(1),
+                            RuntimeErrorType::Interrupted => output::exit(130),
+                            _ => output::exit(1),
                         }
                     }
                     self.context.borrow_mut().pop_call();
                 }
             }
         }
+        event_loop::run(self);
+    }
+
+    /// Runs declarations a thread was given, keeping them for the threads
+    /// it spawns in turn.
+    pub fn declare(&mut self, declarations: Vec<stmt::Stmt>) -> Result<(), ExecSignal> {
+        for declaration in &declarations {
+            self.execute(declaration)?;
+        }
+        self.declarations.extend(declarations);
+        Ok(())
+    }
+
+    pub fn declarations(&self) -> &[stmt::Stmt] {
+        &self.declarations
     }
 
-    fn execute(&mut self, statement: &stmt::Stmt) -> Result<(), ExecSignal> {
+    pub fn execute(&mut self, statement: &stmt::Stmt) -> Result<(), ExecSignal> {
+        if signal::pending() {
+            let context = self.context.clone();
+            signal::dispatch(self, &context)?;
+        }
         statement.accept(self)
     }
 
+    /// Evaluates an expression inside another environment, e.g. a field
+    /// initializer in the scope its class was declared in.
+    pub fn evaluate_in(
+        &mut self,
+        expression: &expr::Expr,
+        env: EnvRef,
+    ) -> Result<Object, ExecSignal> {
+        self.with_env(env, |interpreter| interpreter.evaluate(expression))
+    }
+
+    /// Calls a global function the way a call expression would. The test
+    /// runner uses this to invoke each discovered test, threads to start
+    /// their function.
+    pub fn call_global(
+        &mut self,
+        name: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Object, ExecSignal> {
+        let callee = self.environment.borrow().get(name)?;
+        let Object::Callable(function) = callee else {
+            let err_type = RuntimeErrorType::NotCallable;
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        };
+
+        if function.arity() != arguments.len() {
+            let err_type = RuntimeErrorType::InvalidArguments {
+                token: name.clone(),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        }
+
+        let callframe = CallFrame::new(function.to_string(), name.get_location());
+        self.context.borrow_mut().push_call(callframe);
+        let result = function.call(self, &arguments, self.context.clone());
+        if result.is_ok() {
+            self.context.borrow_mut().pop_call();
+        }
+
+        result
+    }
+
     pub fn resolve(&mut self, expression_id: ExprId, depth: usize) {
         self.locals.insert(expression_id, depth);
     }
 
+    pub fn resolve_enclosing_class(&mut self, expression_id: ExprId, depth: usize) {
+        self.enclosing_classes.insert(expression_id, depth);
+    }
+
+    /// Private members can be reached from inside the class that declares
+    /// them and from its subclasses, nowhere else.
+    fn check_access(&self, id: ExprId, class: &ReiClass, name: &Token) -> Result<(), ExecSignal> {
+        let Some(owner) = class.private_owner(&name.lexeme) else {
+            return Ok(());
+        };
+
+        let accessor = self
+            .enclosing_classes
+            .get(&id)
+            .and_then(|distance| Environment::get_at(&self.environment, *distance, "base").ok());
+        let allowed = match accessor {
+            Some(Object::Callable(accessor)) => accessor
+                .as_any()
+                .downcast_ref::<ReiClass>()
+                .is_some_and(|accessor| accessor.resolution_order().any(|c| c.same_class(owner))),
+            _ => false,
+        };
+        if allowed {
+            return Ok(());
+        }
+
+        let err_type = RuntimeErrorType::PrivateAccess {
+            token: name.clone(),
+        };
+        Err(ExecSignal::RuntimeError(RuntimeError::new(
+            err_type,
+            self.context.clone(),
+        )))
+    }
+
     pub fn execute_block(
         &mut self,
         statements: &Vec<stmt::Stmt>,
@@ -880,12 +1548,15 @@ impl Interpreter {
                 s
             }
             Object::Range(s, e) => format!("<range | {}..{}>", s, e),
-            Object::MBlock(p, s) => format!("<mblock | ptr: {:p} size: {}>", p, s),
+            Object::Bytes(b) => format!("<bytes | size: {}>", b.borrow().len()),
             Object::Bool(b) => b.to_string(),
             Object::Dummy => "dummy".to_string(),
             Object::Str(s) => s.clone(),
             Object::Callable(c) => c.to_string(),
             Object::Instance(i) => i.borrow().to_string(),
+            Object::Variant(v) => v.to_string(),
+            Object::Generator(g) => format!("<generator {}>", g.name),
+            Object::Task(t) => format!("<task {}>", t.name),
             Object::Vec(v) => {
                 let vec_borrow = v.borrow();
                 let elements: Vec<String> = vec_borrow.iter().map(|o| o.to_string()).collect();
@@ -893,6 +1564,10 @@ impl Interpreter {
             }
             Object::Exception(e) => format!("{}", e),
             Object::Router(r) => format!("{:?}", r),
+            Object::Socket(s) => s.to_string(),
+            Object::Child(c) => c.to_string(),
+            Object::Thread(t) => t.to_string(),
+            Object::Channel(c) => c.to_string(),
         }
     }
 
@@ -900,7 +1575,7 @@ impl Interpreter {
         expression.accept(self)
     }
 
-    fn is_truthy(&self, object: &Object) -> bool {
+    pub fn is_truthy(&self, object: &Object) -> bool {
         match object {
             Object::Null => false,
             Object::Bool(v) => *v,
@@ -953,6 +1628,16 @@ impl Interpreter {
             (Object::Bool(a), Object::Bool(b)) => a == b,
             (Object::Number(a), Object::Number(b)) => a == b,
             (Object::Str(a), Object::Str(b)) => a == b,
+            (Object::Bytes(a), Object::Bytes(b)) => *a.borrow() == *b.borrow(),
+            // Same variant of the same enum, with equal payloads
+            (Object::Variant(a), Object::Variant(b)) => {
+                a.enum_name == b.enum_name
+                    && a.name == b.name
+                    && a.fields
+                        .iter()
+                        .zip(&b.fields)
+                        .all(|((_, x), (_, y))| self.is_equal(x.clone(), y.clone()))
+            }
             _ => false,
         }
     }
@@ -1011,6 +1696,55 @@ impl Interpreter {
         }
     }
 
+    /// Checks a `loop` counter against the end of its range. A step written
+    /// as a number counts up to the end, any other step (a variable, `-1`...)
+    /// counts down to it. Returns the step to add after the iteration, or
+    /// None when the loop is over.
+    pub fn range_step(
+        &self,
+        keyword: &Token,
+        current: Object,
+        end: Object,
+        step_expr: &expr::Expr,
+        step: Object,
+    ) -> Result<Option<f64>, ExecSignal> {
+        let (current, end, step) = match (current, end, step) {
+            (Object::Number(c), Object::Number(e), Object::Number(s)) => (c, e, s),
+            _ => {
+                let err_type = RuntimeErrorType::OperandMustBeNumber {
+                    token: keyword.clone(),
+                };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type,
+                    self.context.clone(),
+                )));
+            }
+        };
+        if step == 0.0 {
+            let err_type = RuntimeErrorType::ZeroStep {
+                token: keyword.clone(),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        }
+
+        let counts_up = matches!(
+            step_expr,
+            expr::Expr::Literal {
+                value: Object::Number(_),
+                ..
+            }
+        );
+        let in_range = if counts_up {
+            current < end
+        } else {
+            current > end
+        };
+        Ok(in_range.then_some(step))
+    }
+
     pub fn with_env<F, R>(&mut self, env: EnvRef, f: F) -> R
     where
         F: FnOnce(&mut Interpreter) -> R,
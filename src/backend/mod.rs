@@ -1,12 +1,16 @@
 pub mod environment;
+pub mod event_loop;
 pub mod exec_signal;
 pub mod interpreter;
 pub mod native;
 pub mod rei_callable;
 pub mod rei_class;
+pub mod rei_enum;
 pub mod rei_function;
+pub mod rei_generator;
 pub mod rei_instance;
 pub mod rei_return;
+pub mod rei_trait;
 pub mod resolver;
 pub mod stack_trace;
 pub mod stmt;
@@ -7,6 +7,7 @@ use std::time::{Duration, Instant};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::backend::environment::Environment;
+use crate::backend::event_loop::ReiTask;
 use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use crate::backend::exec_signal::ExecSignal;
 use crate::backend::interpreter::Interpreter;
@@ -85,6 +86,45 @@ impl ReiCallable for Sleep {
     }
 }
 
+/// Like sleep, but returns a task to `await` so other tasks keep running.
+#[derive(Clone, Debug)]
+pub struct Delay;
+impl ReiCallable for Delay {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let duration = match &arguments[0] {
+            Object::Number(ms) if *ms >= 0.0 => *ms,
+            _ => {
+                let err_type = RuntimeErrorType::ErrorInNativeFn {
+                    msg: "Expected number (milliseconds) as argument to <delay>".to_string(),
+                };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type, context,
+                )));
+            }
+        };
+
+        let timer = ReiTask::timer(Duration::from_millis(duration as u64), context);
+        Ok(interpreter.event_loop.spawn(timer))
+    }
+
+    fn to_string(&self) -> String {
+        String::from("<native_fn>delay")
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FormatTime;
 impl ReiCallable for FormatTime {
@@ -155,6 +195,9 @@ pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     let sleep: Rc<dyn ReiCallable> = Rc::new(Sleep);
     env.define("_C_sleep".to_string(), Object::Callable(sleep))?;
 
+    let delay: Rc<dyn ReiCallable> = Rc::new(Delay);
+    env.define("_C_delay".to_string(), Object::Callable(delay))?;
+
     let format_time: Rc<dyn ReiCallable> = Rc::new(FormatTime);
     env.define("_C_format_time".to_string(), Object::Callable(format_time))?;
 
@@ -0,0 +1,693 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use chrono::format::{Item, ParseErrorKind, StrftimeItems};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, TimeDelta, Timelike,
+    Utc,
+};
+
+use crate::backend::environment::Environment;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::ExecContext;
+
+use crate::crux::token::Object;
+
+/// What parse() tries, in order, when no format is given
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+fn native_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err_type = RuntimeErrorType::ErrorInNativeFn { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context))
+}
+
+/// Dates, offsets and formats that don't exist or don't parse come from
+/// data more often than from the script, which may want to catch them.
+fn date_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err_type = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context))
+}
+
+fn number_arg(
+    object: &Object,
+    name: &str,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<f64, ExecSignal> {
+    match object {
+        Object::Number(n) if n.is_finite() => Ok(*n),
+        _ => Err(native_error(
+            format!("Expected number ({})", name),
+            context.clone(),
+        )),
+    }
+}
+
+fn whole_arg(
+    object: &Object,
+    name: &str,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<i64, ExecSignal> {
+    let n = number_arg(object, name, context)?;
+    if n.fract() != 0.0 {
+        return Err(native_error(
+            format!("Expected a whole number ({})", name),
+            context.clone(),
+        ));
+    }
+    Ok(n as i64)
+}
+
+/// Seconds east of UTC, or "Z", "UTC", "+09:00", "-0530" or "+02".
+fn offset_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<FixedOffset, ExecSignal> {
+    let offset = match object {
+        Object::Number(seconds) if seconds.fract() == 0.0 => FixedOffset::east_opt(*seconds as i32),
+        Object::Str(text) => parse_offset(text),
+        _ => None,
+    };
+    offset.ok_or_else(|| {
+        date_error(
+            format!(
+                "Invalid offset {}, expected seconds east of UTC or like '+09:00'",
+                object
+            ),
+            context.clone(),
+        )
+    })
+}
+
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    if text == "Z" || text.eq_ignore_ascii_case("UTC") {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let digits = rest.replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+fn number_field(object: &Object, name: &str) -> Option<f64> {
+    let Object::Instance(instance) = object else {
+        return None;
+    };
+    match instance.borrow().fields.borrow().get(name) {
+        Some(Object::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+fn from_parts(timestamp: f64, nanosecond: f64, offset: f64) -> Option<DateTime<FixedOffset>> {
+    if timestamp.fract() != 0.0 || nanosecond.fract() != 0.0 || nanosecond < 0.0 {
+        return None;
+    }
+    let offset = FixedOffset::east_opt(offset as i32)?;
+    let utc = DateTime::from_timestamp(timestamp as i64, nanosecond as u32)?;
+    Some(utc.with_timezone(&offset))
+}
+
+/// The date and time a `Time` instance holds in its private `#timestamp`,
+/// `#nanosecond` and `#offset` fields.
+fn datetime_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<DateTime<FixedOffset>, ExecSignal> {
+    let timestamp = number_field(object, "#timestamp");
+    let nanosecond = number_field(object, "#nanosecond");
+    let offset = number_field(object, "#offset");
+    if let (Some(timestamp), Some(nanosecond), Some(offset)) = (timestamp, nanosecond, offset) {
+        if let Some(datetime) = from_parts(timestamp, nanosecond, offset) {
+            return Ok(datetime);
+        }
+    }
+    Err(native_error("Expected Time".into(), context.clone()))
+}
+
+/// Calls the `Time` class the script passed, so natives hand back
+/// instances of it.
+fn time_object(
+    class: &Object,
+    datetime: DateTime<FixedOffset>,
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) -> Result<Object, ExecSignal> {
+    let Object::Callable(class) = class else {
+        return Err(native_error("Expected the Time class".into(), context));
+    };
+    let arguments = vec![
+        Object::Number(datetime.timestamp() as f64),
+        Object::Number(datetime.timestamp_subsec_nanos() as f64),
+        Object::Number(datetime.offset().local_minus_utc() as f64),
+    ];
+    class.call(interpreter, &arguments, context)
+}
+
+/// Seconds, fractions included, as a TimeDelta.
+fn delta(seconds: f64) -> Option<TimeDelta> {
+    let whole = seconds.floor();
+    let nanos = ((seconds - whole) * 1e9).round().min(999_999_999.0);
+    TimeDelta::try_seconds(whole as i64)?.checked_add(&TimeDelta::nanoseconds(nanos as i64))
+}
+
+fn strftime<'a>(
+    format: &'a str,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Vec<Item<'a>>, ExecSignal> {
+    StrftimeItems::new(format)
+        .parse()
+        .map_err(|_| date_error(format!("Invalid format '{}'", format), context.clone()))
+}
+
+/// Text in `format`, read as UTC when it has no offset and as midnight
+/// when it has no time. Without a format, RFC 3339 or ISO 8601 with a
+/// space or a T.
+fn parse(text: &str, format: Option<&str>) -> Result<DateTime<FixedOffset>, String> {
+    let utc = |naive: NaiveDateTime| naive.and_utc().fixed_offset();
+    let Some(format) = format else {
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+            return Ok(datetime);
+        }
+        for format in NAIVE_FORMATS {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(text, format) {
+                return Ok(utc(naive));
+            }
+        }
+        return NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map(|date| utc(date.and_time(Default::default())))
+            .map_err(|_| "expected RFC 3339, like 2024-05-01T12:30:00+02:00".to_string());
+    };
+
+    let err = match DateTime::parse_from_str(text, format) {
+        Ok(datetime) => return Ok(datetime),
+        Err(err) if err.kind() != ParseErrorKind::NotEnough => err,
+        Err(_) => match NaiveDateTime::parse_from_str(text, format) {
+            Ok(naive) => return Ok(utc(naive)),
+            Err(err) if err.kind() != ParseErrorKind::NotEnough => err,
+            Err(_) => match NaiveDate::parse_from_str(text, format) {
+                Ok(date) => return Ok(utc(date.and_time(Default::default()))),
+                Err(err) => err,
+            },
+        },
+    };
+    Err(err.to_string())
+}
+
+/// fill(time, timestamp, nanosecond, offset) -> null, sets the private
+/// fields of a `Time`: the three given ones and the year, month, day,
+/// hour, minute, second, weekday (1 for Monday) and day_of_year they make
+#[derive(Clone, Debug)]
+pub struct Fill;
+impl ReiCallable for Fill {
+    fn arity(&self) -> usize {
+        4
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Instance(instance) = &arguments[0] else {
+            return Err(native_error("Expected Time".into(), context));
+        };
+        let timestamp = number_arg(&arguments[1], "timestamp", &context)?;
+        let nanosecond = number_arg(&arguments[2], "nanosecond", &context)?;
+        let offset = number_arg(&arguments[3], "offset", &context)?;
+        let Some(datetime) = from_parts(timestamp, nanosecond, offset) else {
+            return Err(date_error(
+                format!(
+                    "No time at timestamp {}, nanosecond {} and offset {}",
+                    timestamp, nanosecond, offset
+                ),
+                context,
+            ));
+        };
+        let components = [
+            ("#timestamp", timestamp),
+            ("#nanosecond", nanosecond),
+            ("#offset", offset),
+            ("#year", datetime.year() as f64),
+            ("#month", datetime.month() as f64),
+            ("#day", datetime.day() as f64),
+            ("#hour", datetime.hour() as f64),
+            ("#minute", datetime.minute() as f64),
+            ("#second", datetime.second() as f64),
+            ("#weekday", datetime.weekday().number_from_monday() as f64),
+            ("#day_of_year", datetime.ordinal() as f64),
+        ];
+        let instance = instance.borrow();
+        let mut fields = instance.fields.borrow_mut();
+        for (name, value) in components {
+            fields.insert(name.to_string(), Object::Number(value));
+        }
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_fill".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// now(class, local) -> Time, in UTC or the system's offset
+#[derive(Clone, Debug)]
+pub struct Now;
+impl ReiCallable for Now {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let now = match arguments[1] {
+            Object::Bool(true) => Local::now().fixed_offset(),
+            _ => Utc::now().fixed_offset(),
+        };
+        time_object(&arguments[0], now, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_now".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// from_parts(class, year, month, day, hour, minute, second, offset) ->
+/// Time, second may have a fraction
+#[derive(Clone, Debug)]
+pub struct FromParts;
+impl ReiCallable for FromParts {
+    fn arity(&self) -> usize {
+        8
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let year = whole_arg(&arguments[1], "year", &context)?;
+        let month = whole_arg(&arguments[2], "month", &context)?;
+        let day = whole_arg(&arguments[3], "day", &context)?;
+        let hour = whole_arg(&arguments[4], "hour", &context)?;
+        let minute = whole_arg(&arguments[5], "minute", &context)?;
+        let second = number_arg(&arguments[6], "second", &context)?;
+        let offset = offset_arg(&arguments[7], &context)?;
+
+        let nanos = ((second - second.floor()) * 1e9).round().min(999_999_999.0);
+        let datetime = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)
+            .and_then(|date| {
+                date.and_hms_nano_opt(
+                    hour as u32,
+                    minute as u32,
+                    second.floor() as u32,
+                    nanos as u32,
+                )
+            })
+            .and_then(|naive| naive.and_local_timezone(offset).single())
+            .ok_or_else(|| {
+                date_error(
+                    format!(
+                        "Invalid date {}-{:02}-{:02} {:02}:{:02}:{:02}",
+                        year, month, day, hour, minute, second
+                    ),
+                    context.clone(),
+                )
+            })?;
+        time_object(&arguments[0], datetime, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_from_parts".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// from_timestamp(class, seconds, offset) -> Time, seconds since the Unix
+/// epoch, fractions included
+#[derive(Clone, Debug)]
+pub struct FromTimestamp;
+impl ReiCallable for FromTimestamp {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let seconds = number_arg(&arguments[1], "seconds", &context)?;
+        let offset = offset_arg(&arguments[2], &context)?;
+        let datetime = delta(seconds)
+            .and_then(|delta| DateTime::UNIX_EPOCH.checked_add_signed(delta))
+            .ok_or_else(|| {
+                date_error(
+                    format!("Timestamp {} is out of range", seconds),
+                    context.clone(),
+                )
+            })?;
+        time_object(
+            &arguments[0],
+            datetime.with_timezone(&offset),
+            interpreter,
+            context,
+        )
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_from_timestamp".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// parse(class, text, format) -> Time, format is strftime-like or null
+#[derive(Clone, Debug)]
+pub struct Parse;
+impl ReiCallable for Parse {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Str(text) = &arguments[1] else {
+            return Err(native_error("Expected string to parse".into(), context));
+        };
+        let format = match &arguments[2] {
+            Object::Str(format) => {
+                strftime(format, &context)?;
+                Some(format.as_str())
+            }
+            Object::Null => None,
+            _ => {
+                return Err(native_error(
+                    "Expected string or null (format)".into(),
+                    context,
+                ))
+            }
+        };
+        let datetime = parse(text, format).map_err(|msg| {
+            date_error(format!("Invalid date '{}': {}", text, msg), context.clone())
+        })?;
+        time_object(&arguments[0], datetime, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_parse".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// format(time, format) -> string, format is strftime-like or null for
+/// RFC 3339
+#[derive(Clone, Debug)]
+pub struct Format;
+impl ReiCallable for Format {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[0], &context)?;
+        match &arguments[1] {
+            Object::Str(format) => {
+                let items = strftime(format, &context)?;
+                Ok(Object::Str(
+                    datetime.format_with_items(items.iter()).to_string(),
+                ))
+            }
+            Object::Null => Ok(Object::Str(datetime.to_rfc3339())),
+            _ => Err(native_error(
+                "Expected string or null (format)".into(),
+                context,
+            )),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_format".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// with_offset(class, time, offset) -> Time, the same instant elsewhere
+#[derive(Clone, Debug)]
+pub struct WithOffset;
+impl ReiCallable for WithOffset {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[1], &context)?;
+        let offset = offset_arg(&arguments[2], &context)?;
+        time_object(
+            &arguments[0],
+            datetime.with_timezone(&offset),
+            interpreter,
+            context,
+        )
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_with_offset".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// add(class, time, seconds) -> Time, seconds may be negative
+#[derive(Clone, Debug)]
+pub struct Add;
+impl ReiCallable for Add {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[1], &context)?;
+        let seconds = number_arg(&arguments[2], "seconds", &context)?;
+        let datetime = delta(seconds)
+            .and_then(|delta| datetime.checked_add_signed(delta))
+            .ok_or_else(|| date_error("Date out of range".into(), context.clone()))?;
+        time_object(&arguments[0], datetime, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_add".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// add_months(class, time, months) -> Time, the day is kept unless the
+/// month is shorter, then it's the month's last
+#[derive(Clone, Debug)]
+pub struct AddMonths;
+impl ReiCallable for AddMonths {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[1], &context)?;
+        let months = whole_arg(&arguments[2], "months", &context)?;
+        let shifted = u32::try_from(months.unsigned_abs())
+            .ok()
+            .and_then(|n| match months < 0 {
+                true => datetime.checked_sub_months(Months::new(n)),
+                false => datetime.checked_add_months(Months::new(n)),
+            })
+            .ok_or_else(|| date_error("Date out of range".into(), context.clone()))?;
+        time_object(&arguments[0], shifted, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_add_months".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// since(time, other) -> seconds from other to time, negative when other
+/// is later
+#[derive(Clone, Debug)]
+pub struct Since;
+impl ReiCallable for Since {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[0], &context)?;
+        let other = datetime_arg(&arguments[1], &context)?;
+        Ok(Object::Number(
+            datetime.signed_duration_since(other).as_seconds_f64(),
+        ))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_since".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// compare(time, other) -> -1, 0 or 1, whatever their offsets
+#[derive(Clone, Debug)]
+pub struct Compare;
+impl ReiCallable for Compare {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let datetime = datetime_arg(&arguments[0], &context)?;
+        let other = datetime_arg(&arguments[1], &context)?;
+        let order = match datetime.cmp(&other) {
+            Ordering::Less => -1.0,
+            Ordering::Equal => 0.0,
+            Ordering::Greater => 1.0,
+        };
+        Ok(Object::Number(order))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>dt_compare".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    let fill: Rc<dyn ReiCallable> = Rc::new(Fill);
+    env.define("_C_dt_fill".to_string(), Object::Callable(fill))?;
+
+    let now: Rc<dyn ReiCallable> = Rc::new(Now);
+    env.define("_C_dt_now".to_string(), Object::Callable(now))?;
+
+    let from_parts: Rc<dyn ReiCallable> = Rc::new(FromParts);
+    env.define("_C_dt_from_parts".to_string(), Object::Callable(from_parts))?;
+
+    let from_timestamp: Rc<dyn ReiCallable> = Rc::new(FromTimestamp);
+    env.define(
+        "_C_dt_from_timestamp".to_string(),
+        Object::Callable(from_timestamp),
+    )?;
+
+    let parse: Rc<dyn ReiCallable> = Rc::new(Parse);
+    env.define("_C_dt_parse".to_string(), Object::Callable(parse))?;
+
+    let format: Rc<dyn ReiCallable> = Rc::new(Format);
+    env.define("_C_dt_format".to_string(), Object::Callable(format))?;
+
+    let with_offset: Rc<dyn ReiCallable> = Rc::new(WithOffset);
+    env.define(
+        "_C_dt_with_offset".to_string(),
+        Object::Callable(with_offset),
+    )?;
+
+    let add: Rc<dyn ReiCallable> = Rc::new(Add);
+    env.define("_C_dt_add".to_string(), Object::Callable(add))?;
+
+    let add_months: Rc<dyn ReiCallable> = Rc::new(AddMonths);
+    env.define("_C_dt_add_months".to_string(), Object::Callable(add_months))?;
+
+    let since: Rc<dyn ReiCallable> = Rc::new(Since);
+    env.define("_C_dt_since".to_string(), Object::Callable(since))?;
+
+    let compare: Rc<dyn ReiCallable> = Rc::new(Compare);
+    env.define("_C_dt_compare".to_string(), Object::Callable(compare))?;
+
+    Ok(())
+}
@@ -1,8 +1,15 @@
 use std::any::Any;
 use std::cell::RefCell;
+use std::env;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use walkdir::WalkDir;
 
 use crate::backend::environment::Environment;
 use crate::backend::exec_signal::{
@@ -15,6 +22,209 @@ use crate::backend::stack_trace::ExecContext;
 
 use crate::crux::token::Object;
 
+/// Temp entries made by this process so far, part of their unique names.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn str_arg<'a>(
+    arguments: &'a [Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<&'a str, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Str(s)) => Ok(s),
+        _ => {
+            let err_type = RuntimeErrorType::ErrorInNativeFn {
+                msg: "expected Str".to_string(),
+            };
+            Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                context.clone(),
+            )))
+        }
+    }
+}
+
+fn bool_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<bool, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Bool(b)) => Ok(*b),
+        _ => {
+            let err_type = RuntimeErrorType::ErrorInNativeFn {
+                msg: "expected Bool".to_string(),
+            };
+            Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                context.clone(),
+            )))
+        }
+    }
+}
+
+fn bytes_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Vec<u8>, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Bytes(bytes)) => Ok(bytes.borrow().clone()),
+        _ => {
+            let err_type = RuntimeErrorType::ErrorInNativeFn {
+                msg: "expected Bytes".to_string(),
+            };
+            Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                context.clone(),
+            )))
+        }
+    }
+}
+
+fn paths_object(paths: Vec<PathBuf>) -> Object {
+    let items = paths
+        .into_iter()
+        .map(|p| Object::Str(p.to_string_lossy().to_string()))
+        .collect();
+    Object::Vec(Rc::new(RefCell::new(items)))
+}
+
+/// Failures of the file system itself can be caught with `do/fail`.
+fn fs_error(
+    action: &str,
+    path: &str,
+    error: impl std::fmt::Display,
+    context: Rc<RefCell<ExecContext>>,
+) -> ExecSignal {
+    let err_type = RuntimeErrorType::CustomMsg {
+        msg: format!("Failed to {} '{}': {}", action, path, error),
+    };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context))
+}
+
+fn append(path: &str, data: &[u8]) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(data)
+}
+
+fn metadata(path: &str, context: Rc<RefCell<ExecContext>>) -> Result<fs::Metadata, ExecSignal> {
+    fs::metadata(path).map_err(|e| fs_error("read metadata of", path, e, context))
+}
+
+/// Name for a new temp entry that no other process or call uses.
+fn temp_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let count = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("rei-{}-{}-{}", process::id(), count, nanos))
+}
+
+/// Whether `pattern` matches all of `items`, where a `star` pattern item
+/// stands for any run of items and `one` matches a single item. Tracks for
+/// each prefix of `items` whether the pattern so far matches it, so many
+/// stars don't backtrack.
+fn wildcard<P, I>(
+    pattern: &[P],
+    items: &[I],
+    star: impl Fn(&P) -> bool,
+    one: impl Fn(&P, &I) -> bool,
+) -> bool {
+    let mut matched = vec![false; items.len() + 1];
+    matched[0] = true;
+    for p in pattern {
+        let mut next = vec![false; items.len() + 1];
+        for j in 0..=items.len() {
+            next[j] = if star(p) {
+                matched[j] || (j > 0 && next[j - 1])
+            } else {
+                j > 0 && matched[j - 1] && one(p, &items[j - 1])
+            };
+        }
+        matched = next;
+    }
+    matched[items.len()]
+}
+
+/// Matches one path component against a pattern with `*` (any run of
+/// characters) and `?` (any single character).
+fn matches_component(pattern: &[char], name: &[char]) -> bool {
+    wildcard(pattern, name, |p| *p == '*', |p, n| *p == '?' || p == n)
+}
+
+/// Matches path components, where a `**` pattern component stands for any
+/// number of directories.
+fn matches_path(pattern: &[String], path: &[String]) -> bool {
+    wildcard(
+        pattern,
+        path,
+        |p| p == "**",
+        |p, name| {
+            let p: Vec<char> = p.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_component(&p, &name)
+        },
+    )
+}
+
+fn components(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Paths matching a glob pattern, sorted. Only the part of the pattern after
+/// its last plain directory is searched, and it not existing means no
+/// matches. Fails on directories below it that can't be read.
+fn glob(pattern: &str) -> walkdir::Result<Vec<PathBuf>> {
+    let mut root = PathBuf::new();
+    let mut rest = vec![];
+    for component in Path::new(pattern).components() {
+        let text = component.as_os_str().to_string_lossy();
+        if rest.is_empty() && !text.contains(['*', '?']) {
+            root.push(component);
+        } else {
+            rest.push(text.to_string());
+        }
+    }
+    if rest.is_empty() {
+        return Ok(if root.exists() { vec![root] } else { vec![] });
+    }
+
+    let search = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root.as_path()
+    };
+    if !search.is_dir() {
+        return Ok(vec![]);
+    }
+    // Without `**` the pattern says how deep matches can be
+    let depth = if rest.iter().any(|c| c == "**") {
+        usize::MAX
+    } else {
+        rest.len()
+    };
+    let mut found = vec![];
+    for entry in WalkDir::new(search).min_depth(1).max_depth(depth) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(search).unwrap_or(entry.path());
+        if matches_path(&rest, &components(relative)) {
+            found.push(root.join(relative));
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
 #[derive(Clone, Debug)]
 pub struct BuildDir;
 impl ReiCallable for BuildDir {
@@ -28,41 +238,13 @@ impl ReiCallable for BuildDir {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let path = match arguments.get(0) {
-            Some(Object::Str(n)) => n,
-            _ => {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "expected Str".to_string(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
-        };
-        let is_recursive = match arguments.get(1) {
-            Some(Object::Bool(n)) => n,
-            _ => {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "expected Bool".to_string(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
-        };
-
-        if *is_recursive {
-            if let Err(e) = fs::DirBuilder::new().recursive(true).create(path) {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: format!("Failed to create directory: {}", e),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
-        } else {
-            if let Err(e) = fs::DirBuilder::new().create(path) {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: format!("Failed to create directory: {}", e),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
-        }
+        let path = str_arg(arguments, 0, &context)?;
+        let is_recursive = bool_arg(arguments, 1, &context)?;
 
+        fs::DirBuilder::new()
+            .recursive(is_recursive)
+            .create(path)
+            .map_err(|e| fs_error("create directory", path, e, context))?;
         Ok(Object::Null)
     }
 
@@ -88,24 +270,16 @@ impl ReiCallable for ReadDir {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let path = match arguments.get(0) {
-            Some(Object::Str(n)) => n,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "expected Str".to_string(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type, context,
-                )));
-            }
-        };
-
-        let entries = fs::read_dir(path)?
-            .map(|res| res.map(|e| Object::Str(e.path().to_string_lossy().to_string())))
-            .collect::<Result<Vec<_>, io::Error>>()?;
+        let path = str_arg(arguments, 0, &context)?;
 
-        let vec = Rc::new(RefCell::new(entries));
-        Ok(Object::Vec(vec))
+        let mut entries = fs::read_dir(path)
+            .and_then(|dir| {
+                dir.map(|e| e.map(|e| e.path()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|e| fs_error("read directory", path, e, context))?;
+        entries.sort();
+        Ok(paths_object(entries))
     }
 
     fn to_string(&self) -> String {
@@ -130,28 +304,519 @@ impl ReiCallable for CreateFile {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let path = match arguments.get(0) {
-            Some(Object::Str(n)) => n,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "expected Str".to_string(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type, context,
-                )));
-            }
+        let path = str_arg(arguments, 0, &context)?;
+
+        fs::File::create(path).map_err(|e| fs_error("create", path, e, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_create_file".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadText;
+impl ReiCallable for ReadText {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+
+        let text = fs::read_to_string(path).map_err(|e| fs_error("read", path, e, context))?;
+        Ok(Object::Str(text))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_read_text".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WriteText;
+impl ReiCallable for WriteText {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let text = str_arg(arguments, 1, &context)?;
+
+        fs::write(path, text).map_err(|e| fs_error("write", path, e, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_write_text".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AppendText;
+impl ReiCallable for AppendText {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let text = str_arg(arguments, 1, &context)?;
+
+        append(path, text.as_bytes()).map_err(|e| fs_error("append to", path, e, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_append_text".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadBytes;
+impl ReiCallable for ReadBytes {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+
+        let bytes = fs::read(path).map_err(|e| fs_error("read", path, e, context))?;
+        Ok(Object::Bytes(Rc::new(RefCell::new(bytes))))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_read_bytes".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WriteBytes;
+impl ReiCallable for WriteBytes {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let bytes = bytes_arg(arguments, 1, &context)?;
+
+        fs::write(path, bytes).map_err(|e| fs_error("write", path, e, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_write_bytes".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AppendBytes;
+impl ReiCallable for AppendBytes {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let bytes = bytes_arg(arguments, 1, &context)?;
+
+        append(path, &bytes).map_err(|e| fs_error("append to", path, e, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_append_bytes".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Exists;
+impl ReiCallable for Exists {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        Ok(Object::Bool(Path::new(path).exists()))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_exists".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// size(path) -> number of bytes
+#[derive(Clone, Debug)]
+pub struct Size;
+impl ReiCallable for Size {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        Ok(Object::Number(metadata(path, context)?.len() as f64))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_size".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// modified(path) -> seconds since the Unix epoch
+#[derive(Clone, Debug)]
+pub struct Modified;
+impl ReiCallable for Modified {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let modified = metadata(path, context.clone())?
+            .modified()
+            .map_err(|e| fs_error("read modification time of", path, e, context))?;
+
+        let seconds = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        Ok(Object::Number(seconds))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_modified".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IsDir;
+impl ReiCallable for IsDir {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        Ok(Object::Bool(metadata(path, context)?.is_dir()))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_is_dir".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// remove(path, recursive) -> null. Directories need `recursive` unless empty.
+#[derive(Clone, Debug)]
+pub struct Remove;
+impl ReiCallable for Remove {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+        let is_recursive = bool_arg(arguments, 1, &context)?;
+
+        let removed = match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_dir() && is_recursive => fs::remove_dir_all(path),
+            Ok(meta) if meta.is_dir() => fs::remove_dir(path),
+            Ok(_) => fs::remove_file(path),
+            Err(e) => Err(e),
         };
+        removed.map_err(|e| fs_error("remove", path, e, context))?;
+        Ok(Object::Null)
+    }
 
-        let entries = fs::read_dir(path)?
-            .map(|res| res.map(|e| Object::Str(e.path().to_string_lossy().to_string())))
-            .collect::<Result<Vec<_>, io::Error>>()?;
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_remove".to_string()
+    }
 
-        let vec = Rc::new(RefCell::new(entries));
-        Ok(Object::Vec(vec))
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Rename;
+impl ReiCallable for Rename {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let from = str_arg(arguments, 0, &context)?;
+        let to = str_arg(arguments, 1, &context)?;
+
+        fs::rename(from, to).map_err(|e| fs_error("rename", from, e, context))?;
+        Ok(Object::Null)
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>_Fs_create_file".to_string()
+        "<native_fn>_Fs_rename".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// copy(from, to) -> number of bytes copied
+#[derive(Clone, Debug)]
+pub struct CopyFile;
+impl ReiCallable for CopyFile {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let from = str_arg(arguments, 0, &context)?;
+        let to = str_arg(arguments, 1, &context)?;
+
+        let copied = fs::copy(from, to).map_err(|e| fs_error("copy", from, e, context))?;
+        Ok(Object::Number(copied as f64))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_copy".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// walk(path) -> every file and directory below `path`, sorted
+#[derive(Clone, Debug)]
+pub struct Walk;
+impl ReiCallable for Walk {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = str_arg(arguments, 0, &context)?;
+
+        let entries = WalkDir::new(path)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .map(|entry| entry.map(|e| e.into_path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| fs_error("walk", path, e, context))?;
+        Ok(paths_object(entries))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_walk".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// glob(pattern) -> matching paths, sorted. Supports `*`, `?` and `**`.
+#[derive(Clone, Debug)]
+pub struct Glob;
+impl ReiCallable for Glob {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let pattern = str_arg(arguments, 0, &context)?;
+        let found = glob(pattern).map_err(|e| fs_error("glob", pattern, e, context))?;
+        Ok(paths_object(found))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_glob".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// temp_file() -> path of a new empty file in the system's temp directory
+#[derive(Clone, Debug)]
+pub struct TempFile;
+impl ReiCallable for TempFile {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = temp_path();
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| fs_error("create", &path.to_string_lossy(), e, context))?;
+        Ok(Object::Str(path.to_string_lossy().to_string()))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_temp_file".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// temp_dir() -> path of a new empty directory in the system's temp directory
+#[derive(Clone, Debug)]
+pub struct TempDir;
+impl ReiCallable for TempDir {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = temp_path();
+        fs::create_dir(&path)
+            .map_err(|e| fs_error("create directory", &path.to_string_lossy(), e, context))?;
+        Ok(Object::Str(path.to_string_lossy().to_string()))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Fs_temp_dir".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -168,6 +833,54 @@ pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
         "_Fs_read_dir".to_string(),
         Object::Callable(Rc::new(ReadDir)),
     )?;
+    env.define(
+        "_Fs_create_file".to_string(),
+        Object::Callable(Rc::new(CreateFile)),
+    )?;
+    env.define(
+        "_Fs_read_text".to_string(),
+        Object::Callable(Rc::new(ReadText)),
+    )?;
+    env.define(
+        "_Fs_write_text".to_string(),
+        Object::Callable(Rc::new(WriteText)),
+    )?;
+    env.define(
+        "_Fs_append_text".to_string(),
+        Object::Callable(Rc::new(AppendText)),
+    )?;
+    env.define(
+        "_Fs_read_bytes".to_string(),
+        Object::Callable(Rc::new(ReadBytes)),
+    )?;
+    env.define(
+        "_Fs_write_bytes".to_string(),
+        Object::Callable(Rc::new(WriteBytes)),
+    )?;
+    env.define(
+        "_Fs_append_bytes".to_string(),
+        Object::Callable(Rc::new(AppendBytes)),
+    )?;
+    env.define("_Fs_exists".to_string(), Object::Callable(Rc::new(Exists)))?;
+    env.define("_Fs_size".to_string(), Object::Callable(Rc::new(Size)))?;
+    env.define(
+        "_Fs_modified".to_string(),
+        Object::Callable(Rc::new(Modified)),
+    )?;
+    env.define("_Fs_is_dir".to_string(), Object::Callable(Rc::new(IsDir)))?;
+    env.define("_Fs_remove".to_string(), Object::Callable(Rc::new(Remove)))?;
+    env.define("_Fs_rename".to_string(), Object::Callable(Rc::new(Rename)))?;
+    env.define("_Fs_copy".to_string(), Object::Callable(Rc::new(CopyFile)))?;
+    env.define("_Fs_walk".to_string(), Object::Callable(Rc::new(Walk)))?;
+    env.define("_Fs_glob".to_string(), Object::Callable(Rc::new(Glob)))?;
+    env.define(
+        "_Fs_temp_file".to_string(),
+        Object::Callable(Rc::new(TempFile)),
+    )?;
+    env.define(
+        "_Fs_temp_dir".to_string(),
+        Object::Callable(Rc::new(TempDir)),
+    )?;
 
     Ok(())
 }
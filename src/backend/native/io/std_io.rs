@@ -1,9 +1,11 @@
 use std::any::Any;
 use std::cell::RefCell;
+use std::fs;
 use std::io::{self, Write};
 use std::rc::Rc;
 
 use crate::backend::environment::Environment;
+use crate::backend::event_loop::ReiTask;
 use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use crate::backend::exec_signal::ExecSignal;
 use crate::backend::interpreter::Interpreter;
@@ -93,6 +95,52 @@ impl ReiCallable for Read {
     }
 }
 
+/// read_file(path) -> task resolving to the file's contents
+#[derive(Clone, Debug)]
+pub struct ReadFile;
+impl ReiCallable for ReadFile {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let path = match &arguments[0] {
+            Object::Str(path) => path.clone(),
+            _ => {
+                let err_type = RuntimeErrorType::ErrorInNativeFn {
+                    msg: "Expected string path as argument to <read_file>".to_string(),
+                };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type, context,
+                )));
+            }
+        };
+
+        let job = ReiTask::job(
+            "read_file",
+            move || match fs::read_to_string(&path) {
+                Ok(contents) => Ok(Box::new(move || Object::Str(contents))),
+                Err(e) => Err(format!("Failed to read '{}': {}", path, e)),
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>read_file".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     let read_line: Rc<dyn ReiCallable> = Rc::new(ReadLine);
     env.define("_IO_read_line".to_string(), Object::Callable(read_line))?;
@@ -100,5 +148,8 @@ pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     let read: Rc<dyn ReiCallable> = Rc::new(Read);
     env.define("_IO_read".to_string(), Object::Callable(read))?;
 
+    let read_file: Rc<dyn ReiCallable> = Rc::new(ReadFile);
+    env.define("_IO_read_file".to_string(), Object::Callable(read_file))?;
+
     Ok(())
 }
@@ -0,0 +1,186 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde_json::{Map, Number, Value};
+
+use crate::backend::environment::Environment;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::http::record;
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::ExecContext;
+use crate::crux::token::Object;
+
+/// Deepest nesting `from_object` follows, a Vec holding itself would
+/// otherwise never end.
+const MAX_DEPTH: usize = 128;
+
+/// Parses JSON text, a syntax error reads "Invalid JSON at line:col: ...".
+pub fn parse(text: &[u8], context: Rc<RefCell<ExecContext>>) -> Result<Object, String> {
+    match serde_json::from_slice(text) {
+        Ok(value) => Ok(to_object(value, context)),
+        Err(e) => {
+            // serde_json ends its message with the position, put it first
+            let msg = e.to_string();
+            let msg = msg.split(" at line ").next().unwrap_or_default();
+            Err(format!(
+                "Invalid JSON at {}:{}: {}",
+                e.line(),
+                e.column(),
+                msg
+            ))
+        }
+    }
+}
+
+/// Object a JSON value maps to: objects become `Object` instances with a
+/// field per key, arrays Vecs.
+pub fn to_object(value: Value, context: Rc<RefCell<ExecContext>>) -> Object {
+    match value {
+        Value::Null => Object::Null,
+        Value::Bool(b) => Object::Bool(b),
+        Value::Number(n) => Object::Number(n.as_f64().unwrap_or(f64::NAN)),
+        Value::String(s) => Object::Str(s),
+        Value::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| to_object(item, context.clone()))
+                .collect();
+            Object::Vec(Rc::new(RefCell::new(items)))
+        }
+        Value::Object(map) => {
+            let fields = map
+                .into_iter()
+                .map(|(key, value)| (key, to_object(value, context.clone())))
+                .collect();
+            record("Object", fields, context)
+        }
+    }
+}
+
+/// JSON value of an object: Vecs and Bytes become arrays, instances
+/// objects of their fields.
+pub fn from_object(object: &Object) -> Result<Value, String> {
+    json_value(object, 0)
+}
+
+fn json_value(object: &Object, depth: usize) -> Result<Value, String> {
+    if depth > MAX_DEPTH {
+        return Err("Value is nested too deep to serialize".into());
+    }
+    let value = match object {
+        Object::Null => Value::Null,
+        Object::Bool(b) => Value::Bool(*b),
+        Object::Number(n) => number(*n)?,
+        Object::Str(s) => Value::String(s.clone()),
+        Object::Vec(items) => Value::Array(
+            items
+                .borrow()
+                .iter()
+                .map(|item| json_value(item, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Bytes(bytes) => Value::Array(bytes.borrow().iter().map(|b| (*b).into()).collect()),
+        Object::Instance(instance) => {
+            let instance = instance.borrow();
+            let mut map = Map::new();
+            for (key, value) in instance.fields.borrow().iter() {
+                map.insert(key.clone(), json_value(value, depth + 1)?);
+            }
+            Value::Object(map)
+        }
+        other => return Err(format!("Can't serialize {} to JSON", other)),
+    };
+    Ok(value)
+}
+
+/// Whole numbers are written without a fraction, `1` rather than `1.0`.
+fn number(n: f64) -> Result<Value, String> {
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        return Ok(Value::Number((n as i64).into()));
+    }
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| format!("Can't serialize {} to JSON", n))
+}
+
+/// Bad input rather than a bug in the script, so `do`/`fail` can catch it.
+fn json_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+/// parse(text: string) -> value
+#[derive(Clone, Debug)]
+pub struct Parse;
+impl ReiCallable for Parse {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let text = args[0].as_str().map_err(|_| {
+            let err = RuntimeErrorType::ErrorInNativeFn {
+                msg: "Expected string to parse".into(),
+            };
+            ExecSignal::RuntimeError(RuntimeError::new(err, context.clone()))
+        })?;
+        parse(text.as_bytes(), context.clone()).map_err(|msg| json_error(msg, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>parse".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// stringify(value, pretty: bool) -> string
+#[derive(Clone, Debug)]
+pub struct Stringify;
+impl ReiCallable for Stringify {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let value = from_object(&args[0]).map_err(|msg| json_error(msg, context.clone()))?;
+        let text = match args[1] {
+            Object::Bool(true) => serde_json::to_string_pretty(&value),
+            _ => serde_json::to_string(&value),
+        };
+        text.map(Object::Str)
+            .map_err(|e| json_error(e.to_string(), context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>stringify".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define("_JSON_parse".into(), Object::Callable(Rc::new(Parse)))?;
+    env.define(
+        "_JSON_stringify".into(),
+        Object::Callable(Rc::new(Stringify)),
+    )?;
+    Ok(())
+}
@@ -1,4 +1,3 @@
-use std::alloc::{alloc, dealloc, Layout};
 use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -8,15 +7,314 @@ use crate::backend::exec_signal::runtime_error::RuntimeErrorType;
 use crate::backend::exec_signal::{runtime_error::RuntimeError, ExecSignal};
 use crate::backend::interpreter::Interpreter;
 use crate::backend::rei_callable::ReiCallable;
+use crate::backend::rei_generator::ReiGenerator;
 use crate::backend::rei_instance::ReiInstance;
 use crate::backend::stack_trace::ExecContext;
 
 use crate::crux::token::Object;
 use crate::crux::token::Token;
 
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn native_error(msg: String, context: &Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err_type = RuntimeErrorType::ErrorInNativeFn { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
+}
+
+/// Malformed input data or too little memory, which `do/fail` can catch.
+fn decode_error(msg: String, context: &Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err_type = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
+}
+
+fn bytes_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<RefCell<Vec<u8>>>, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Bytes(bytes)) => Ok(bytes.clone()),
+        _ => Err(native_error(
+            format!("Expected Bytes as argument {}", index + 1),
+            context,
+        )),
+    }
+}
+
+fn number_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<f64, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Number(n)) => Ok(*n),
+        _ => Err(native_error(
+            format!("Expected Number as argument {}", index + 1),
+            context,
+        )),
+    }
+}
+
+/// Offsets and lengths have to be whole, non-negative numbers.
+fn index_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<usize, ExecSignal> {
+    let n = number_arg(arguments, index, context)?;
+    if n < 0.0 || n.fract() != 0.0 || n >= usize::MAX as f64 {
+        return Err(native_error(
+            format!("Expected a whole, non-negative number, got {}", n),
+            context,
+        ));
+    }
+    Ok(n as usize)
+}
+
+fn str_arg<'a>(
+    arguments: &'a [Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<&'a str, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Str(s)) => Ok(s),
+        _ => Err(native_error(
+            format!("Expected Str as argument {}", index + 1),
+            context,
+        )),
+    }
+}
+
+fn bool_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<bool, ExecSignal> {
+    match arguments.get(index) {
+        Some(Object::Bool(b)) => Ok(*b),
+        _ => Err(native_error(
+            format!("Expected Bool as argument {}", index + 1),
+            context,
+        )),
+    }
+}
+
+fn bytes_object(bytes: Vec<u8>) -> Object {
+    Object::Bytes(Rc::new(RefCell::new(bytes)))
+}
+
+fn check_range(
+    start: usize,
+    len: usize,
+    size: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<(), ExecSignal> {
+    if start.checked_add(len).is_none_or(|end| end > size) {
+        return Err(native_error(
+            format!(
+                "Range {}..{} out of bounds for {} bytes",
+                start,
+                start.saturating_add(len),
+                size
+            ),
+            context,
+        ));
+    }
+    Ok(())
+}
+
+/// Number types that can be read from and written to a buffer.
+#[derive(Clone, Copy, Debug)]
+enum NumType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl NumType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => NumType::U8,
+            "u16" => NumType::U16,
+            "u32" => NumType::U32,
+            "u64" => NumType::U64,
+            "i8" => NumType::I8,
+            "i16" => NumType::I16,
+            "i32" => NumType::I32,
+            "i64" => NumType::I64,
+            "f32" => NumType::F32,
+            "f64" => NumType::F64,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            NumType::U8 => "u8",
+            NumType::U16 => "u16",
+            NumType::U32 => "u32",
+            NumType::U64 => "u64",
+            NumType::I8 => "i8",
+            NumType::I16 => "i16",
+            NumType::I32 => "i32",
+            NumType::I64 => "i64",
+            NumType::F32 => "f32",
+            NumType::F64 => "f64",
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            NumType::U8 | NumType::I8 => 1,
+            NumType::U16 | NumType::I16 => 2,
+            NumType::U32 | NumType::I32 | NumType::F32 => 4,
+            NumType::U64 | NumType::I64 | NumType::F64 => 8,
+        }
+    }
+
+    /// Smallest value of an integer type and the first one past its
+    /// largest, both exact as numbers. Past 2^53 numbers lose precision,
+    /// like any other number in REI.
+    fn bounds(self) -> Option<(f64, f64)> {
+        let bits = (self.size() * 8) as i32;
+        match self {
+            NumType::U8 | NumType::U16 | NumType::U32 | NumType::U64 => {
+                Some((0.0, 2f64.powi(bits)))
+            }
+            NumType::I8 | NumType::I16 | NumType::I32 | NumType::I64 => {
+                Some((-2f64.powi(bits - 1), 2f64.powi(bits - 1)))
+            }
+            NumType::F32 | NumType::F64 => None,
+        }
+    }
+
+    /// `bytes` is exactly `size()` long, in big-endian order.
+    fn decode(self, bytes: &[u8]) -> f64 {
+        let mut wide = [0u8; 8];
+        wide[8 - bytes.len()..].copy_from_slice(bytes);
+        let raw = u64::from_be_bytes(wide);
+        match self {
+            NumType::U8 | NumType::U16 | NumType::U32 | NumType::U64 => raw as f64,
+            NumType::I8 => raw as u8 as i8 as f64,
+            NumType::I16 => raw as u16 as i16 as f64,
+            NumType::I32 => raw as u32 as i32 as f64,
+            NumType::I64 => raw as i64 as f64,
+            NumType::F32 => f32::from_bits(raw as u32) as f64,
+            NumType::F64 => f64::from_bits(raw),
+        }
+    }
+
+    /// Big-endian bytes of `value`, which must fit the type.
+    fn encode(self, value: f64) -> Result<Vec<u8>, String> {
+        if let Some((min, end)) = self.bounds() {
+            if value.fract() != 0.0 || value < min || value >= end {
+                return Err(format!("{} doesn't fit in {}", value, self.name()));
+            }
+        }
+
+        let raw = match self {
+            NumType::U8 | NumType::U16 | NumType::U32 | NumType::U64 => value as u64,
+            NumType::I8 | NumType::I16 | NumType::I32 | NumType::I64 => value as i64 as u64,
+            NumType::F32 => (value as f32).to_bits() as u64,
+            NumType::F64 => value.to_bits(),
+        };
+        Ok(raw.to_be_bytes()[8 - self.size()..].to_vec())
+    }
+}
+
+fn num_type_arg(
+    arguments: &[Object],
+    index: usize,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<NumType, ExecSignal> {
+    let name = str_arg(arguments, index, context)?;
+    NumType::parse(name).ok_or_else(|| {
+        native_error(
+            format!(
+                "Unknown number type '{}', expected u8..u64, i8..i64, f32 or f64",
+                name
+            ),
+            context,
+        )
+    })
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Padded base64, as `to_base64` writes it.
+fn from_base64(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(4) {
+        return Err("Base64 text comes in groups of four characters".to_string());
+    }
+    let data = text.trim_end_matches('=');
+    if text.len() - data.len() > 2 {
+        return Err("Invalid base64 padding".to_string());
+    }
+    let text = data;
+    let mut decoded = vec![];
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| format!("Invalid base64 character '{}'", c as char))?;
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((acc >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err("Hex text needs two digits per byte".to_string());
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            // from_str_radix would take a sign too
+            text.get(i..i + 2)
+                .filter(|pair| pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("Invalid hex digits at {}", i))
+        })
+        .collect()
+}
+
+/// alloc(size) -> Bytes of `size` zeroes, an error when there isn't
+/// memory for them
 #[derive(Clone, Debug)]
-pub struct ReiMalloc;
-impl ReiCallable for ReiMalloc {
+pub struct ReiAlloc;
+impl ReiCallable for ReiAlloc {
     fn arity(&self) -> usize {
         1
     }
@@ -27,43 +325,44 @@ impl ReiCallable for ReiMalloc {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let size = match arguments.get(0) {
-            Some(Object::Number(n)) => *n as usize,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected a Number".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+        let size = index_arg(arguments, 0, &context)?;
+        let mut bytes = vec![];
+        bytes
+            .try_reserve_exact(size)
+            .map_err(|_| decode_error(format!("Can't allocate {} bytes", size), &context))?;
+        bytes.resize(size, 0);
+        Ok(bytes_object(bytes))
+    }
 
-        let layout = Layout::from_size_align(size, 8).map_err(|e| {
-            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("invalid layout: {}", e),
-            };
-            ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
-        })?;
+    fn to_string(&self) -> String {
+        "<native_fn>_M_alloc".to_string()
+    }
 
-        unsafe {
-            let ptr = alloc(layout);
-            if ptr.is_null() {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Allocation failed".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-            Ok(Object::MBlock(ptr, size))
-        }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReiLen;
+impl ReiCallable for ReiLen {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let len = bytes.borrow().len();
+        Ok(Object::Number(len as f64))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>_M_alloc".to_string()
+        "<native_fn>_M_len".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -71,6 +370,7 @@ impl ReiCallable for ReiMalloc {
     }
 }
 
+/// read(bytes, offset, type, little_endian) -> number
 #[derive(Clone, Debug)]
 pub struct ReiRead;
 impl ReiCallable for ReiRead {
@@ -84,130 +384,102 @@ impl ReiCallable for ReiRead {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let (ptr, size) = match arguments.get(0) {
-            Some(Object::MBlock(p, s)) => (*p, *s),
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected MBlock as first arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let offset = index_arg(arguments, 1, &context)?;
+        let num_type = num_type_arg(arguments, 2, &context)?;
+        let little_endian = bool_arg(arguments, 3, &context)?;
+
+        let bytes = bytes.borrow();
+        check_range(offset, num_type.size(), bytes.len(), &context)?;
+        let mut field = bytes[offset..offset + num_type.size()].to_vec();
+        if little_endian {
+            field.reverse();
+        }
+        Ok(Object::Number(num_type.decode(&field)))
+    }
 
-        let offset = match arguments.get(1) {
-            Some(Object::Number(n)) => *n as usize,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected number offset as second arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+    fn to_string(&self) -> String {
+        "<native_fn>_M_read".to_string()
+    }
 
-        let length = match arguments.get(2) {
-            Some(Object::Number(n)) => *n as usize,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected Number as third arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-        let mode = match arguments.get(3) {
-            Some(Object::Bool(n)) => n,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected a Bool as fourth arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+/// write(bytes, offset, type, value, little_endian) -> null
+#[derive(Clone, Debug)]
+pub struct ReiWrite;
+impl ReiCallable for ReiWrite {
+    fn arity(&self) -> usize {
+        5
+    }
 
-        if offset + length > size {
-            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("Offset {} out of bounds for size {}", offset + length, size),
-            };
-            return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                err_type,
-                context.clone(),
-            )));
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let offset = index_arg(arguments, 1, &context)?;
+        let num_type = num_type_arg(arguments, 2, &context)?;
+        let value = number_arg(arguments, 3, &context)?;
+        let little_endian = bool_arg(arguments, 4, &context)?;
+
+        let mut field = num_type
+            .encode(value)
+            .map_err(|msg| native_error(msg, &context))?;
+        if little_endian {
+            field.reverse();
         }
 
-        let ptr = ptr as *const u8;
-        unsafe {
-            let slice = std::slice::from_raw_parts(ptr.add(offset), length);
-            let val = if *mode {
-                match length {
-                    1 => Object::Number(slice[0] as f64),
-                    8 => {
-                        if (slice.as_ptr() as usize) % std::mem::align_of::<f64>() != 0 {
-                            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                                msg: "Unaligned f64 read".to_string(),
-                            };
-                            return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                                err_type,
-                                context.clone(),
-                            )));
-                        }
-                        let num = *(slice.as_ptr() as *const f64);
-                        Object::Number(num)
-                    }
-                    _ => {
-                        let repr = slice
-                            .iter()
-                            .map(|b| format!("0x{:02X}", b))
-                            .collect::<Vec<_>>()
-                            .join(" ");
-                        let info = format!(
-                            "mem[{:p}+{}..+{}]: [{}]",
-                            ptr,
-                            offset,
-                            offset + length,
-                            repr
-                        );
-                        Object::Str(info)
-                    }
-                }
-            } else {
-                match std::str::from_utf8(slice) {
-                    Ok(s) => Object::Str(s.to_string()),
-                    Err(_) => {
-                        let repr = slice
-                            .iter()
-                            .map(|b| format!("0x{:02X}", b))
-                            .collect::<Vec<_>>()
-                            .join(" ");
-                        let info = format!(
-                            "mem[{:p}+{}..+{}]: [{}]",
-                            ptr,
-                            offset,
-                            offset + length,
-                            repr
-                        );
-                        Object::Str(info)
-                    }
-                }
-            };
+        let mut bytes = bytes.borrow_mut();
+        check_range(offset, field.len(), bytes.len(), &context)?;
+        bytes[offset..offset + field.len()].copy_from_slice(&field);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_M_write".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// slice(bytes, start, end) -> new Bytes with a copy of start..end
+#[derive(Clone, Debug)]
+pub struct ReiSlice;
+impl ReiCallable for ReiSlice {
+    fn arity(&self) -> usize {
+        3
+    }
 
-            Ok(val)
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let start = index_arg(arguments, 1, &context)?;
+        let end = index_arg(arguments, 2, &context)?;
+
+        if end < start {
+            return Err(native_error(
+                format!("Slice end {} is before its start {}", end, start),
+                &context,
+            ));
         }
+        let bytes = bytes.borrow();
+        check_range(start, end - start, bytes.len(), &context)?;
+        Ok(bytes_object(bytes[start..end].to_vec()))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>_M_read".to_string()
+        "<native_fn>_M_slice".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -215,11 +487,12 @@ impl ReiCallable for ReiRead {
     }
 }
 
+/// concat(a, b) -> new Bytes with a followed by b
 #[derive(Clone, Debug)]
-pub struct ReiWrite;
-impl ReiCallable for ReiWrite {
+pub struct ReiConcat;
+impl ReiCallable for ReiConcat {
     fn arity(&self) -> usize {
-        3
+        2
     }
 
     fn call(
@@ -228,106 +501,42 @@ impl ReiCallable for ReiWrite {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let (ptr, size) = match arguments.get(0) {
-            Some(Object::MBlock(p, s)) => (*p, *s),
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected MBlock as first arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+        let first = bytes_arg(arguments, 0, &context)?;
+        let second = bytes_arg(arguments, 1, &context)?;
 
-        let offset = match arguments.get(1) {
-            Some(Object::Number(n)) => *n as usize,
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected Number as second arg".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+        let mut joined = first.borrow().clone();
+        joined.extend_from_slice(&second.borrow());
+        Ok(bytes_object(joined))
+    }
 
-        let value = arguments.get(2).ok_or_else(|| {
-            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                msg: "Missing third argument".into(),
-            };
-            ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
-        })?;
+    fn to_string(&self) -> String {
+        "<native_fn>_M_concat".to_string()
+    }
 
-        if offset >= size {
-            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("Offset {} out of bounds for size {}", offset, size),
-            };
-            return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                err_type,
-                context.clone(),
-            )));
-        }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-        unsafe {
-            match value {
-                Object::Number(n) => {
-                    if offset + 8 > size {
-                        let err_type = RuntimeErrorType::ErrorInNativeFn {
-                            msg: format!("offset {}+8 out of bounds", offset),
-                        };
-                        return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                            err_type,
-                            context.clone(),
-                        )));
-                    }
-                    let ptr = ptr.add(offset) as *mut f64;
-                    *ptr = *n;
-                }
-                Object::Bool(b) => {
-                    if offset >= size {
-                        let err_type = RuntimeErrorType::ErrorInNativeFn {
-                            msg: format!("write: offset {} out of bounds", offset),
-                        };
-                        return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                            err_type,
-                            context.clone(),
-                        )));
-                    }
-                    *ptr.add(offset) = *b as u8;
-                }
-                Object::Str(s) => {
-                    let bytes = s.as_bytes();
-                    if offset + bytes.len() > size {
-                        let err_type = RuntimeErrorType::ErrorInNativeFn {
-                            msg: format!("string too long to fit at offset {}", offset),
-                        };
-                        return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                            err_type,
-                            context.clone(),
-                        )));
-                    }
-                    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.add(offset), bytes.len());
-                }
-                _ => {
-                    let err_type = RuntimeErrorType::ErrorInNativeFn {
-                        msg: "unsupported type for write".to_string(),
-                    };
-                    return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                        err_type,
-                        context.clone(),
-                    )));
-                }
-            }
-        }
+#[derive(Clone, Debug)]
+pub struct ReiFromStr;
+impl ReiCallable for ReiFromStr {
+    fn arity(&self) -> usize {
+        1
+    }
 
-        Ok(Object::Null)
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let text = str_arg(arguments, 0, &context)?;
+        Ok(bytes_object(text.as_bytes().to_vec()))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>_M_write".to_string()
+        "<native_fn>_M_from_str".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -335,9 +544,10 @@ impl ReiCallable for ReiWrite {
     }
 }
 
+/// to_str(bytes) -> the bytes read as UTF-8 text
 #[derive(Clone, Debug)]
-pub struct ReiFree;
-impl ReiCallable for ReiFree {
+pub struct ReiToStr;
+impl ReiCallable for ReiToStr {
     fn arity(&self) -> usize {
         1
     }
@@ -348,35 +558,126 @@ impl ReiCallable for ReiFree {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let (ptr, size) = match arguments.get(0) {
-            Some(Object::MBlock(p, s)) => (*p, *s),
-            _ => {
-                let err_type = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "expected a MBlock when freeing memory".to_string(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    err_type,
-                    context.clone(),
-                )));
-            }
-        };
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let text = String::from_utf8(bytes.borrow().clone())
+            .map_err(|e| decode_error(format!("Bytes aren't valid UTF-8: {}", e), &context))?;
+        Ok(Object::Str(text))
+    }
 
-        let layout = Layout::from_size_align(size, 8).map_err(|e| {
-            let err_type = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("invalid layout: {}", e),
-            };
-            ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
-        })?;
+    fn to_string(&self) -> String {
+        "<native_fn>_M_to_str".to_string()
+    }
 
-        unsafe {
-            dealloc(ptr, layout);
-        }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-        Ok(Object::Null)
+#[derive(Clone, Debug)]
+pub struct ReiToHex;
+impl ReiCallable for ReiToHex {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let hex = bytes
+            .borrow()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok(Object::Str(hex))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>_M_free".to_string()
+        "<native_fn>_M_to_hex".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReiFromHex;
+impl ReiCallable for ReiFromHex {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let text = str_arg(arguments, 0, &context)?;
+        let bytes = from_hex(text).map_err(|msg| decode_error(msg, &context))?;
+        Ok(bytes_object(bytes))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_M_from_hex".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReiToBase64;
+impl ReiCallable for ReiToBase64 {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let bytes = bytes_arg(arguments, 0, &context)?;
+        let encoded = to_base64(&bytes.borrow());
+        Ok(Object::Str(encoded))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_M_to_base64".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReiFromBase64;
+impl ReiCallable for ReiFromBase64 {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let text = str_arg(arguments, 0, &context)?;
+        let bytes = from_base64(text).map_err(|msg| decode_error(msg, &context))?;
+        Ok(bytes_object(bytes))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_M_from_base64".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -397,7 +698,7 @@ impl ReiCallable for ReiSizeOf {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let obj = arguments.get(0).ok_or_else(|| {
+        let obj = arguments.first().ok_or_else(|| {
             let err_type = RuntimeErrorType::ErrorInNativeFn {
                 msg: "Expected one argument to _M_sizeof".to_string(),
             };
@@ -413,7 +714,9 @@ impl ReiCallable for ReiSizeOf {
             Object::Null => 0,
             Object::Callable(_) => std::mem::size_of::<Rc<dyn ReiCallable>>(),
             Object::Instance(_) => std::mem::size_of::<Rc<RefCell<ReiInstance>>>(),
-            Object::MBlock(_, size) => *size,
+            Object::Variant(v) => std::mem::size_of_val(&**v),
+            Object::Generator(_) => std::mem::size_of::<Rc<ReiGenerator>>(),
+            Object::Bytes(b) => b.borrow().len(),
             Object::Vec(v) => {
                 let object_size = std::mem::size_of::<Object>();
                 let total = object_size * v.borrow().len();
@@ -422,6 +725,12 @@ impl ReiCallable for ReiSizeOf {
             Object::Exception(e) => {
                 std::mem::size_of::<Box<RuntimeError<Token>>>() + std::mem::size_of_val(&**e)
             }
+            Object::Task(_)
+            | Object::Router(_)
+            | Object::Socket(_)
+            | Object::Child(_)
+            | Object::Thread(_)
+            | Object::Channel(_) => std::mem::size_of::<Object>(),
         };
 
         Ok(Object::Number(size as f64))
@@ -437,8 +746,11 @@ impl ReiCallable for ReiSizeOf {
 }
 
 pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
-    let malloc: Rc<dyn ReiCallable> = Rc::new(ReiMalloc);
-    env.define("_M_alloc".to_string(), Object::Callable(malloc))?;
+    let alloc: Rc<dyn ReiCallable> = Rc::new(ReiAlloc);
+    env.define("_M_alloc".to_string(), Object::Callable(alloc))?;
+
+    let len: Rc<dyn ReiCallable> = Rc::new(ReiLen);
+    env.define("_M_len".to_string(), Object::Callable(len))?;
 
     let write: Rc<dyn ReiCallable> = Rc::new(ReiWrite);
     env.define("_M_write".to_string(), Object::Callable(write))?;
@@ -446,8 +758,29 @@ pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     let read: Rc<dyn ReiCallable> = Rc::new(ReiRead);
     env.define("_M_read".to_string(), Object::Callable(read))?;
 
-    let free: Rc<dyn ReiCallable> = Rc::new(ReiFree);
-    env.define("_M_free".to_string(), Object::Callable(free))?;
+    let slice: Rc<dyn ReiCallable> = Rc::new(ReiSlice);
+    env.define("_M_slice".to_string(), Object::Callable(slice))?;
+
+    let concat: Rc<dyn ReiCallable> = Rc::new(ReiConcat);
+    env.define("_M_concat".to_string(), Object::Callable(concat))?;
+
+    let from_str: Rc<dyn ReiCallable> = Rc::new(ReiFromStr);
+    env.define("_M_from_str".to_string(), Object::Callable(from_str))?;
+
+    let to_str: Rc<dyn ReiCallable> = Rc::new(ReiToStr);
+    env.define("_M_to_str".to_string(), Object::Callable(to_str))?;
+
+    let to_hex: Rc<dyn ReiCallable> = Rc::new(ReiToHex);
+    env.define("_M_to_hex".to_string(), Object::Callable(to_hex))?;
+
+    let from_hex: Rc<dyn ReiCallable> = Rc::new(ReiFromHex);
+    env.define("_M_from_hex".to_string(), Object::Callable(from_hex))?;
+
+    let to_base64: Rc<dyn ReiCallable> = Rc::new(ReiToBase64);
+    env.define("_M_to_base64".to_string(), Object::Callable(to_base64))?;
+
+    let from_base64: Rc<dyn ReiCallable> = Rc::new(ReiFromBase64);
+    env.define("_M_from_base64".to_string(), Object::Callable(from_base64))?;
 
     let size_of: Rc<dyn ReiCallable> = Rc::new(ReiSizeOf);
     env.define("_M_size_of".to_string(), Object::Callable(size_of))?;
@@ -7,7 +7,10 @@ use crate::backend::environment::Environment;
 use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use crate::backend::exec_signal::ExecSignal;
 use crate::backend::interpreter::Interpreter;
+use crate::backend::native::thread::channel::Channel;
 use crate::backend::rei_callable::ReiCallable;
+use crate::backend::rei_class::ReiClass;
+use crate::backend::rei_enum::ReiVariant;
 use crate::backend::rei_instance::ReiInstance;
 use crate::backend::stack_trace::ExecContext;
 
@@ -26,57 +29,13 @@ impl ReiCallable for ReiClone {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let value = arguments.get(0).ok_or_else(|| {
+        let value = arguments.first().ok_or_else(|| {
             let err_type = RuntimeErrorType::ErrorInNativeFn {
                 msg: "Expected one argument".to_string(),
             };
             ExecSignal::RuntimeError(RuntimeError::new(err_type, context.clone()))
         })?;
 
-        fn deep_clone(obj: &Object, context: Rc<RefCell<ExecContext>>) -> Object {
-            match obj {
-                Object::Number(n) => Object::Number(*n),
-                Object::Str(s) => Object::Str(s.clone()),
-                Object::Bool(b) => Object::Bool(*b),
-                Object::Null => Object::Null,
-                Object::Dummy => Object::Dummy,
-                Object::Range(s, e) => Object::Range(*s, *e),
-
-                Object::Vec(vec_ref) => {
-                    let vec_borrow = vec_ref.borrow();
-                    let cloned_vec: Vec<Object> = vec_borrow
-                        .iter()
-                        .map(|o| deep_clone(o, context.clone()))
-                        .collect();
-                    Object::Vec(Rc::new(RefCell::new(cloned_vec)))
-                }
-
-                Object::Instance(inst_ref) => {
-                    let inst = inst_ref.borrow();
-                    let mut cloned_fields = HashMap::new();
-
-                    for (key, val) in inst.fields.borrow().iter() {
-                        cloned_fields.insert(key.clone(), deep_clone(val, context.clone()));
-                    }
-
-                    let new_inst = ReiInstance {
-                        class: inst.class.clone(),
-                        fields: Rc::new(RefCell::new(cloned_fields)),
-                        context,
-                    };
-                    Object::Instance(Rc::new(RefCell::new(new_inst)))
-                }
-
-                Object::Callable(c) => Object::Callable(c.clone()), // shallow copy
-                Object::MBlock(p, s) => Object::MBlock(*p, *s),     // risky af
-                Object::Exception(e) => {
-                    let cloned_error = Box::new((**e).clone()); // assumes RuntimeError<T> implements Clone
-                    Object::Exception(cloned_error)
-                }
-                _ => {}
-            }
-        }
-
         Ok(deep_clone(value, context))
     }
 
@@ -89,6 +48,202 @@ impl ReiCallable for ReiClone {
     }
 }
 
+/// Copies a value along with everything it holds, so changing the copy
+/// never changes the original. Functions stay shared.
+pub fn deep_clone(obj: &Object, context: Rc<RefCell<ExecContext>>) -> Object {
+    match obj {
+        Object::Number(n) => Object::Number(*n),
+        Object::Str(s) => Object::Str(s.clone()),
+        Object::Bool(b) => Object::Bool(*b),
+        Object::Null => Object::Null,
+        Object::Dummy => Object::Dummy,
+        Object::Range(s, e) => Object::Range(*s, *e),
+
+        Object::Vec(vec_ref) => {
+            let vec_borrow = vec_ref.borrow();
+            let cloned_vec: Vec<Object> = vec_borrow
+                .iter()
+                .map(|o| deep_clone(o, context.clone()))
+                .collect();
+            Object::Vec(Rc::new(RefCell::new(cloned_vec)))
+        }
+
+        Object::Instance(inst_ref) => {
+            let inst = inst_ref.borrow();
+            let mut cloned_fields = HashMap::new();
+
+            for (key, val) in inst.fields.borrow().iter() {
+                cloned_fields.insert(key.clone(), deep_clone(val, context.clone()));
+            }
+
+            let new_inst = ReiInstance {
+                class: inst.class.clone(),
+                fields: Rc::new(RefCell::new(cloned_fields)),
+                context,
+            };
+            Object::Instance(Rc::new(RefCell::new(new_inst)))
+        }
+
+        Object::Variant(variant) => {
+            let fields = variant
+                .fields
+                .iter()
+                .map(|(name, val)| (name.clone(), deep_clone(val, context.clone())))
+                .collect();
+            Object::Variant(Rc::new(ReiVariant {
+                fields,
+                ..(**variant).clone()
+            }))
+        }
+
+        Object::Callable(c) => Object::Callable(c.clone()), // shallow copy
+        Object::Bytes(b) => Object::Bytes(Rc::new(RefCell::new(b.borrow().clone()))),
+        Object::Exception(e) => {
+            let cloned_error = Box::new((**e).clone()); // assumes RuntimeError<T> implements Clone
+            Object::Exception(cloned_error)
+        }
+        // Generators, tasks, routers, sockets, threads and channels are
+        // shared, not copied
+        _ => obj.clone(),
+    }
+}
+
+/// A deep copy that owns everything it holds, so it can move to another
+/// thread. Classes are looked up by name again on the other side.
+#[derive(Debug)]
+pub enum Detached {
+    Number(f64),
+    Bool(bool),
+    Range(f64, f64),
+    Str(String),
+    Null,
+    Vec(Vec<Detached>),
+    Bytes(Vec<u8>),
+    Instance {
+        class: String,
+        fields: Vec<(String, Detached)>,
+    },
+    Variant {
+        enum_name: String,
+        name: String,
+        fields: Vec<(String, Detached)>,
+    },
+    /// Another end of the channel, for the receiving thread
+    Channel(Channel),
+}
+
+/// Same walk as `deep_clone`, for a value leaving its thread. Functions and
+/// anything tied to the interpreter can't be sent.
+pub fn detach(obj: &Object) -> Result<Detached, String> {
+    let detach_fields = |fields: &mut dyn Iterator<Item = (&String, &Object)>| {
+        fields
+            .map(|(name, val)| Ok((name.clone(), detach(val)?)))
+            .collect::<Result<Vec<_>, String>>()
+    };
+
+    Ok(match obj {
+        Object::Number(n) => Detached::Number(*n),
+        Object::Str(s) => Detached::Str(s.clone()),
+        Object::Bool(b) => Detached::Bool(*b),
+        Object::Null => Detached::Null,
+        Object::Range(s, e) => Detached::Range(*s, *e),
+        Object::Bytes(b) => Detached::Bytes(b.borrow().clone()),
+        Object::Vec(vec_ref) => Detached::Vec(
+            vec_ref
+                .borrow()
+                .iter()
+                .map(detach)
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Instance(inst_ref) => {
+            let inst = inst_ref.borrow();
+            let fields = detach_fields(&mut inst.fields.borrow().iter())?;
+            Detached::Instance {
+                class: inst.class.name.clone(),
+                fields,
+            }
+        }
+        Object::Variant(variant) => Detached::Variant {
+            enum_name: variant.enum_name.clone(),
+            name: variant.name.clone(),
+            fields: detach_fields(&mut variant.fields.iter().map(|(n, v)| (n, v)))?,
+        },
+        Object::Channel(channel) => Detached::Channel((**channel).clone()),
+        other => return Err(format!("'{}' can't be sent to another thread", other)),
+    })
+}
+
+impl Detached {
+    /// Rebuilds the value in `interpreter`, whose top level must declare the
+    /// classes of the instances it holds.
+    pub fn attach(
+        self,
+        interpreter: &Interpreter,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, String> {
+        let attach_fields = |fields: Vec<(String, Detached)>| {
+            fields
+                .into_iter()
+                .map(|(name, val)| Ok((name, val.attach(interpreter, context.clone())?)))
+                .collect::<Result<Vec<_>, String>>()
+        };
+
+        Ok(match self {
+            Detached::Number(n) => Object::Number(n),
+            Detached::Str(s) => Object::Str(s),
+            Detached::Bool(b) => Object::Bool(b),
+            Detached::Null => Object::Null,
+            Detached::Range(s, e) => Object::Range(s, e),
+            Detached::Bytes(b) => Object::Bytes(Rc::new(RefCell::new(b))),
+            Detached::Vec(items) => {
+                let items = items
+                    .into_iter()
+                    .map(|item| item.attach(interpreter, context.clone()))
+                    .collect::<Result<_, _>>()?;
+                Object::Vec(Rc::new(RefCell::new(items)))
+            }
+            Detached::Instance { class, fields } => {
+                let Some(class) = global_class(interpreter, &class) else {
+                    return Err(format!("Class '{}' isn't declared on this thread", class));
+                };
+                let instance = ReiInstance {
+                    class: Rc::new(class),
+                    fields: Rc::new(RefCell::new(attach_fields(fields)?.into_iter().collect())),
+                    context: context.clone(),
+                };
+                Object::Instance(Rc::new(RefCell::new(instance)))
+            }
+            Detached::Variant {
+                enum_name,
+                name,
+                fields,
+            } => Object::Variant(Rc::new(ReiVariant {
+                enum_name,
+                name,
+                fields: attach_fields(fields)?,
+            })),
+            Detached::Channel(channel) => Object::Channel(Rc::new(channel)),
+        })
+    }
+}
+
+fn global_class(interpreter: &Interpreter, name: &str) -> Option<ReiClass> {
+    let mut env = interpreter.environment.clone();
+    loop {
+        let enclosing = env.borrow().enclosing.clone();
+        match enclosing {
+            Some(outer) => env = outer,
+            None => break,
+        }
+    }
+
+    let value = env.borrow().values.get(name).cloned();
+    match value {
+        Some(Object::Callable(c)) => c.as_any().downcast_ref::<ReiClass>().cloned(),
+        _ => None,
+    }
+}
+
 pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     env.define("_Mi_clone".to_string(), Object::Callable(Rc::new(ReiClone)))?;
 
@@ -4,26 +4,36 @@ use crate::backend::environment::Environment;
 
 pub mod chrono;
 pub mod collections;
-// pub mod fs;
+pub mod fs;
 pub mod io;
+pub mod json;
 pub mod math;
-// pub mod memory;
-// pub mod misc;
+pub mod memory;
+pub mod misc;
 pub mod net;
 pub mod process;
+pub mod test;
+pub mod thread;
 
 pub fn register_all_native_fns(
     mut env: RefMut<Environment>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     chrono::clock::register(&mut *env)?;
-    // memory::mem::register(&mut *env)?;
+    chrono::datetime::register(&mut *env)?;
+    memory::mem::register(&mut *env)?;
     io::std_io::register(&mut *env)?;
     math::math::register(&mut *env)?;
-    // fs::file::register(&mut *env)?;
+    fs::file::register(&mut *env)?;
     collections::vec::register(&mut *env)?;
-    // misc::clone::register(&mut *env)?;
+    misc::clone::register(&mut *env)?;
     process::process::register(&mut *env)?;
     net::net::register(&mut *env)?;
+    net::client::register(&mut *env)?;
+    json::json::register(&mut *env)?;
+    net::server::register(&mut *env)?;
+    net::websocket::register(&mut *env)?;
+    test::assert::register(&mut env)?;
+    thread::thread::register(&mut env)?;
 
     Ok(())
 }
@@ -0,0 +1,253 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use reqwest::Method;
+
+use crate::backend::environment::Environment;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::json::json;
+use crate::backend::native::net::http::{headers_record, record};
+use crate::backend::native::net::net::native_error;
+use crate::backend::native::net::server::{field, str_field};
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::ExecContext;
+use crate::crux::token::Object;
+
+/// Failures of the request itself rather than of the script, which it may
+/// want to catch and retry.
+fn request_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+/// reqwest hides the cause, a timeout or a refused connection, in the
+/// error's sources.
+fn describe(error: &reqwest::Error) -> String {
+    let mut msg = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        msg.push_str(": ");
+        msg.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    msg
+}
+
+/// Strings of a Vec field, which `std/request` fills in.
+fn strings(object: &Object, name: &str) -> Vec<String> {
+    match field(object, name) {
+        Object::Vec(items) => items.borrow().iter().map(|item| item.to_string()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Vec field holding names and values one after the other.
+fn pairs(object: &Object, name: &str) -> Vec<(String, String)> {
+    strings(object, name)
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect()
+}
+
+fn client(request: &Object, context: &Rc<RefCell<ExecContext>>) -> Result<Client, ExecSignal> {
+    let policy = match field(request, "max_redirects") {
+        Object::Number(n) if n < 1.0 => Policy::none(),
+        Object::Number(n) => Policy::limited(n as usize),
+        _ => Policy::default(),
+    };
+    let mut builder = Client::builder().redirect(policy);
+    if let Object::Number(seconds) = field(request, "timeout_secs") {
+        if seconds > 0.0 {
+            builder = builder.timeout(Duration::from_secs_f64(seconds));
+        }
+    }
+    builder
+        .build()
+        .map_err(|e| native_error(describe(&e), context.clone()))
+}
+
+/// Adds the body: form fields, or the payload as JSON unless it is a
+/// string or bytes. Content-Type is only set when the script didn't.
+fn with_body(
+    mut builder: RequestBuilder,
+    request: &Object,
+    has_type: bool,
+) -> Result<RequestBuilder, String> {
+    let form = pairs(request, "form_pairs");
+    if !form.is_empty() {
+        return Ok(builder.form(&form));
+    }
+
+    let as_json = matches!(field(request, "payload_json"), Object::Bool(true));
+    let (body, content_type) = match field(request, "payload") {
+        Object::Null if !as_json => return Ok(builder),
+        Object::Str(text) if !as_json => (text.into_bytes(), "text/plain; charset=utf-8"),
+        Object::Bytes(bytes) if !as_json => (bytes.borrow().clone(), "application/octet-stream"),
+        value => (
+            json::from_object(&value)?.to_string().into_bytes(),
+            "application/json",
+        ),
+    };
+    if !has_type {
+        builder = builder.header(CONTENT_TYPE, content_type);
+    }
+    Ok(builder.body(body))
+}
+
+fn response_object(
+    response: Response,
+    context: Rc<RefCell<ExecContext>>,
+) -> Result<Object, ExecSignal> {
+    let status = response.status();
+    let url = response.url().to_string();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+            (name.to_string(), value)
+        })
+        .collect();
+    let body = response
+        .bytes()
+        .map_err(|e| request_error(describe(&e), context.clone()))?;
+    let body = Rc::new(body.to_vec());
+
+    let fields = vec![
+        ("status".to_string(), Object::Number(status.as_u16() as f64)),
+        ("ok".to_string(), Object::Bool(status.is_success())),
+        ("url".to_string(), Object::Str(url)),
+        (
+            "headers".to_string(),
+            headers_record(headers, context.clone()),
+        ),
+        (
+            "text".to_string(),
+            Object::Callable(Rc::new(Text { body: body.clone() })),
+        ),
+        ("json".to_string(), Object::Callable(Rc::new(Json { body }))),
+    ];
+    Ok(record("Response", fields, context))
+}
+
+/// request(request) -> Response, performs what `std/request` built
+#[derive(Clone, Debug)]
+pub struct Send;
+impl ReiCallable for Send {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let request = &args[0];
+        let url = str_field(request, "url");
+        let method = Method::from_bytes(str_field(request, "method").as_bytes())
+            .map_err(|_| native_error("Invalid HTTP method".into(), context.clone()))?;
+
+        let mut builder = client(request, &context)?.request(method, &url);
+        let mut has_type = false;
+        for line in strings(request, "header_lines") {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            has_type |= name.trim().eq_ignore_ascii_case("content-type");
+            builder = builder.header(name.trim(), value.trim());
+        }
+        let query = pairs(request, "query_pairs");
+        if !query.is_empty() {
+            builder = builder.query(&query);
+        }
+        let builder = with_body(builder, request, has_type)
+            .map_err(|msg| native_error(msg, context.clone()))?;
+
+        let response = builder.send().map_err(|e| {
+            request_error(
+                format!("Request to '{}' failed: {}", url, describe(&e)),
+                context.clone(),
+            )
+        })?;
+        response_object(response, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>request".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// text() -> string, the body of a response
+#[derive(Clone, Debug)]
+pub struct Text {
+    body: Rc<Vec<u8>>,
+}
+impl ReiCallable for Text {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        Ok(Object::Str(String::from_utf8_lossy(&self.body).to_string()))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>text".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// json() -> value, the body of a response parsed as JSON
+#[derive(Clone, Debug)]
+pub struct Json {
+    body: Rc<Vec<u8>>,
+}
+impl ReiCallable for Json {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        json::parse(&self.body, context.clone()).map_err(|msg| request_error(msg, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>json".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define("_NET_request".into(), Object::Callable(Rc::new(Send)))?;
+    Ok(())
+}
@@ -0,0 +1,494 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::backend::rei_class::ReiClass;
+use crate::backend::rei_instance::ReiInstance;
+use crate::backend::stack_trace::ExecContext;
+use crate::crux::token::Object;
+
+/// Longest request line plus headers a request may have
+const MAX_HEAD: usize = 64 * 1024;
+/// Largest body a request may have
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Value of a header, whatever the case of its name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Why no request could be read.
+#[derive(Debug)]
+pub enum ReadError {
+    /// The peer closed the connection, or it went quiet for too long
+    Closed,
+    /// The request is broken, answered with this status and message
+    Invalid(u16, String),
+}
+
+fn invalid(status: u16, msg: &str) -> ReadError {
+    ReadError::Invalid(status, msg.to_string())
+}
+
+/// Reads requests off a connection. What was read past the end of a
+/// request stays in `buffer` for the next one on the same connection.
+pub struct Incoming<R> {
+    reader: R,
+    pub buffer: Vec<u8>,
+}
+
+impl<R: Read> Incoming<R> {
+    pub fn new(reader: R, buffer: Vec<u8>) -> Self {
+        Incoming { reader, buffer }
+    }
+
+    /// Reads more of the connection into the buffer. Zero once it's closed.
+    fn fill(&mut self) -> io::Result<usize> {
+        let mut chunk = [0u8; 4096];
+        let n = self.reader.read(&mut chunk)?;
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(n)
+    }
+
+    /// Next line without its `\r\n`. None when the connection closes
+    /// before any of it arrived.
+    fn line(&mut self, limit: usize) -> Result<Option<String>, ReadError> {
+        let mut searched = 0;
+        loop {
+            if let Some(i) = self.buffer[searched..].iter().position(|&b| b == b'\n') {
+                let end = searched + i;
+                let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return String::from_utf8(line)
+                    .map(Some)
+                    .map_err(|_| invalid(400, "Request head isn't valid UTF-8"));
+            }
+            searched = self.buffer.len();
+            if searched > limit {
+                return Err(invalid(431, "Request head is too large"));
+            }
+            match self.fill() {
+                Ok(0) if self.buffer.is_empty() => return Ok(None),
+                Ok(0) => return Err(invalid(400, "Request ends in the middle of a line")),
+                Ok(_) => {}
+                Err(_) => return Err(ReadError::Closed),
+            }
+        }
+    }
+
+    fn exact(&mut self, len: usize) -> Result<Vec<u8>, ReadError> {
+        while self.buffer.len() < len {
+            match self.fill() {
+                Ok(0) => return Err(invalid(400, "Body is shorter than announced")),
+                Ok(_) => {}
+                Err(_) => return Err(ReadError::Closed),
+            }
+        }
+        Ok(self.buffer.drain(..len).collect())
+    }
+
+    /// Reads the next request on the connection.
+    pub fn request(&mut self) -> Result<HttpRequest, ReadError> {
+        // Empty lines before a request are allowed
+        let request_line = loop {
+            match self.line(MAX_HEAD)? {
+                None => return Err(ReadError::Closed),
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+            }
+        };
+
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid(400, "Malformed request line"));
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Err(invalid(505, "Only HTTP/1.x is supported"));
+        }
+
+        let mut headers = vec![];
+        let mut head_size = request_line.len();
+        loop {
+            let Some(line) = self.line(MAX_HEAD.saturating_sub(head_size))? else {
+                return Err(invalid(400, "Request ends before its headers do"));
+            };
+            if line.is_empty() {
+                break;
+            }
+            head_size += line.len();
+            if head_size > MAX_HEAD {
+                return Err(invalid(431, "Request head is too large"));
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(invalid(400, "Malformed header"));
+            };
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, parse_query(query)),
+            None => (target, vec![]),
+        };
+        let mut request = HttpRequest {
+            method: method.to_ascii_uppercase(),
+            path: percent_decode(path, false),
+            query,
+            version: version.to_string(),
+            headers,
+            body: vec![],
+        };
+
+        let chunked = request
+            .header("Transfer-Encoding")
+            .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+        if chunked {
+            request.body = self.chunked_body()?;
+        } else if let Some(length) = request.header("Content-Length") {
+            let length: usize = length
+                .parse()
+                .map_err(|_| invalid(400, "Invalid Content-Length"))?;
+            if length > MAX_BODY {
+                return Err(invalid(413, "Body is too large"));
+            }
+            request.body = self.exact(length)?;
+        }
+        Ok(request)
+    }
+
+    /// Body sent as `Transfer-Encoding: chunked`, trailers are dropped.
+    fn chunked_body(&mut self) -> Result<Vec<u8>, ReadError> {
+        let mut body = vec![];
+        loop {
+            let Some(line) = self.line(MAX_HEAD)? else {
+                return Err(invalid(400, "Body ends before its last chunk"));
+            };
+            // The size may be followed by extensions, which mean nothing here
+            let size = line.split(';').next().unwrap_or("").trim();
+            let size =
+                usize::from_str_radix(size, 16).map_err(|_| invalid(400, "Invalid chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            // A huge size would overflow the sum rather than exceed the limit
+            if body
+                .len()
+                .checked_add(size)
+                .is_none_or(|len| len > MAX_BODY)
+            {
+                return Err(invalid(413, "Body is too large"));
+            }
+            body.extend(self.exact(size)?);
+            if self.line(MAX_HEAD)? != Some(String::new()) {
+                return Err(invalid(400, "Chunk is longer than announced"));
+            }
+        }
+        loop {
+            match self.line(MAX_HEAD)? {
+                Some(trailer) if !trailer.is_empty() => continue,
+                _ => return Ok(body),
+            }
+        }
+    }
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key, true), percent_decode(value, true))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes, and `+` as a space in query strings. Broken
+/// escapes are kept as they are.
+fn percent_decode(text: &str, plus_as_space: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (b'+', _) if plus_as_space => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Values of the `:name` segments when `path` matches `pattern`. Empty
+/// segments don't count, so a trailing slash doesn't matter.
+pub fn match_route(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if pattern.len() != path.len() {
+        return None;
+    }
+
+    let mut params = vec![];
+    for (expected, actual) in pattern.iter().zip(path) {
+        match expected.strip_prefix(':') {
+            Some(name) => params.push((name.to_string(), actual.to_string())),
+            None if *expected == actual => {}
+            None => return None,
+        }
+    }
+    Some(params)
+}
+
+/// Field name a header gets on the request object: `Content-Type` is read
+/// as `headers.content_type`.
+pub fn header_field(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+/// Instance of a class with no methods, holding `fields`.
+pub fn record(
+    name: &str,
+    fields: Vec<(String, Object)>,
+    context: Rc<RefCell<ExecContext>>,
+) -> Object {
+    let class = ReiClass::new(
+        name.to_string(),
+        vec![],
+        vec![],
+        HashMap::new(),
+        HashMap::new(),
+        vec![],
+        HashMap::new(),
+    );
+    let instance = ReiInstance::new(class, context);
+    instance.fields.borrow_mut().extend(fields);
+    Object::Instance(Rc::new(RefCell::new(instance)))
+}
+
+/// `Headers` record with a field per header, named by `header_field`.
+pub fn headers_record(headers: Vec<(String, String)>, context: Rc<RefCell<ExecContext>>) -> Object {
+    let mut fields: Vec<(String, Object)> = vec![];
+    for (name, value) in headers {
+        let field = header_field(&name);
+        // Repeated headers are joined, the way HTTP allows
+        match fields.iter_mut().find(|(key, _)| *key == field) {
+            Some((_, Object::Str(joined))) => {
+                joined.push_str(", ");
+                joined.push_str(&value);
+            }
+            _ => fields.push((field, Object::Str(value))),
+        }
+    }
+    record("Headers", fields, context)
+}
+
+/// What handlers get: `method`, `path`, `version`, `body` and the `query`,
+/// `headers` and `params` records. The router fills `params` in.
+pub fn request_object(request: HttpRequest, context: Rc<RefCell<ExecContext>>) -> Object {
+    let query = request
+        .query
+        .into_iter()
+        .map(|(key, value)| (key, Object::Str(value)))
+        .collect();
+
+    let fields = vec![
+        ("method".to_string(), Object::Str(request.method)),
+        ("path".to_string(), Object::Str(request.path)),
+        ("version".to_string(), Object::Str(request.version)),
+        ("query".to_string(), record("Query", query, context.clone())),
+        (
+            "headers".to_string(),
+            headers_record(request.headers, context.clone()),
+        ),
+        (
+            "params".to_string(),
+            record("Params", vec![], context.clone()),
+        ),
+        (
+            "body".to_string(),
+            Object::Str(String::from_utf8_lossy(&request.body).to_string()),
+        ),
+    ];
+    record("Request", fields, context)
+}
+
+/// Instance a `after` middleware gets, shaped like `std/response`: a
+/// `status`, a `body` and `headers` as "Name: value" strings.
+pub fn response_object(response: HttpResponse, context: Rc<RefCell<ExecContext>>) -> Object {
+    let headers = response
+        .headers
+        .iter()
+        .map(|(name, value)| Object::Str(format!("{}: {}", name, value)))
+        .collect();
+    let body = match String::from_utf8(response.body) {
+        Ok(text) => Object::Str(text),
+        Err(e) => Object::Bytes(Rc::new(RefCell::new(e.into_bytes()))),
+    };
+    let fields = vec![
+        ("status".to_string(), Object::Number(response.status as f64)),
+        ("body".to_string(), body),
+        (
+            "headers".to_string(),
+            Object::Vec(Rc::new(RefCell::new(headers))),
+        ),
+    ];
+    record("Response", fields, context)
+}
+
+/// Content type of a file, from its extension.
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "reix" => "text/plain; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        HttpResponse {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    /// Response with no body and so no content type, like a 204.
+    pub fn empty(status: u16) -> Self {
+        HttpResponse {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn text(status: u16, body: &str) -> Self {
+        Self::new(
+            status,
+            "text/plain; charset=utf-8",
+            body.as_bytes().to_vec(),
+        )
+    }
+
+    /// Status line, headers and body. `Content-Length` and `Connection` are
+    /// always worked out here, whatever the handler set.
+    pub fn to_bytes(&self, with_body: bool, keep_alive: bool) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            let managed = ["content-length", "connection", "transfer-encoding"];
+            if !managed.contains(&name.to_ascii_lowercase().as_str()) {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        // 204 and 304 never have a body
+        if self.status != 204 && self.status != 304 {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        let connection = if keep_alive { "keep-alive" } else { "close" };
+        head.push_str(&format!("Connection: {}\r\n\r\n", connection));
+
+        let mut bytes = head.into_bytes();
+        if with_body && self.status != 204 && self.status != 304 {
+            bytes.extend_from_slice(&self.body);
+        }
+        bytes
+    }
+}
+
+pub fn reason(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        505 => "HTTP Version Not Supported",
+        _ => "Unknown",
+    }
+}
@@ -1 +1,6 @@
+pub mod client;
+pub mod http;
 pub mod net;
+pub mod server;
+pub mod socket;
+pub mod websocket;
@@ -1,39 +1,87 @@
-use reqwest::blocking;
 use std::any::Any;
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::fmt::Debug;
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
+use std::net::{TcpListener, TcpStream, UdpSocket};
 use std::rc::Rc;
-use std::sync::LazyLock;
-use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::backend::environment::Environment;
+use crate::backend::event_loop::{JobResult, ReiTask};
 use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use crate::backend::exec_signal::ExecSignal;
 use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::http::record;
+use crate::backend::native::net::socket::{self, Endpoint, Socket};
 use crate::backend::rei_callable::ReiCallable;
 use crate::backend::stack_trace::ExecContext;
-use crate::crux::token;
 use crate::crux::token::Object;
 
-static LISTENERS: LazyLock<Mutex<Vec<TcpListener>>> = LazyLock::new(|| Mutex::new(Vec::new()));
-static STREAMS: LazyLock<Mutex<Vec<TcpStream>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+/// Longest read_until goes without finding its delimiter, unless told
+const MAX_UNTIL: usize = 1024 * 1024;
 
-fn push_listener(listener: TcpListener) -> usize {
-    let mut l = LISTENERS.lock().unwrap();
-    l.push(listener);
-    l.len() - 1
+pub(super) fn native_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::ErrorInNativeFn { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
 }
 
-fn push_stream(stream: TcpStream) -> usize {
-    let mut s = STREAMS.lock().unwrap();
-    s.push(stream);
-    s.len() - 1
+/// Failures of the network rather than of the script: a refused
+/// connection, a timeout. Scripts can catch and retry them.
+pub(super) fn io_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
 }
 
-/// listen(addr: string) -> number (listener id)
+pub(super) fn socket_object(endpoint: Endpoint) -> Object {
+    Object::Socket(Rc::new(Socket::new(endpoint)))
+}
+
+pub(super) fn socket_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<Socket>, ExecSignal> {
+    match object {
+        Object::Socket(socket) => Ok(socket.clone()),
+        _ => Err(native_error("Expected socket".into(), context.clone())),
+    }
+}
+
+/// Lengths have to be whole, non-negative numbers.
+fn len_arg(object: &Object, context: &Rc<RefCell<ExecContext>>) -> Result<usize, ExecSignal> {
+    match object.as_number() {
+        Ok(n) if n >= 0.0 && n.fract() == 0.0 && n < usize::MAX as f64 => Ok(n as usize),
+        _ => Err(native_error("Expected length".into(), context.clone())),
+    }
+}
+
+fn addr_arg<'a>(
+    object: &'a Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<&'a str, ExecSignal> {
+    object
+        .as_str()
+        .map_err(|_| native_error("Expected address string".into(), context.clone()))
+}
+
+fn data_arg(object: &Object, context: &Rc<RefCell<ExecContext>>) -> Result<Vec<u8>, ExecSignal> {
+    match object {
+        Object::Str(s) => Ok(s.as_bytes().to_vec()),
+        Object::Bytes(b) => Ok(b.borrow().clone()),
+        _ => Err(native_error(
+            "Expected string or bytes to send".into(),
+            context.clone(),
+        )),
+    }
+}
+
+fn text(data: &[u8]) -> Object {
+    Object::Str(String::from_utf8_lossy(data).to_string())
+}
+
+fn bytes(data: Vec<u8>) -> Object {
+    Object::Bytes(Rc::new(RefCell::new(data)))
+}
+
+/// listen(addr: string) -> socket
 #[derive(Clone, Debug)]
 pub struct Listen;
 impl ReiCallable for Listen {
@@ -47,22 +95,14 @@ impl ReiCallable for Listen {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        match &args[0] {
-            Object::Str(addr) => {
-                let listener = TcpListener::bind(addr).map_err(|e| {
-                    let err = RuntimeErrorType::ErrorInNativeFn { msg: e.to_string() };
-                    ExecSignal::RuntimeError(RuntimeError::new(err, context))
-                })?;
-                let id = push_listener(listener);
-                Ok(Object::Number(id as f64))
-            }
-            _ => {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected string for listen()".into(),
-                };
-                Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)))
-            }
-        }
+        let addr = addr_arg(&args[0], &context)?;
+        let listener = TcpListener::bind(addr).map_err(|e| {
+            io_error(
+                format!("Failed to listen on '{}': {}", addr, e),
+                context.clone(),
+            )
+        })?;
+        Ok(socket_object(Endpoint::Listener(listener)))
     }
 
     fn to_string(&self) -> String {
@@ -74,7 +114,7 @@ impl ReiCallable for Listen {
     }
 }
 
-/// accept(listener_id) -> conn_id
+/// accept(listener) -> socket
 #[derive(Clone, Debug)]
 pub struct Accept;
 impl ReiCallable for Accept {
@@ -88,36 +128,50 @@ impl ReiCallable for Accept {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let id = match args[0] {
-            Object::Number(n) => n as usize,
-            _ => {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected listener id".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
-        };
+        let listener = socket_arg(&args[0], &context)?
+            .listener()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let (stream, _) = listener
+            .accept()
+            .map_err(|e| io_error(socket::describe(&e), context))?;
+        Ok(socket_object(Endpoint::Stream(stream)))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>accept".to_string()
+    }
 
-        let l = LISTENERS.lock().unwrap();
-        let listener = l.get(id);
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// connect(addr: string) -> socket
+#[derive(Clone, Debug)]
+pub struct Connect;
+impl ReiCallable for Connect {
+    fn arity(&self) -> usize {
+        1
+    }
 
-        if let Some(listener) = listener {
-            let (stream, _) = listener.accept().map_err(|e| {
-                let err = RuntimeErrorType::ErrorInNativeFn { msg: e.to_string() };
-                ExecSignal::RuntimeError(RuntimeError::new(err, context))
-            })?;
-            let sid = push_stream(stream);
-            Ok(Object::Number(sid as f64))
-        } else {
-            let err = RuntimeErrorType::ErrorInNativeFn {
-                msg: "Invalid listener id".into(),
-            };
-            Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)))
-        }
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let addr = addr_arg(&args[0], &context)?;
+        let stream = TcpStream::connect(addr).map_err(|e| {
+            io_error(
+                format!("Failed to connect to '{}': {}", addr, e),
+                context.clone(),
+            )
+        })?;
+        Ok(socket_object(Endpoint::Stream(stream)))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>accept".to_string()
+        "<native_fn>connect".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -125,7 +179,7 @@ impl ReiCallable for Accept {
     }
 }
 
-/// recv(conn_id, len) -> string
+/// recv(conn, max) -> string, empty once the peer has closed the connection
 #[derive(Clone, Debug)]
 pub struct Recv;
 impl ReiCallable for Recv {
@@ -139,23 +193,12 @@ impl ReiCallable for Recv {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let id = args[0].as_number().unwrap_or(-1.0) as usize;
-        let len = args[1].as_number().unwrap_or(0.0) as usize;
-        let mut buf = vec![0u8; len];
-
-        let mut streams = STREAMS.lock().unwrap();
-        if let Some(stream) = streams.get_mut(id) {
-            let n = stream.read(&mut buf).map_err(|e| {
-                let err = RuntimeErrorType::ErrorInNativeFn { msg: e.to_string() };
-                ExecSignal::RuntimeError(RuntimeError::new(err, context))
-            })?;
-            Ok(Object::Str(String::from_utf8_lossy(&buf[..n]).to_string()))
-        } else {
-            let err = RuntimeErrorType::ErrorInNativeFn {
-                msg: "Invalid conn id".into(),
-            };
-            Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)))
-        }
+        let socket = socket_arg(&args[0], &context)?;
+        let max = len_arg(&args[1], &context)?;
+        let data = socket
+            .read(max)
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(text(&data))
     }
 
     fn to_string(&self) -> String {
@@ -167,10 +210,42 @@ impl ReiCallable for Recv {
     }
 }
 
-/// send(conn_id, data) -> null
+/// recv_bytes(conn, max) -> bytes, empty once the peer has closed the
+/// connection
 #[derive(Clone, Debug)]
-pub struct Send;
-impl ReiCallable for Send {
+pub struct RecvBytes;
+impl ReiCallable for RecvBytes {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?;
+        let max = len_arg(&args[1], &context)?;
+        let data = socket
+            .read(max)
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(bytes(data))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>recv_bytes".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// read_exact(conn, len) -> bytes, fails if the connection ends first
+#[derive(Clone, Debug)]
+pub struct ReadExact;
+impl ReiCallable for ReadExact {
     fn arity(&self) -> usize {
         2
     }
@@ -181,30 +256,79 @@ impl ReiCallable for Send {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let id = args[0].as_number().unwrap_or(-1.0) as usize;
-        let data = match &args[1] {
-            Object::Str(s) => s.clone(),
-            _ => {
-                let err = RuntimeErrorType::ErrorInNativeFn {
-                    msg: "Expected string for send".into(),
-                };
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
-            }
+        let socket = socket_arg(&args[0], &context)?;
+        let len = len_arg(&args[1], &context)?;
+        let data = socket
+            .read_exact(len)
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(bytes(data))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>read_exact".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// read_until(conn, delimiter, max) -> string ending with the delimiter, or
+/// what was left when the connection ended. A null max means MAX_UNTIL.
+#[derive(Clone, Debug)]
+pub struct ReadUntil;
+impl ReiCallable for ReadUntil {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?;
+        let delimiter = data_arg(&args[1], &context)?;
+        let max = match &args[2] {
+            Object::Null => MAX_UNTIL,
+            max => len_arg(max, &context)?,
         };
+        let data = socket
+            .read_until(&delimiter, max)
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(text(&data))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>read_until".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// send(conn, data) -> null, data being a string or bytes
+#[derive(Clone, Debug)]
+pub struct Send;
+impl ReiCallable for Send {
+    fn arity(&self) -> usize {
+        2
+    }
 
-        let mut streams = STREAMS.lock().unwrap();
-        if let Some(stream) = streams.get_mut(id) {
-            stream.write_all(data.as_bytes()).map_err(|e| {
-                let err = RuntimeErrorType::ErrorInNativeFn { msg: e.to_string() };
-                ExecSignal::RuntimeError(RuntimeError::new(err, context))
-            })?;
-            Ok(Object::Null)
-        } else {
-            let err = RuntimeErrorType::ErrorInNativeFn {
-                msg: "Invalid conn id".into(),
-            };
-            Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)))
-        }
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?;
+        let data = data_arg(&args[1], &context)?;
+        socket
+            .send(&data)
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(Object::Null)
     }
 
     fn to_string(&self) -> String {
@@ -216,16 +340,128 @@ impl ReiCallable for Send {
     }
 }
 
+/// accept_async(listener) -> task resolving to a socket
+#[derive(Clone, Debug)]
+pub struct AcceptAsync;
+impl ReiCallable for AcceptAsync {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let listener = socket_arg(&args[0], &context)?
+            .listener()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+
+        let job = ReiTask::job(
+            "accept",
+            move || -> JobResult {
+                let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+                Ok(Box::new(move || socket_object(Endpoint::Stream(stream))))
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>accept_async".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// recv_async(conn, max) -> task resolving to a string, empty once the
+/// peer has closed the connection
 #[derive(Clone, Debug)]
-pub struct Route {
-    pub method: String,
-    pub pattern: String,
+pub struct RecvAsync;
+impl ReiCallable for RecvAsync {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?;
+        let max = len_arg(&args[1], &context)?;
+        let mut stream = socket
+            .stream()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let buffer = socket.buffer.clone();
+
+        let job = ReiTask::job(
+            "recv",
+            move || -> JobResult {
+                let data = socket::read_buffered(&mut stream, &buffer, max)
+                    .map_err(|e| socket::describe(&e))?;
+                Ok(Box::new(move || text(&data)))
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>recv_async".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// set_timeout(socket, ms) -> null, how long reads and writes may wait.
+/// 0 or null waits forever
+#[derive(Clone, Debug)]
+pub struct SetTimeout;
+impl ReiCallable for SetTimeout {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?;
+        let timeout = match args[1] {
+            Object::Null => None,
+            Object::Number(0.0) => None,
+            Object::Number(ms) if ms > 0.0 => Some(Duration::from_secs_f64(ms / 1000.0)),
+            _ => return Err(native_error("Expected timeout in ms".into(), context)),
+        };
+        socket
+            .set_timeout(timeout)
+            .map_err(|msg| native_error(msg, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>set_timeout".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
-static ROUTES: LazyLock<Mutex<Vec<Route>>> = LazyLock::new(|| Mutex::new(Vec::new()));
 
+/// peer_addr(socket) -> string, "ip:port" of the other end
 #[derive(Clone, Debug)]
-pub struct Get;
-impl ReiCallable for Get {
+pub struct PeerAddr;
+impl ReiCallable for PeerAddr {
     fn arity(&self) -> usize {
         1
     }
@@ -236,27 +472,43 @@ impl ReiCallable for Get {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let url = args[0].as_str().unwrap();
+        let addr = socket_arg(&args[0], &context)?
+            .peer_addr()
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(Object::Str(addr.to_string()))
+    }
 
-        let response = blocking::get(url).map_err(|e| {
-            let err = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("{}", e),
-            };
-            ExecSignal::RuntimeError(RuntimeError::new(err, context.clone()))
-        })?;
+    fn to_string(&self) -> String {
+        "<native_fn>peer_addr".to_string()
+    }
 
-        let body = response.text().map_err(|e| {
-            let err = RuntimeErrorType::ErrorInNativeFn {
-                msg: format!("{}", e),
-            };
-            ExecSignal::RuntimeError(RuntimeError::new(err, context))
-        })?;
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-        Ok(Object::Str(body))
+/// local_addr(socket) -> string, "ip:port" of this end
+#[derive(Clone, Debug)]
+pub struct LocalAddr;
+impl ReiCallable for LocalAddr {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let addr = socket_arg(&args[0], &context)?
+            .local_addr()
+            .map_err(|msg| io_error(msg, context.clone()))?;
+        Ok(Object::Str(addr.to_string()))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>get".into()
+        "<native_fn>local_addr".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -264,26 +516,27 @@ impl ReiCallable for Get {
     }
 }
 
+/// close(socket) -> null, the socket is also closed once nothing refers
+/// to it
 #[derive(Clone, Debug)]
-pub struct Router;
-impl ReiCallable for Router {
+pub struct Close;
+impl ReiCallable for Close {
     fn arity(&self) -> usize {
-        0
+        1
     }
 
     fn call(
         &self,
         _interpreter: &mut Interpreter,
-        _args: &Vec<Object>,
-        _context: Rc<RefCell<ExecContext>>,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        Ok(Object::Router(Rc::new(RefCell::new(token::Router {
-            routes: HashMap::new(),
-        }))))
+        socket_arg(&args[0], &context)?.close();
+        Ok(Object::Null)
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>router".into()
+        "<native_fn>close".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -291,11 +544,12 @@ impl ReiCallable for Router {
     }
 }
 
+/// udp_bind(addr: string) -> socket
 #[derive(Clone, Debug)]
-pub struct RouterGet;
-impl ReiCallable for RouterGet {
+pub struct UdpBind;
+impl ReiCallable for UdpBind {
     fn arity(&self) -> usize {
-        3
+        1
     }
 
     fn call(
@@ -304,51 +558,48 @@ impl ReiCallable for RouterGet {
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let router = match &args[0] {
-            Object::Router(r) => r.clone(),
-            _ => {
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    RuntimeErrorType::ErrorInNativeFn {
-                        msg: "Expected router".into(),
-                    },
-                    context,
-                )))
-            }
-        };
+        let addr = addr_arg(&args[0], &context)?;
+        let socket = UdpSocket::bind(addr)
+            .map_err(|e| io_error(format!("Failed to bind '{}': {}", addr, e), context.clone()))?;
+        Ok(socket_object(Endpoint::Udp(socket)))
+    }
 
-        let path = match &args[1] {
-            Object::Str(s) => s.clone(),
-            _ => {
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    RuntimeErrorType::ErrorInNativeFn {
-                        msg: "Expected string path".into(),
-                    },
-                    context,
-                )))
-            }
-        };
+    fn to_string(&self) -> String {
+        "<native_fn>udp_bind".to_string()
+    }
 
-        let handler = match &args[2] {
-            Object::Callable(c) => c.clone(),
-            _ => {
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    RuntimeErrorType::ErrorInNativeFn {
-                        msg: "Expected function".into(),
-                    },
-                    context,
-                )))
-            }
-        };
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
 
-        router
-            .borrow_mut()
-            .routes
-            .insert(("GET".into(), path), handler);
+/// send_to(socket, data, addr) -> null, one datagram
+#[derive(Clone, Debug)]
+pub struct SendTo;
+impl ReiCallable for SendTo {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let socket = socket_arg(&args[0], &context)?
+            .udp()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let data = data_arg(&args[1], &context)?;
+        let addr = addr_arg(&args[2], &context)?;
+        socket
+            .send_to(&data, addr)
+            .map_err(|e| io_error(socket::describe(&e), context.clone()))?;
         Ok(Object::Null)
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>router_get".into()
+        "<native_fn>send_to".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -356,102 +607,42 @@ impl ReiCallable for RouterGet {
     }
 }
 
+/// recv_from(socket, max) -> Datagram with the `data` bytes, the same as
+/// `text` and the sender's `addr`. A longer datagram is cut to max
 #[derive(Clone, Debug)]
-pub struct RouterServe;
-impl ReiCallable for RouterServe {
+pub struct RecvFrom;
+impl ReiCallable for RecvFrom {
     fn arity(&self) -> usize {
         2
     }
 
     fn call(
         &self,
-        interpreter: &mut Interpreter,
+        _interpreter: &mut Interpreter,
         args: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let router = match &args[0] {
-            Object::Router(r) => r.clone(),
-            _ => {
-                return Err(ExecSignal::RuntimeError(RuntimeError::new(
-                    RuntimeErrorType::ErrorInNativeFn {
-                        msg: "Expected router".into(),
-                    },
-                    context,
-                )))
-            }
-        };
-
-        let addr = args[1].as_str().map_err(|msg| {
-            let err = RuntimeErrorType::ErrorInNativeFn { msg };
-            ExecSignal::RuntimeError(RuntimeError::new(err, context.clone()))
-        })?;
-
-        let listener = std::net::TcpListener::bind(addr).map_err(|e| {
-            let err = RuntimeErrorType::ErrorInNativeFn { msg: e.to_string() };
-            ExecSignal::RuntimeError(RuntimeError::new(err, context.clone()))
-        })?;
+        let socket = socket_arg(&args[0], &context)?
+            .udp()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let max = len_arg(&args[1], &context)?;
 
-        for stream in listener.incoming() {
-            if let Ok(mut stream) = stream {
-                let mut buf = [0; 2048];
-                let n = stream.read(&mut buf).unwrap_or(0);
-                let req = String::from_utf8_lossy(&buf[..n]);
-
-                // simple parse: first line "GET /path HTTP/1.1"
-                let mut parts = req.lines().next().unwrap_or("").split_whitespace();
-                let method = parts.next().unwrap_or("").to_string();
-                let path = parts.next().unwrap_or("").to_string();
-
-                // handle OPTIONS preflight
-                if method == "OPTIONS" {
-                    let resp = "HTTP/1.1 204 No Content\r\n\
-                        Access-Control-Allow-Origin: *\r\n\
-                        Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-                        Access-Control-Allow-Headers: Content-Type\r\n\
-                        Content-Length: 0\r\n\r\n";
-                    let _ = stream.write_all(resp.as_bytes());
-                    continue;
-                }
-
-                let handler_opt = {
-                    let r = router.borrow();
-                    r.routes.get(&(method.clone(), path.clone())).cloned()
-                };
-
-                let resp_body = if let Some(handler) = handler_opt {
-                    match handler.call(
-                        interpreter,
-                        &vec![Object::Str(req.to_string())],
-                        context.clone(),
-                    ) {
-                        Ok(Object::Str(s)) => s,
-                        Ok(other) => format!("{:?}", other),
-                        Err(_) => "Internal Server Error".into(),
-                    }
-                } else {
-                    "404 Not Found".into()
-                };
-
-                let resp = format!(
-                    "HTTP/1.1 200 OK\r\n\
-                    Content-Length: {}\r\n\
-                    Content-Type: text/plain\r\n\
-                    Access-Control-Allow-Origin: *\r\n\
-                    Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-                    Access-Control-Allow-Headers: Content-Type\r\n\r\n{}",
-                    resp_body.len(),
-                    resp_body
-                );
-
-                let _ = stream.write_all(resp.as_bytes());
-            }
-        }
+        let mut data = vec![0u8; max.min(socket::MAX_READ)];
+        let (n, from) = socket
+            .recv_from(&mut data)
+            .map_err(|e| io_error(socket::describe(&e), context.clone()))?;
+        data.truncate(n);
 
-        Ok(Object::Null)
+        let fields = vec![
+            ("text".to_string(), text(&data)),
+            ("data".to_string(), bytes(data)),
+            ("addr".to_string(), Object::Str(from.to_string())),
+        ];
+        Ok(record("Datagram", fields, context))
     }
 
     fn to_string(&self) -> String {
-        "<native_fn>serve".into()
+        "<native_fn>recv_from".to_string()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -462,14 +653,56 @@ impl ReiCallable for RouterServe {
 pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     env.define("_NET_listen".to_string(), Object::Callable(Rc::new(Listen)))?;
     env.define("_NET_accept".to_string(), Object::Callable(Rc::new(Accept)))?;
+    env.define(
+        "_NET_connect".to_string(),
+        Object::Callable(Rc::new(Connect)),
+    )?;
     env.define("_NET_recv".to_string(), Object::Callable(Rc::new(Recv)))?;
+    env.define(
+        "_NET_recv_bytes".to_string(),
+        Object::Callable(Rc::new(RecvBytes)),
+    )?;
+    env.define(
+        "_NET_read_exact".to_string(),
+        Object::Callable(Rc::new(ReadExact)),
+    )?;
+    env.define(
+        "_NET_read_until".to_string(),
+        Object::Callable(Rc::new(ReadUntil)),
+    )?;
     env.define("_NET_send".to_string(), Object::Callable(Rc::new(Send)))?;
-    env.define("_NET_router".into(), Object::Callable(Rc::new(Router)))?;
     env.define(
-        "_NET_router_get".into(),
-        Object::Callable(Rc::new(RouterGet)),
+        "_NET_accept_async".to_string(),
+        Object::Callable(Rc::new(AcceptAsync)),
+    )?;
+    env.define(
+        "_NET_recv_async".to_string(),
+        Object::Callable(Rc::new(RecvAsync)),
+    )?;
+    env.define(
+        "_NET_set_timeout".to_string(),
+        Object::Callable(Rc::new(SetTimeout)),
+    )?;
+    env.define(
+        "_NET_peer_addr".to_string(),
+        Object::Callable(Rc::new(PeerAddr)),
+    )?;
+    env.define(
+        "_NET_local_addr".to_string(),
+        Object::Callable(Rc::new(LocalAddr)),
+    )?;
+    env.define("_NET_close".to_string(), Object::Callable(Rc::new(Close)))?;
+    env.define(
+        "_NET_udp_bind".to_string(),
+        Object::Callable(Rc::new(UdpBind)),
+    )?;
+    env.define(
+        "_NET_send_to".to_string(),
+        Object::Callable(Rc::new(SendTo)),
+    )?;
+    env.define(
+        "_NET_recv_from".to_string(),
+        Object::Callable(Rc::new(RecvFrom)),
     )?;
-    env.define("_NET_serve".into(), Object::Callable(Rc::new(RouterServe)))?;
-    env.define("_NET_get".into(), Object::Callable(Rc::new(Get)))?;
     Ok(())
 }
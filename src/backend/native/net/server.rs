@@ -0,0 +1,1306 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::backend::environment::Environment;
+use crate::backend::event_loop::{self, JobResult, ReiTask, Step};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::http::{self, HttpResponse, Incoming, ReadError};
+use crate::backend::native::net::net::{native_error, socket_arg, socket_object};
+use crate::backend::native::net::socket::Endpoint;
+use crate::backend::native::process::signal;
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::{CallFrame, ExecContext};
+use crate::crux::output;
+use crate::crux::token::{self, Object};
+
+/// How long a kept-alive connection may stay quiet before it's closed
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often a waiting server checks whether it should stop
+pub(super) const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Set by SIGINT or SIGTERM while a server runs, every server then stops.
+/// A second signal ends the process in case one doesn't. Cleared once the
+/// last server stopped.
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+/// Servers currently accepting connections
+static SERVING: AtomicUsize = AtomicUsize::new(0);
+
+static HOOKED: Once = Once::new();
+
+/// Ctrl-C stops servers rather than interrupting the script while one runs.
+pub fn serving() -> bool {
+    SERVING.load(Ordering::SeqCst) > 0
+}
+
+/// Counts the router as serving until `finish_serving`. Does nothing for
+/// one that already is.
+fn start_serving(serving: &AtomicBool) {
+    HOOKED.call_once(|| {
+        for signal in [SIGINT, SIGTERM] {
+            // Only touches atomics and exits, both safe in a signal handler
+            let _ = unsafe {
+                signal_hook::low_level::register(signal, move || {
+                    if SERVING.load(Ordering::SeqCst) == 0 {
+                        // Without a server or a script wanting it, the signal
+                        // does what it would have done
+                        if !signal::wanted(signal) {
+                            let _ = signal_hook::low_level::emulate_default_handler(signal);
+                        }
+                    } else if SIGNALLED.swap(true, Ordering::SeqCst) {
+                        signal_hook::low_level::exit(1);
+                    }
+                })
+            };
+        }
+    });
+    if !serving.swap(true, Ordering::SeqCst) && SERVING.fetch_add(1, Ordering::SeqCst) == 0 {
+        SIGNALLED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Once no server is left the signal that stopped them is spent, later
+/// servers run until they are stopped again.
+fn finish_serving(serving: &AtomicBool) {
+    if serving.swap(false, Ordering::SeqCst) && SERVING.fetch_sub(1, Ordering::SeqCst) == 1 {
+        SIGNALLED.store(false, Ordering::SeqCst);
+    }
+}
+
+pub(super) fn should_stop(stopping: &AtomicBool) -> bool {
+    stopping.load(Ordering::SeqCst) || SIGNALLED.load(Ordering::SeqCst)
+}
+
+/// Waits for the next connection. None once the server should stop.
+fn accept_until_stopped(
+    listener: &TcpListener,
+    stopping: &AtomicBool,
+) -> io::Result<Option<TcpStream>> {
+    listener.set_nonblocking(true)?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(Some(stream));
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if should_stop(stopping) {
+                    return Ok(None);
+                }
+                thread::sleep(STOP_POLL);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads a connection, giving up once it's been quiet for
+/// KEEP_ALIVE_TIMEOUT or the server is stopping.
+struct Watched {
+    stream: TcpStream,
+    stopping: Arc<AtomicBool>,
+}
+
+impl Watched {
+    fn new(stream: &TcpStream, stopping: Arc<AtomicBool>) -> io::Result<Self> {
+        let stream = stream.try_clone()?;
+        stream.set_read_timeout(Some(STOP_POLL))?;
+        Ok(Watched { stream, stopping })
+    }
+}
+
+impl Read for Watched {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        loop {
+            match self.stream.read(buf) {
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if should_stop(&self.stopping) || started.elapsed() >= KEEP_ALIVE_TIMEOUT {
+                        return Err(e);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+pub(super) fn router_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<RefCell<token::Router>>, ExecSignal> {
+    match object {
+        Object::Router(router) => Ok(router.clone()),
+        _ => Err(native_error("Expected router".into(), context.clone())),
+    }
+}
+
+fn handler_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<dyn ReiCallable>, ExecSignal> {
+    match object {
+        Object::Callable(handler) => Ok(handler.clone()),
+        _ => Err(native_error("Expected function".into(), context.clone())),
+    }
+}
+
+pub(super) fn str_arg<'a>(
+    object: &'a Object,
+    what: &str,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<&'a str, ExecSignal> {
+    object
+        .as_str()
+        .map_err(|_| native_error(format!("Expected string {}", what), context.clone()))
+}
+
+#[derive(Clone, Debug)]
+pub struct Router;
+impl ReiCallable for Router {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        Ok(Object::Router(Rc::new(RefCell::new(
+            token::Router::default(),
+        ))))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn add_route(
+    router: &Object,
+    method: &str,
+    pattern: &Object,
+    handler: &Object,
+    context: Rc<RefCell<ExecContext>>,
+) -> Result<Object, ExecSignal> {
+    let router = router_arg(router, &context)?;
+    let pattern = str_arg(pattern, "path", &context)?;
+    let handler = handler_arg(handler, &context)?;
+
+    router.borrow_mut().routes.push(token::Route {
+        method: method.to_ascii_uppercase(),
+        pattern: pattern.to_string(),
+        handler,
+    });
+    Ok(Object::Null)
+}
+
+/// router_get(router, path, handler) -> null
+#[derive(Clone, Debug)]
+pub struct RouterGet;
+impl ReiCallable for RouterGet {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        add_route(&args[0], "GET", &args[1], &args[2], context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_get".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_add(router, method, path, handler) -> null, `path` may have
+/// `:name` segments that end up in `request.params`
+#[derive(Clone, Debug)]
+pub struct RouterAdd;
+impl ReiCallable for RouterAdd {
+    fn arity(&self) -> usize {
+        4
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let method = str_arg(&args[1], "method", &context)?;
+        add_route(&args[0], method, &args[2], &args[3], context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_add".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_before(router, middleware) -> null, the middleware is called with
+/// the request before its route
+#[derive(Clone, Debug)]
+pub struct RouterBefore;
+impl ReiCallable for RouterBefore {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let middleware = handler_arg(&args[1], &context)?;
+        router.borrow_mut().before.push(middleware);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_before".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_after(router, middleware) -> null, the middleware is called with
+/// the request and its response
+#[derive(Clone, Debug)]
+pub struct RouterAfter;
+impl ReiCallable for RouterAfter {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let middleware = handler_arg(&args[1], &context)?;
+        router.borrow_mut().after.push(middleware);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_after".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_not_found(router, handler) -> null, the handler answers with 404
+/// unless it returns a response of its own
+#[derive(Clone, Debug)]
+pub struct RouterNotFound;
+impl ReiCallable for RouterNotFound {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let handler = handler_arg(&args[1], &context)?;
+        router.borrow_mut().not_found = Some(handler);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_not_found".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_mount(router, prefix, dir) -> null, GET requests under `prefix`
+/// no route matches are answered with the files in `dir`
+#[derive(Clone, Debug)]
+pub struct RouterMount;
+impl ReiCallable for RouterMount {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let prefix = str_arg(&args[1], "prefix", &context)?;
+        let dir = str_arg(&args[2], "directory", &context)?;
+        let prefix = format!("/{}", prefix.trim_matches('/'));
+        router
+            .borrow_mut()
+            .mounts
+            .push((prefix, PathBuf::from(dir)));
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_mount".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_cors(router, origin) -> null, allows requests from `origin` and
+/// answers their preflight
+#[derive(Clone, Debug)]
+pub struct RouterCors;
+impl ReiCallable for RouterCors {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let origin = str_arg(&args[1], "origin", &context)?;
+        router.borrow_mut().cors = Some(origin.to_string());
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_cors".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// router_stop(router) -> null, stops accepting connections and closes the
+/// open ones once their current request is answered
+#[derive(Clone, Debug)]
+pub struct RouterStop;
+impl ReiCallable for RouterStop {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        router.borrow().stopping.store(true, Ordering::SeqCst);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>router_stop".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Field of an instance, null for anything else.
+pub(super) fn field(object: &Object, name: &str) -> Object {
+    match object {
+        Object::Instance(instance) => instance
+            .borrow()
+            .fields
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or(Object::Null),
+        _ => Object::Null,
+    }
+}
+
+pub(super) fn str_field(object: &Object, name: &str) -> String {
+    match field(object, name) {
+        Object::Str(s) => s,
+        _ => String::new(),
+    }
+}
+
+/// HTTP/1.1 keeps the connection open unless asked not to, 1.0 only when
+/// asked to.
+fn keeps_alive(request: &Object) -> bool {
+    let connection = str_field(&field(request, "headers"), "connection").to_ascii_lowercase();
+    if str_field(request, "version") == "HTTP/1.0" {
+        connection.contains("keep-alive")
+    } else {
+        !connection.contains("close")
+    }
+}
+
+/// Turns what a handler returned into a response. A string is sent as
+/// text and bytes as binary, both with `status`, null has no body and an
+/// instance with a `status` (like `std/response`) is sent as it says.
+fn to_response(value: Object, status: u16) -> Result<HttpResponse, String> {
+    let fields = match &value {
+        Object::Str(s) => return Ok(HttpResponse::text(status, s)),
+        Object::Bytes(b) => {
+            return Ok(HttpResponse::new(
+                status,
+                "application/octet-stream",
+                b.borrow().clone(),
+            ))
+        }
+        Object::Null if status == 200 => return Ok(HttpResponse::empty(204)),
+        Object::Null => return Ok(HttpResponse::empty(status)),
+        Object::Instance(instance) if !matches!(field(&value, "status"), Object::Null) => {
+            instance.borrow().fields.clone()
+        }
+        other => return Ok(HttpResponse::text(status, &other.to_string())),
+    };
+    let fields = fields.borrow();
+
+    let status = match fields.get("status") {
+        Some(Object::Number(n)) if (100.0..600.0).contains(n) && n.fract() == 0.0 => *n as u16,
+        Some(other) => return Err(format!("'{}' isn't a valid status", other)),
+        None => status,
+    };
+    let mut response = match fields.get("body") {
+        Some(Object::Str(s)) if !s.is_empty() => HttpResponse::text(status, s),
+        Some(Object::Bytes(b)) => {
+            HttpResponse::new(status, "application/octet-stream", b.borrow().clone())
+        }
+        Some(Object::Str(_)) | Some(Object::Null) | None => HttpResponse::empty(status),
+        Some(other) => HttpResponse::text(status, &other.to_string()),
+    };
+
+    if let Some(Object::Vec(headers)) = fields.get("headers") {
+        for header in headers.borrow().iter() {
+            let header = header.to_string();
+            let Some((name, value)) = header.split_once(':') else {
+                return Err(format!("'{}' isn't a 'Name: value' header", header));
+            };
+            let (name, value) = (name.trim().to_string(), value.trim().to_string());
+            // What the handler says replaces the default content type
+            response
+                .headers
+                .retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+            response.headers.push((name, value));
+        }
+    }
+    Ok(response)
+}
+
+fn server_error() -> HttpResponse {
+    HttpResponse::text(500, "500 Internal Server Error")
+}
+
+/// Response for what a handler returned, with `status` unless it chose
+/// one. A response that makes no sense is logged and answered with 500.
+fn answer(value: Object, status: u16) -> HttpResponse {
+    to_response(value, status).unwrap_or_else(|msg| {
+        output::err(&format!("Invalid response: {}\n", msg));
+        server_error()
+    })
+}
+
+/// Puts the `:name` segments a route matched in `request.params`.
+pub(super) fn fill_params(request: &Object, params: Vec<(String, String)>) {
+    if let Object::Instance(params_record) = field(request, "params") {
+        let params_record = params_record.borrow();
+        let mut fields = params_record.fields.borrow_mut();
+        for (name, value) in params {
+            fields.insert(name, Object::Str(value));
+        }
+    }
+}
+
+/// Calls a handler or middleware with as many of `arguments` as it takes.
+/// Gives what it returned, the task of an async one is left to await. None
+/// when it failed, the error is logged with its stack trace.
+pub(super) fn call_handler(
+    handler: &Rc<dyn ReiCallable>,
+    arguments: &[Object],
+    request: &Object,
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) -> Option<Object> {
+    let arguments = arguments[..handler.arity().min(arguments.len())].to_vec();
+    let location = format!(
+        "handling {} {}",
+        str_field(request, "method"),
+        str_field(request, "path")
+    );
+    let depth = context.borrow().call_stack.len();
+    context
+        .borrow_mut()
+        .push_call(CallFrame::new(handler.to_string(), location));
+
+    let result = handler.call(interpreter, &arguments, context.clone());
+    let value = settled(result);
+    // A failed call leaves its frames behind for the trace, the server
+    // carries on without them
+    context.borrow_mut().call_stack.truncate(depth);
+    value
+}
+
+/// What an awaited handler finished with. None when it failed, the error
+/// is logged rather than raised in the server's own tasks.
+pub(super) fn settled(outcome: Result<Object, ExecSignal>) -> Option<Object> {
+    if let Err(signal) = &outcome {
+        output::err(&signal.to_string());
+    }
+    outcome.ok()
+}
+
+/// Task calling a handler and waiting for it when it's async. It finishes
+/// with null either way, a failure is only logged.
+pub(super) fn handler_task(
+    handler: Rc<dyn ReiCallable>,
+    arguments: Vec<Object>,
+    request: Object,
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) -> Object {
+    let mut called = false;
+    let handler_context = context.clone();
+    let task = ReiTask::native(
+        "handler",
+        move |interpreter, sent| {
+            // Resumed a second time only with what its task finished with
+            if std::mem::replace(&mut called, true) {
+                settled(sent);
+                return Ok(Step::Return(Object::Null));
+            }
+            let context = handler_context.clone();
+            Ok(
+                match call_handler(&handler, &arguments, &request, interpreter, context) {
+                    Some(Object::Task(task)) => Step::Await(Object::Task(task)),
+                    _ => Step::Return(Object::Null),
+                },
+            )
+        },
+        context,
+    );
+    interpreter.event_loop.spawn(task)
+}
+
+/// File under one of the router's mounts for `path`. Paths leaving the
+/// mounted directory never match.
+fn serve_static(router: &Rc<RefCell<token::Router>>, path: &str) -> Option<HttpResponse> {
+    let mounts = router.borrow().mounts.clone();
+    for (prefix, dir) in mounts {
+        let rest = match path.strip_prefix(prefix.trim_end_matches('/')) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ => continue,
+        };
+
+        let mut file = dir;
+        for segment in rest.split('/').filter(|s| !s.is_empty()) {
+            if segment == ".." || segment.contains('\\') {
+                return None;
+            }
+            file.push(segment);
+        }
+        if file.is_dir() {
+            file.push("index.html");
+        }
+        if let Ok(body) = fs::read(&file) {
+            return Some(HttpResponse::new(200, http::mime_type(&file), body));
+        }
+    }
+    None
+}
+
+/// Who answers a request once the middleware let it through.
+enum Routed {
+    /// This handler, with the status it answers with unless it picks one
+    Handler(Rc<dyn ReiCallable>, u16),
+    Answered(HttpResponse),
+}
+
+/// Finds what answers `request`: its route, a mounted file, or 404. A known
+/// path asked with another method is a 405, or the list of methods for
+/// OPTIONS.
+fn route(router: &Rc<RefCell<token::Router>>, request: &Object) -> Routed {
+    let method = str_field(request, "method");
+    let path = str_field(request, "path");
+    // HEAD is answered by the GET route, without the body
+    let wanted = if method == "HEAD" {
+        "GET"
+    } else {
+        method.as_str()
+    };
+
+    let mut allowed: Vec<String> = vec![];
+    let mut found = None;
+    for route in router.borrow().routes.iter() {
+        let Some(params) = http::match_route(&route.pattern, &path) else {
+            continue;
+        };
+        if route.method == wanted {
+            found = Some((route.handler.clone(), params));
+            break;
+        }
+        if !allowed.contains(&route.method) {
+            allowed.push(route.method.clone());
+        }
+    }
+
+    if let Some((handler, params)) = found {
+        fill_params(request, params);
+        return Routed::Handler(handler, 200);
+    }
+
+    if wanted == "GET" {
+        if let Some(response) = serve_static(router, &path) {
+            return Routed::Answered(response);
+        }
+    }
+
+    if !allowed.is_empty() {
+        allowed.push("OPTIONS".to_string());
+        let allowed = allowed.join(", ");
+        if method == "OPTIONS" {
+            let mut response = HttpResponse::empty(204);
+            response
+                .headers
+                .push(("Allow".to_string(), allowed.clone()));
+            // A CORS preflight asks which methods and headers it may use
+            if router.borrow().cors.is_some() {
+                let requested =
+                    str_field(&field(request, "headers"), "access_control_request_headers");
+                let headers = match requested.is_empty() {
+                    true => "Content-Type".to_string(),
+                    false => requested,
+                };
+                response
+                    .headers
+                    .push(("Access-Control-Allow-Methods".to_string(), allowed));
+                response
+                    .headers
+                    .push(("Access-Control-Allow-Headers".to_string(), headers));
+            }
+            return Routed::Answered(response);
+        }
+        let mut response = HttpResponse::text(405, "405 Method Not Allowed");
+        response.headers.push(("Allow".to_string(), allowed));
+        return Routed::Answered(response);
+    }
+
+    let not_found = router.borrow().not_found.clone();
+    match not_found {
+        Some(handler) => Routed::Handler(handler, 404),
+        None => Routed::Answered(HttpResponse::text(404, "404 Not Found")),
+    }
+}
+
+/// What answering a request needs next.
+enum Next {
+    /// Call this handler or middleware and go on with what it returns
+    Call(Rc<dyn ReiCallable>, Vec<Object>),
+    Send(HttpResponse),
+}
+
+/// Where a request is on its way through the middleware and its route.
+#[derive(Clone)]
+enum Stage {
+    /// Waiting on the `before` middleware at this index
+    Before(usize),
+    /// Waiting on the route's handler, answering with this status
+    Route(u16),
+    /// Waiting on the `after` middleware at this index, with the response
+    /// as it was before it
+    After(usize, Object),
+}
+
+/// Answers one request, calling each handler it needs in turn. Whoever
+/// drives it awaits the async ones, so a slow handler only holds up its
+/// own connection.
+struct Exchange {
+    router: Rc<RefCell<token::Router>>,
+    request: Object,
+    stage: Stage,
+    context: Rc<RefCell<ExecContext>>,
+}
+
+impl Exchange {
+    fn new(
+        router: Rc<RefCell<token::Router>>,
+        request: Object,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Self {
+        Exchange {
+            router,
+            request,
+            stage: Stage::Before(0),
+            context,
+        }
+    }
+
+    /// Runs the `before` middleware from `index` on. One returning anything
+    /// but null answers in place of the route.
+    fn before(&mut self, index: usize) -> Next {
+        let middleware = self.router.borrow().before.get(index).cloned();
+        let Some(middleware) = middleware else {
+            return match route(&self.router, &self.request) {
+                Routed::Handler(handler, status) => {
+                    self.stage = Stage::Route(status);
+                    Next::Call(handler, vec![self.request.clone()])
+                }
+                Routed::Answered(response) => self.after(response),
+            };
+        };
+        self.stage = Stage::Before(index);
+        Next::Call(middleware, vec![self.request.clone()])
+    }
+
+    /// Hands the response to the `after` middleware, which may replace it.
+    fn after(&mut self, mut response: HttpResponse) -> Next {
+        if let Some(origin) = self.router.borrow().cors.clone() {
+            response
+                .headers
+                .push(("Access-Control-Allow-Origin".to_string(), origin));
+        }
+        if self.router.borrow().after.is_empty() {
+            return Next::Send(response);
+        }
+        let value = http::response_object(response, self.context.clone());
+        self.after_from(0, value)
+    }
+
+    fn after_from(&mut self, index: usize, value: Object) -> Next {
+        let middleware = self.router.borrow().after.get(index).cloned();
+        let Some(middleware) = middleware else {
+            return Next::Send(answer(value, 200));
+        };
+        self.stage = Stage::After(index, value.clone());
+        Next::Call(middleware, vec![self.request.clone(), value])
+    }
+
+    /// Goes on with what the last handler called returned, None when it
+    /// failed.
+    fn returned(&mut self, value: Option<Object>) -> Next {
+        match (self.stage.clone(), value) {
+            (Stage::Before(index), Some(Object::Null)) => self.before(index + 1),
+            (Stage::Before(_), Some(value)) => self.after(answer(value, 200)),
+            (Stage::Route(status), Some(value)) => self.after(answer(value, status)),
+            (Stage::Before(_) | Stage::Route(_), None) => self.after(server_error()),
+            (Stage::After(index, value), Some(Object::Null)) => self.after_from(index + 1, value),
+            (Stage::After(index, _), Some(replaced)) => self.after_from(index + 1, replaced),
+            (Stage::After(..), None) => Next::Send(server_error()),
+        }
+    }
+
+    /// Calls handlers until one gives a task, which is returned to await,
+    /// or the response is ready.
+    fn advance(
+        &mut self,
+        mut next: Next,
+        interpreter: &mut Interpreter,
+    ) -> Result<HttpResponse, Rc<ReiTask>> {
+        loop {
+            next = match next {
+                Next::Send(response) => return Ok(response),
+                Next::Call(handler, arguments) => match call_handler(
+                    &handler,
+                    &arguments,
+                    &self.request,
+                    interpreter,
+                    self.context.clone(),
+                ) {
+                    Some(Object::Task(task)) => return Err(task),
+                    value => self.returned(value),
+                },
+            }
+        }
+    }
+}
+
+/// Writes the response to `request` on `stream`. True when the connection
+/// stays open for another request.
+fn send(
+    router: &Rc<RefCell<token::Router>>,
+    stream: &mut TcpStream,
+    request: &Object,
+    response: HttpResponse,
+) -> bool {
+    let with_body = str_field(request, "method") != "HEAD";
+    // The handler may have stopped the server
+    let keep_alive = keeps_alive(request) && !should_stop(&router.borrow().stopping);
+    let sent = stream.write_all(&response.to_bytes(with_body, keep_alive));
+    keep_alive && sent.is_ok()
+}
+
+/// Answers `request` right away, for a server that handles one connection
+/// at a time anyway. True when the connection stays open.
+fn respond_blocking(
+    router: &Rc<RefCell<token::Router>>,
+    stream: &mut TcpStream,
+    request: &Object,
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) -> bool {
+    let mut exchange = Exchange::new(router.clone(), request.clone(), context);
+    let mut next = exchange.before(0);
+    let response = loop {
+        match exchange.advance(next, interpreter) {
+            Ok(response) => break response,
+            Err(task) => {
+                let value = settled(event_loop::block_on(interpreter, &task));
+                next = exchange.returned(value);
+            }
+        }
+    };
+    send(router, stream, request, response)
+}
+
+/// Answers a request that couldn't be read, the connection is closed after.
+fn reject(stream: &mut TcpStream, status: u16, msg: &str) {
+    let response = HttpResponse::text(status, msg);
+    let _ = stream.write_all(&response.to_bytes(true, false));
+}
+
+/// accept_until_stopped(router, listener) -> task resolving to a socket,
+/// or null once the router is stopped or the process is asked to end
+#[derive(Clone, Debug)]
+pub struct AcceptUntilStopped;
+impl ReiCallable for AcceptUntilStopped {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let stopping = router.borrow().stopping.clone();
+        let serving = router.borrow().serving.clone();
+        let listener = socket_arg(&args[1], &context)?
+            .listener()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+
+        start_serving(&serving);
+        let job = ReiTask::job(
+            "accept",
+            move || -> JobResult {
+                let accepted = accept_until_stopped(&listener, &stopping);
+                if !matches!(accepted, Ok(Some(_))) {
+                    finish_serving(&serving);
+                }
+                match accepted.map_err(|e| e.to_string())? {
+                    Some(stream) => Ok(Box::new(move || socket_object(Endpoint::Stream(stream)))),
+                    None => Ok(Box::new(|| Object::Null)),
+                }
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>accept_until_stopped".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// read_request(router, conn) -> task resolving to the next request on
+/// the connection, or null once it's closed
+#[derive(Clone, Debug)]
+pub struct ReadRequest;
+impl ReiCallable for ReadRequest {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let stopping = router_arg(&args[0], &context)?.borrow().stopping.clone();
+        let socket = socket_arg(&args[1], &context)?;
+        let mut stream = socket
+            .stream()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        // Bytes read past the last request belong to this one
+        let pending = socket.buffer.clone();
+
+        let job = ReiTask::job(
+            "read_request",
+            move || -> JobResult {
+                let reader = Watched::new(&stream, stopping).map_err(|e| e.to_string())?;
+                let leftover = std::mem::take(&mut *pending.lock().unwrap());
+                let mut incoming = Incoming::new(reader, leftover);
+                match incoming.request() {
+                    Ok(request) => {
+                        *pending.lock().unwrap() = incoming.buffer;
+                        Ok(Box::new(move || {
+                            let context = Rc::new(RefCell::new(ExecContext::new()));
+                            http::request_object(request, context)
+                        }))
+                    }
+                    Err(ReadError::Invalid(status, msg)) => {
+                        reject(&mut stream, status, &msg);
+                        Ok(Box::new(|| Object::Null))
+                    }
+                    Err(ReadError::Closed) => Ok(Box::new(|| Object::Null)),
+                }
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>read_request".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// respond(router, conn, request) -> task resolving to a bool, answers the
+/// request and tells whether the connection stays open for another one
+#[derive(Clone, Debug)]
+pub struct RouterRespond;
+impl ReiCallable for RouterRespond {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let socket = socket_arg(&args[1], &context)?;
+        if !matches!(args[2], Object::Instance(_)) {
+            return Err(native_error("Expected request".into(), context));
+        }
+
+        let mut stream = socket
+            .stream()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let request = args[2].clone();
+        let mut exchange = Exchange::new(router.clone(), request.clone(), context.clone());
+        let mut started = false;
+        let task = ReiTask::native(
+            "respond",
+            move |interpreter, sent| {
+                // Past the first time, it's resumed with what a handler's
+                // task finished with
+                let next = match std::mem::replace(&mut started, true) {
+                    true => exchange.returned(settled(sent)),
+                    false => exchange.before(0),
+                };
+                Ok(match exchange.advance(next, interpreter) {
+                    Ok(response) => {
+                        let keep_alive = send(&router, &mut stream, &request, response);
+                        Step::Return(Object::Bool(keep_alive))
+                    }
+                    Err(task) => Step::Await(Object::Task(task)),
+                })
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(task))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>respond".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// header(request, name) -> string or null, whatever the case of `name`
+#[derive(Clone, Debug)]
+pub struct RequestHeader;
+impl ReiCallable for RequestHeader {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let name = str_arg(&args[1], "name", &context)?;
+        Ok(field(
+            &field(&args[0], "headers"),
+            &http::header_field(name),
+        ))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>header".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// query(request, name) -> string or null
+#[derive(Clone, Debug)]
+pub struct RequestQuery;
+impl ReiCallable for RequestQuery {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let name = str_arg(&args[1], "name", &context)?;
+        Ok(field(&field(&args[0], "query"), name))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>query".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// set_header(response, name, value) -> null, replaces the headers of that
+/// name on a response
+#[derive(Clone, Debug)]
+pub struct SetHeader;
+impl ReiCallable for SetHeader {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Vec(headers) = field(&args[0], "headers") else {
+            return Err(native_error("Expected response".into(), context));
+        };
+        let name = str_arg(&args[1], "name", &context)?;
+
+        let mut headers = headers.borrow_mut();
+        headers.retain(|header| {
+            let header = header.to_string();
+            let existing = header.split(':').next().unwrap_or("").trim();
+            !existing.eq_ignore_ascii_case(name)
+        });
+        headers.push(Object::Str(format!("{}: {}", name, args[2])));
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>set_header".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Answers every request on `stream` until either side closes it.
+fn serve_connection(
+    router: &Rc<RefCell<token::Router>>,
+    mut stream: TcpStream,
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) {
+    let stopping = router.borrow().stopping.clone();
+    let Ok(reader) = Watched::new(&stream, stopping) else {
+        return;
+    };
+    let mut incoming = Incoming::new(reader, vec![]);
+    loop {
+        match incoming.request() {
+            Ok(request) => {
+                let request = http::request_object(request, context.clone());
+                if !respond_blocking(router, &mut stream, &request, interpreter, context.clone()) {
+                    break;
+                }
+            }
+            Err(ReadError::Invalid(status, msg)) => {
+                reject(&mut stream, status, &msg);
+                break;
+            }
+            Err(ReadError::Closed) => break,
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// serve(router, addr) -> null, answers one connection at a time until the
+/// router is stopped
+#[derive(Clone, Debug)]
+pub struct RouterServe;
+impl ReiCallable for RouterServe {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let addr = str_arg(&args[1], "address", &context)?;
+        let listener =
+            TcpListener::bind(addr).map_err(|e| native_error(e.to_string(), context.clone()))?;
+
+        let stopping = router.borrow().stopping.clone();
+        let serving = router.borrow().serving.clone();
+        start_serving(&serving);
+        let served = loop {
+            match accept_until_stopped(&listener, &stopping) {
+                Ok(Some(stream)) => serve_connection(&router, stream, interpreter, context.clone()),
+                Ok(None) => break Ok(Object::Null),
+                Err(e) => break Err(native_error(e.to_string(), context.clone())),
+            }
+        };
+        finish_serving(&serving);
+
+        served
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>serve".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define("_NET_router".into(), Object::Callable(Rc::new(Router)))?;
+    env.define(
+        "_NET_router_get".into(),
+        Object::Callable(Rc::new(RouterGet)),
+    )?;
+    env.define(
+        "_NET_router_add".into(),
+        Object::Callable(Rc::new(RouterAdd)),
+    )?;
+    env.define(
+        "_NET_router_before".into(),
+        Object::Callable(Rc::new(RouterBefore)),
+    )?;
+    env.define(
+        "_NET_router_after".into(),
+        Object::Callable(Rc::new(RouterAfter)),
+    )?;
+    env.define(
+        "_NET_router_not_found".into(),
+        Object::Callable(Rc::new(RouterNotFound)),
+    )?;
+    env.define(
+        "_NET_router_mount".into(),
+        Object::Callable(Rc::new(RouterMount)),
+    )?;
+    env.define(
+        "_NET_router_cors".into(),
+        Object::Callable(Rc::new(RouterCors)),
+    )?;
+    env.define(
+        "_NET_router_stop".into(),
+        Object::Callable(Rc::new(RouterStop)),
+    )?;
+    env.define(
+        "_NET_accept_until_stopped".into(),
+        Object::Callable(Rc::new(AcceptUntilStopped)),
+    )?;
+    env.define(
+        "_NET_read_request".into(),
+        Object::Callable(Rc::new(ReadRequest)),
+    )?;
+    env.define(
+        "_NET_respond".into(),
+        Object::Callable(Rc::new(RouterRespond)),
+    )?;
+    env.define(
+        "_NET_header".into(),
+        Object::Callable(Rc::new(RequestHeader)),
+    )?;
+    env.define("_NET_query".into(), Object::Callable(Rc::new(RequestQuery)))?;
+    env.define(
+        "_NET_set_header".into(),
+        Object::Callable(Rc::new(SetHeader)),
+    )?;
+    env.define("_NET_serve".into(), Object::Callable(Rc::new(RouterServe)))?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How much a read asks the OS for when it doesn't know how much it needs
+const CHUNK: usize = 4096;
+/// Most one read returns whatever the script allows, enough for any
+/// datagram
+pub const MAX_READ: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum Endpoint {
+    Stream(TcpStream),
+    Listener(TcpListener),
+    Udp(UdpSocket),
+}
+
+/// Socket a script holds. It's closed by `close`, or once nothing refers to
+/// it anymore.
+#[derive(Debug)]
+pub struct Socket {
+    /// None once closed
+    endpoint: RefCell<Option<Endpoint>>,
+    /// Bytes read past what was asked for, the next read starts with them.
+    /// Shared with the jobs reading the socket off the interpreter thread.
+    pub buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Socket {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Socket {
+            endpoint: RefCell::new(Some(endpoint)),
+            buffer: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Second handle on the connection, so blocking on it doesn't keep the
+    /// socket borrowed.
+    pub fn stream(&self) -> Result<TcpStream, String> {
+        match &*self.endpoint.borrow() {
+            Some(Endpoint::Stream(stream)) => stream.try_clone().map_err(|e| e.to_string()),
+            Some(_) => Err("Expected a TCP connection".into()),
+            None => Err("Socket is closed".into()),
+        }
+    }
+
+    /// Second handle on the listener, for the same reason.
+    pub fn listener(&self) -> Result<TcpListener, String> {
+        match &*self.endpoint.borrow() {
+            Some(Endpoint::Listener(listener)) => listener.try_clone().map_err(|e| e.to_string()),
+            Some(_) => Err("Expected a TCP listener".into()),
+            None => Err("Socket is closed".into()),
+        }
+    }
+
+    pub fn udp(&self) -> Result<UdpSocket, String> {
+        match &*self.endpoint.borrow() {
+            Some(Endpoint::Udp(socket)) => socket.try_clone().map_err(|e| e.to_string()),
+            Some(_) => Err("Expected a UDP socket".into()),
+            None => Err("Socket is closed".into()),
+        }
+    }
+
+    /// Shuts a connection down, which also ends reads blocked on it.
+    /// Closing twice is fine.
+    pub fn close(&self) {
+        if let Some(Endpoint::Stream(stream)) = self.endpoint.borrow_mut().take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.buffer.lock().unwrap().clear();
+    }
+
+    /// Limits how long reads and writes wait, None waits forever.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), String> {
+        let result = match &*self.endpoint.borrow() {
+            Some(Endpoint::Stream(stream)) => stream
+                .set_read_timeout(timeout)
+                .and_then(|_| stream.set_write_timeout(timeout)),
+            Some(Endpoint::Udp(socket)) => socket
+                .set_read_timeout(timeout)
+                .and_then(|_| socket.set_write_timeout(timeout)),
+            Some(Endpoint::Listener(_)) => return Err("Listeners have no timeout".into()),
+            None => return Err("Socket is closed".into()),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddr, String> {
+        match &*self.endpoint.borrow() {
+            Some(Endpoint::Stream(stream)) => stream.peer_addr().map_err(|e| e.to_string()),
+            Some(Endpoint::Udp(socket)) => socket.peer_addr().map_err(|e| e.to_string()),
+            Some(Endpoint::Listener(_)) => Err("Listeners have no peer".into()),
+            None => Err("Socket is closed".into()),
+        }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        let result = match &*self.endpoint.borrow() {
+            Some(Endpoint::Stream(stream)) => stream.local_addr(),
+            Some(Endpoint::Listener(listener)) => listener.local_addr(),
+            Some(Endpoint::Udp(socket)) => socket.local_addr(),
+            None => return Err("Socket is closed".into()),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Up to `max` bytes, what's buffered first, at most MAX_READ of them.
+    /// Empty once the peer has closed the connection.
+    pub fn read(&self, max: usize) -> Result<Vec<u8>, String> {
+        let mut stream = self.stream()?;
+        read_buffered(&mut stream, &self.buffer, max).map_err(|e| describe(&e))
+    }
+
+    /// Exactly `len` bytes, failing if the connection ends before.
+    pub fn read_exact(&self, len: usize) -> Result<Vec<u8>, String> {
+        let mut stream = self.stream()?;
+        let mut buffer = self.buffer.lock().unwrap();
+        while buffer.len() < len {
+            let got = fill(&mut stream, &mut buffer).map_err(|e| describe(&e))?;
+            if got == 0 {
+                return Err(format!(
+                    "Connection closed after {} of {} bytes",
+                    buffer.len(),
+                    len
+                ));
+            }
+        }
+        Ok(buffer.drain(..len).collect())
+    }
+
+    /// Bytes up to and including `delimiter`, or whatever was left when
+    /// the connection ended without one. Fails once `max` bytes arrived
+    /// without it, rather than buffering a peer that never sends one.
+    pub fn read_until(&self, delimiter: &[u8], max: usize) -> Result<Vec<u8>, String> {
+        if delimiter.is_empty() {
+            return Err("Delimiter is empty".into());
+        }
+        let mut stream = self.stream()?;
+        let mut buffer = self.buffer.lock().unwrap();
+        let mut searched = 0;
+        loop {
+            if let Some(at) = buffer[searched..]
+                .windows(delimiter.len())
+                .position(|window| window == delimiter)
+            {
+                let end = searched + at + delimiter.len();
+                if end <= max {
+                    return Ok(buffer.drain(..end).collect());
+                }
+            }
+            if buffer.len() >= max {
+                return Err(format!("No delimiter in the first {} bytes", max));
+            }
+            // A delimiter may straddle what's read next
+            searched = buffer.len().saturating_sub(delimiter.len() - 1);
+            if fill(&mut stream, &mut buffer).map_err(|e| describe(&e))? == 0 {
+                return Ok(buffer.drain(..).collect());
+            }
+        }
+    }
+
+    pub fn send(&self, data: &[u8]) -> Result<(), String> {
+        self.stream()?.write_all(data).map_err(|e| describe(&e))
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl fmt::Display for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match &*self.endpoint.borrow() {
+            Some(Endpoint::Stream(_)) => "tcp",
+            Some(Endpoint::Listener(_)) => "tcp listener",
+            Some(Endpoint::Udp(_)) => "udp",
+            None => return write!(f, "<socket | closed>"),
+        };
+        match self.local_addr() {
+            Ok(addr) => write!(f, "<socket {} | {}>", kind, addr),
+            Err(_) => write!(f, "<socket {}>", kind),
+        }
+    }
+}
+
+/// Reads what `read` returns, for jobs holding their own handle. One read
+/// returns at most MAX_READ bytes.
+pub fn read_buffered(
+    stream: &mut TcpStream,
+    buffer: &Mutex<Vec<u8>>,
+    max: usize,
+) -> io::Result<Vec<u8>> {
+    let mut buffer = buffer.lock().unwrap();
+    if !buffer.is_empty() {
+        let len = max.min(buffer.len());
+        return Ok(buffer.drain(..len).collect());
+    }
+    let mut data = vec![0u8; max.min(MAX_READ)];
+    let n = stream.read(&mut data)?;
+    data.truncate(n);
+    Ok(data)
+}
+
+/// Appends one read to `buffer`, 0 once the connection is closed.
+fn fill(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> io::Result<usize> {
+    let mut chunk = [0u8; CHUNK];
+    let n = stream.read(&mut chunk)?;
+    buffer.extend_from_slice(&chunk[..n]);
+    Ok(n)
+}
+
+/// A timeout shows up as WouldBlock on some platforms and TimedOut on
+/// others.
+pub fn describe(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => "Timed out".into(),
+        _ => error.to_string(),
+    }
+}
@@ -0,0 +1,602 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Error, Message, WebSocket};
+
+use crate::backend::environment::Environment;
+use crate::backend::event_loop::{JobResult, ReiTask};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::http::{self, record};
+use crate::backend::native::net::net::{io_error, native_error, socket_arg};
+use crate::backend::native::net::server::{
+    field, fill_params, handler_task, router_arg, should_stop, str_arg, str_field, STOP_POLL,
+};
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::ExecContext;
+use crate::crux::token::{Object, WsRoute};
+
+/// One end of a WebSocket, the server's or the client's.
+#[derive(Debug)]
+pub struct WsConnection {
+    /// Reads take the lock for STOP_POLL at most, so sends from the
+    /// interpreter thread get through while a job waits for a message
+    socket: Arc<Mutex<WebSocket<TcpStream>>>,
+    /// What the server calls, none on the client's end
+    route: Option<WsRoute>,
+    /// The router's, reads give up once it's set
+    stopping: Arc<AtomicBool>,
+}
+
+impl WsConnection {
+    fn new(
+        socket: WebSocket<TcpStream>,
+        route: Option<WsRoute>,
+        stopping: Arc<AtomicBool>,
+    ) -> io::Result<Self> {
+        socket.get_ref().set_read_timeout(Some(STOP_POLL))?;
+        Ok(WsConnection {
+            socket: Arc::new(Mutex::new(socket)),
+            route,
+            stopping,
+        })
+    }
+
+    fn send(&self, message: Message) -> Result<(), String> {
+        self.socket
+            .lock()
+            .unwrap()
+            .send(message)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Starts the closing handshake, the peer's answer ends `next_message`.
+    fn close(&self) {
+        let mut socket = self.socket.lock().unwrap();
+        let _ = socket.close(None);
+        let _ = socket.flush();
+    }
+}
+
+/// Waits for the next text or binary message, None once the connection is
+/// closed or the server stops. Pings are answered along the way.
+fn next_message(
+    socket: &Mutex<WebSocket<TcpStream>>,
+    stopping: &AtomicBool,
+) -> Result<Option<Message>, String> {
+    loop {
+        let mut guard = socket.lock().unwrap();
+        match guard.read() {
+            Ok(message @ (Message::Text(_) | Message::Binary(_))) => return Ok(Some(message)),
+            Ok(Message::Close(_)) => {
+                // Sends the answer to the peer's close
+                let _ = guard.flush();
+                return Ok(None);
+            }
+            Ok(_) => {}
+            Err(Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                if should_stop(stopping) {
+                    let _ = guard.close(None);
+                    let _ = guard.flush();
+                    return Ok(None);
+                }
+                drop(guard);
+                // Lets a waiting send take the lock
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err(Error::ConnectionClosed | Error::AlreadyClosed) => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn message_object(message: Message) -> Object {
+    match message {
+        Message::Binary(data) => Object::Bytes(Rc::new(RefCell::new(data.to_vec()))),
+        other => Object::Str(other.into_text().map(|t| t.to_string()).unwrap_or_default()),
+    }
+}
+
+/// The connection behind an object `connection_object` built.
+fn connection_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<WsConnection>, ExecSignal> {
+    if let Object::Callable(send) = field(object, "send") {
+        if let Some(send) = send.as_any().downcast_ref::<WsSend>() {
+            return Ok(send.connection.clone());
+        }
+    }
+    Err(native_error("Expected WebSocket".into(), context.clone()))
+}
+
+/// What scripts hold: `send(data)` and `close()`, plus `receive()` on the
+/// client's end and the upgraded `request` on the server's.
+fn connection_object(
+    connection: WsConnection,
+    request: Option<Object>,
+    context: Rc<RefCell<ExecContext>>,
+) -> Object {
+    let connection = Rc::new(connection);
+    let mut fields = vec![
+        (
+            "send".to_string(),
+            Object::Callable(Rc::new(WsSend {
+                connection: connection.clone(),
+            })),
+        ),
+        (
+            "close".to_string(),
+            Object::Callable(Rc::new(WsClose {
+                connection: connection.clone(),
+            })),
+        ),
+    ];
+    match request {
+        Some(request) => fields.push(("request".to_string(), request)),
+        None => fields.push((
+            "receive".to_string(),
+            Object::Callable(Rc::new(WsReceive { connection })),
+        )),
+    }
+    record("WebSocket", fields, context)
+}
+
+fn optional_handler(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Option<Rc<dyn ReiCallable>>, ExecSignal> {
+    match object {
+        Object::Callable(handler) => Ok(Some(handler.clone())),
+        Object::Null => Ok(None),
+        _ => Err(native_error(
+            "Expected function or null".into(),
+            context.clone(),
+        )),
+    }
+}
+
+/// Whether the request asks to switch to the WebSocket protocol.
+fn is_upgrade(request: &Object) -> bool {
+    let headers = field(request, "headers");
+    str_field(request, "method") == "GET"
+        && str_field(&headers, "upgrade").eq_ignore_ascii_case("websocket")
+        && str_field(&headers, "connection")
+            .to_ascii_lowercase()
+            .contains("upgrade")
+        && !str_field(&headers, "sec_websocket_key").is_empty()
+}
+
+/// websocket(router, path, on_open, on_message, on_close) -> null, any
+/// handler may be null
+#[derive(Clone, Debug)]
+pub struct RouterWebSocket;
+impl ReiCallable for RouterWebSocket {
+    fn arity(&self) -> usize {
+        5
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let route = WsRoute {
+            pattern: str_arg(&args[1], "path", &context)?.to_string(),
+            on_open: optional_handler(&args[2], &context)?,
+            on_message: optional_handler(&args[3], &context)?,
+            on_close: optional_handler(&args[4], &context)?,
+        };
+        router.borrow_mut().websockets.push(route);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>websocket".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// ws_accept(router, conn, request) -> WebSocket, or null when the request
+/// isn't an upgrade to one of the router's WebSocket paths
+#[derive(Clone, Debug)]
+pub struct WsAccept;
+impl ReiCallable for WsAccept {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let router = router_arg(&args[0], &context)?;
+        let socket = socket_arg(&args[1], &context)?;
+        let request = &args[2];
+        if !is_upgrade(request) {
+            return Ok(Object::Null);
+        }
+
+        let path = str_field(request, "path");
+        let found = router.borrow().websockets.iter().find_map(|route| {
+            http::match_route(&route.pattern, &path).map(|params| (route.clone(), params))
+        });
+        let Some((route, params)) = found else {
+            return Ok(Object::Null);
+        };
+        fill_params(request, params);
+
+        let key = str_field(&field(request, "headers"), "sec_websocket_key");
+        let mut stream = socket
+            .stream()
+            .map_err(|msg| native_error(msg, context.clone()))?;
+        let handshake = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            derive_accept_key(key.as_bytes())
+        );
+        stream
+            .write_all(handshake.as_bytes())
+            .map_err(|e| io_error(e.to_string(), context.clone()))?;
+
+        // Frames the client sent right after its request were read with it
+        let leftover = std::mem::take(&mut *socket.buffer.lock().unwrap());
+        let websocket = WebSocket::from_partially_read(stream, leftover, Role::Server, None);
+        let stopping = router.borrow().stopping.clone();
+        let connection = WsConnection::new(websocket, Some(route.clone()), stopping)
+            .map_err(|e| io_error(e.to_string(), context.clone()))?;
+        Ok(connection_object(
+            connection,
+            Some(request.clone()),
+            context,
+        ))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_accept".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// ws_receive(ws) -> task resolving to the next message, a string or
+/// bytes, or null once the WebSocket is closed
+#[derive(Clone, Debug)]
+pub struct WsReceiveAsync;
+impl ReiCallable for WsReceiveAsync {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let connection = connection_arg(&args[0], &context)?;
+        let socket = connection.socket.clone();
+        let stopping = connection.stopping.clone();
+
+        let job = ReiTask::job(
+            "ws_receive",
+            move || -> JobResult {
+                match next_message(&socket, &stopping)? {
+                    Some(message) => Ok(Box::new(move || message_object(message))),
+                    None => Ok(Box::new(|| Object::Null)),
+                }
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_receive".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Task running one of the handlers of the route `ws` came in on, or null
+/// when it has none.
+fn ws_handler(
+    pick: fn(&WsRoute) -> Option<Rc<dyn ReiCallable>>,
+    args: &[Object],
+    interpreter: &mut Interpreter,
+    context: Rc<RefCell<ExecContext>>,
+) -> Result<Object, ExecSignal> {
+    let connection = connection_arg(&args[0], &context)?;
+    let Some(handler) = connection.route.as_ref().and_then(pick) else {
+        return Ok(Object::Null);
+    };
+    let request = field(&args[0], "request");
+    Ok(handler_task(
+        handler,
+        args.to_vec(),
+        request,
+        interpreter,
+        context,
+    ))
+}
+
+/// ws_opened(ws) -> task calling on_open
+#[derive(Clone, Debug)]
+pub struct WsOpened;
+impl ReiCallable for WsOpened {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        ws_handler(|route| route.on_open.clone(), args, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_opened".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// ws_message(ws, message) -> task calling on_message
+#[derive(Clone, Debug)]
+pub struct WsMessage;
+impl ReiCallable for WsMessage {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        ws_handler(|route| route.on_message.clone(), args, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_message".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// ws_closed(ws) -> task calling on_close
+#[derive(Clone, Debug)]
+pub struct WsClosed;
+impl ReiCallable for WsClosed {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        ws_handler(|route| route.on_close.clone(), args, interpreter, context)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_closed".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// ws_connect(url) -> WebSocket, for ws:// urls
+#[derive(Clone, Debug)]
+pub struct WsConnect;
+impl ReiCallable for WsConnect {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let url = str_arg(&args[0], "url", &context)?;
+        let request = url
+            .into_client_request()
+            .map_err(|e| native_error(format!("Invalid url '{}': {}", url, e), context.clone()))?;
+        let uri = request.uri();
+        if uri.scheme_str() != Some("ws") {
+            return Err(native_error(
+                format!("Expected a ws:// url, got '{}'", url),
+                context,
+            ));
+        }
+        let addr = format!(
+            "{}:{}",
+            uri.host().unwrap_or_default(),
+            uri.port_u16().unwrap_or(80)
+        );
+
+        let fail = |msg: String| {
+            io_error(
+                format!("Failed to connect to '{}': {}", url, msg),
+                context.clone(),
+            )
+        };
+        let stream = TcpStream::connect(&addr).map_err(|e| fail(e.to_string()))?;
+        let (websocket, _) =
+            tungstenite::client::client(request, stream).map_err(|e| fail(e.to_string()))?;
+        // Nothing stops a client's reads but the peer
+        let connection = WsConnection::new(websocket, None, Arc::new(AtomicBool::new(false)))
+            .map_err(|e| fail(e.to_string()))?;
+        Ok(connection_object(connection, None, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>ws_connect".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// send(data) -> null, a string goes as a text message, bytes as a binary one
+#[derive(Debug)]
+pub struct WsSend {
+    connection: Rc<WsConnection>,
+}
+impl ReiCallable for WsSend {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let message = match &args[0] {
+            Object::Str(text) => Message::text(text.clone()),
+            Object::Bytes(data) => Message::binary(data.borrow().clone()),
+            _ => {
+                return Err(native_error(
+                    "Expected string or bytes to send".into(),
+                    context,
+                ))
+            }
+        };
+        self.connection
+            .send(message)
+            .map_err(|msg| io_error(msg, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>send".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// close() -> null, the other end is told and on_close is called
+#[derive(Debug)]
+pub struct WsClose {
+    connection: Rc<WsConnection>,
+}
+impl ReiCallable for WsClose {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        self.connection.close();
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>close".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// receive() -> the next message, a string or bytes, or null once closed
+#[derive(Debug)]
+pub struct WsReceive {
+    connection: Rc<WsConnection>,
+}
+impl ReiCallable for WsReceive {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let message = next_message(&self.connection.socket, &self.connection.stopping)
+            .map_err(|msg| io_error(msg, context))?;
+        Ok(message.map(message_object).unwrap_or(Object::Null))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>receive".into()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define(
+        "_NET_router_websocket".into(),
+        Object::Callable(Rc::new(RouterWebSocket)),
+    )?;
+    env.define("_NET_ws_accept".into(), Object::Callable(Rc::new(WsAccept)))?;
+    env.define(
+        "_NET_ws_receive".into(),
+        Object::Callable(Rc::new(WsReceiveAsync)),
+    )?;
+    env.define("_NET_ws_opened".into(), Object::Callable(Rc::new(WsOpened)))?;
+    env.define(
+        "_NET_ws_message".into(),
+        Object::Callable(Rc::new(WsMessage)),
+    )?;
+    env.define("_NET_ws_closed".into(), Object::Callable(Rc::new(WsClosed)))?;
+    env.define(
+        "_NET_ws_connect".into(),
+        Object::Callable(Rc::new(WsConnect)),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{self, ChildStderr, ChildStdin, ChildStdout, ExitStatus};
+
+/// Process a script spawned. Dropping it neither waits for it nor kills it.
+#[derive(Debug)]
+pub struct Child {
+    process: RefCell<process::Child>,
+    /// None unless piped, or once closed
+    stdin: RefCell<Option<ChildStdin>>,
+    stdout: RefCell<Option<BufReader<ChildStdout>>>,
+    stderr: RefCell<Option<BufReader<ChildStderr>>>,
+    /// Set once waited for
+    status: RefCell<Option<ExitStatus>>,
+}
+
+impl Child {
+    pub fn new(mut process: process::Child) -> Self {
+        Child {
+            stdin: RefCell::new(process.stdin.take()),
+            stdout: RefCell::new(process.stdout.take().map(BufReader::new)),
+            stderr: RefCell::new(process.stderr.take().map(BufReader::new)),
+            process: RefCell::new(process),
+            status: RefCell::new(None),
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.process.borrow().id()
+    }
+
+    pub fn write(&self, data: &[u8]) -> Result<(), String> {
+        match &mut *self.stdin.borrow_mut() {
+            Some(stdin) => stdin
+                .write_all(data)
+                .and_then(|_| stdin.flush())
+                .map_err(|e| e.to_string()),
+            None => Err("stdin isn't piped".into()),
+        }
+    }
+
+    /// The child reads the end of its input once stdin is closed.
+    pub fn close_stdin(&self) {
+        self.stdin.borrow_mut().take();
+    }
+
+    /// Next line of stdout without its line break, None at the end.
+    pub fn read_line(&self) -> Result<Option<String>, String> {
+        let mut stdout = self.stdout.borrow_mut();
+        let Some(stdout) = stdout.as_mut() else {
+            return Err("stdout isn't piped".into());
+        };
+        let mut line = vec![];
+        let n = stdout
+            .read_until(b'\n', &mut line)
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(None);
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&line).to_string()))
+    }
+
+    /// What's left of stdout, once the child closes it.
+    pub fn read_stdout(&self) -> Result<String, String> {
+        read_rest(self.stdout.borrow_mut().as_mut(), "stdout")
+    }
+
+    /// What's left of stderr, once the child closes it.
+    pub fn read_stderr(&self) -> Result<String, String> {
+        read_rest(self.stderr.borrow_mut().as_mut(), "stderr")
+    }
+
+    /// Closes stdin and waits for the child to exit. Output left unread in
+    /// a full pipe keeps it from exiting, so read it first.
+    pub fn wait(&self) -> Result<ExitStatus, String> {
+        if let Some(status) = *self.status.borrow() {
+            return Ok(status);
+        }
+        self.close_stdin();
+        let status = self
+            .process
+            .borrow_mut()
+            .wait()
+            .map_err(|e| e.to_string())?;
+        *self.status.borrow_mut() = Some(status);
+        Ok(status)
+    }
+
+    /// Killing a child that already exited is fine.
+    pub fn kill(&self) -> Result<(), String> {
+        if self.status.borrow().is_some() {
+            return Ok(());
+        }
+        match self.process.borrow_mut().kill() {
+            Err(e) if e.kind() != io::ErrorKind::InvalidInput => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Child {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.status.borrow() {
+            Some(status) => write!(f, "<process {} | {}>", self.pid(), status),
+            None => write!(f, "<process {}>", self.pid()),
+        }
+    }
+}
+
+fn read_rest<R: Read>(pipe: Option<&mut R>, name: &str) -> Result<String, String> {
+    let Some(pipe) = pipe else {
+        return Err(format!("{} isn't piped", name));
+    };
+    let mut data = vec![];
+    pipe.read_to_end(&mut data).map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
@@ -1 +1,3 @@
+pub mod child;
 pub mod process;
+pub mod signal;
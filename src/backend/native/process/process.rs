@@ -1,16 +1,183 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex, OnceLock};
 use std::{any::Any, cell::RefCell, env, process, rc::Rc, thread, time::Duration};
 
 use crate::backend::environment::Environment;
+use crate::backend::event_loop::ReiTask;
 use crate::backend::exec_signal::{
     runtime_error::{RuntimeError, RuntimeErrorType},
     ExecSignal,
 };
 use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::http::record;
+use crate::backend::native::process::child::Child;
+use crate::backend::native::process::signal;
 use crate::backend::rei_callable::ReiCallable;
 use crate::backend::stack_trace::ExecContext;
 
+use crate::crux::output;
 use crate::crux::token::Object;
 
+/// The script's path and the arguments after it on the command line
+static ARGV: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Variables scripts set, None for removed ones. They're given to child
+/// processes instead of changing the process's own environment, which
+/// other threads read through libc while scripts run.
+static ENV: LazyLock<Mutex<HashMap<String, Option<String>>>> = LazyLock::new(Default::default);
+
+/// Called by the CLI before it runs a script.
+pub fn set_argv(argv: Vec<String>) {
+    let _ = ARGV.set(argv);
+}
+
+/// A variable as scripts see it, with what they set applied.
+fn env_var(name: &str) -> Option<String> {
+    match ENV.lock().unwrap().get(name) {
+        Some(value) => value.clone(),
+        None => env::var_os(name).map(|value| value.to_string_lossy().to_string()),
+    }
+}
+
+/// Gives a child the variables scripts set and removed.
+fn apply_env(command: &mut Command) {
+    for (name, value) in ENV.lock().unwrap().iter() {
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name),
+        };
+    }
+}
+
+fn native_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::ErrorInNativeFn { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+/// Failures of the child process rather than of the script, which it may
+/// want to catch.
+fn process_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+fn child_arg(object: &Object, context: &Rc<RefCell<ExecContext>>) -> Result<Rc<Child>, ExecSignal> {
+    match object {
+        Object::Child(child) => Ok(child.clone()),
+        _ => Err(native_error("Expected process".into(), context.clone())),
+    }
+}
+
+/// Field of the options record, null when it's missing.
+fn option(opts: &Object, name: &str) -> Object {
+    match opts {
+        Object::Instance(instance) => instance
+            .borrow()
+            .fields
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or(Object::Null),
+        _ => Object::Null,
+    }
+}
+
+fn stdio(
+    opts: &Object,
+    name: &str,
+    default: &str,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Stdio, ExecSignal> {
+    let mode = match option(opts, name) {
+        Object::Null => default.to_string(),
+        Object::Str(mode) => mode,
+        _ => {
+            return Err(native_error(
+                format!("Expected Str ({})", name),
+                context.clone(),
+            ))
+        }
+    };
+    match mode.as_str() {
+        "inherit" => Ok(Stdio::inherit()),
+        "pipe" => Ok(Stdio::piped()),
+        "null" => Ok(Stdio::null()),
+        _ => Err(native_error(
+            format!("{} is 'inherit', 'pipe' or 'null', not '{}'", name, mode),
+            context.clone(),
+        )),
+    }
+}
+
+/// Builds the command `spawn` and `run` start from `cmd`, a Vec of args
+/// and the options record, any of whose fields may be missing: `cwd`,
+/// `env` (a record of variables, null removes one), `clear_env` and the
+/// `stdin`, `stdout` and `stderr` modes. `defaults` are the modes of the
+/// ones not given.
+fn command(
+    arguments: &[Object],
+    defaults: [&str; 3],
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Command, ExecSignal> {
+    let Object::Str(cmd) = &arguments[0] else {
+        return Err(native_error(
+            "expected Str (command)".into(),
+            context.clone(),
+        ));
+    };
+    let mut command = Command::new(cmd);
+    match &arguments[1] {
+        Object::Vec(args) => {
+            command.args(args.borrow().iter().map(|arg| arg.to_string()));
+        }
+        Object::Null => {}
+        _ => return Err(native_error("expected Vec (args)".into(), context.clone())),
+    }
+
+    let opts = &arguments[2];
+    if !matches!(opts, Object::Instance(_) | Object::Null) {
+        return Err(native_error(
+            "expected instance (options)".into(),
+            context.clone(),
+        ));
+    }
+    match option(opts, "cwd") {
+        Object::Str(dir) => {
+            command.current_dir(dir);
+        }
+        Object::Null => {}
+        _ => return Err(native_error("expected Str (cwd)".into(), context.clone())),
+    }
+    apply_env(&mut command);
+    if let Object::Bool(true) = option(opts, "clear_env") {
+        command.env_clear();
+    }
+    match option(opts, "env") {
+        Object::Instance(vars) => {
+            for (name, value) in vars.borrow().fields.borrow().iter() {
+                match value {
+                    Object::Null => command.env_remove(name),
+                    value => command.env(name, value.to_string()),
+                };
+            }
+        }
+        Object::Null => {}
+        _ => {
+            return Err(native_error(
+                "expected instance (env)".into(),
+                context.clone(),
+            ))
+        }
+    }
+    command
+        .stdin(stdio(opts, "stdin", defaults[0], context)?)
+        .stdout(stdio(opts, "stdout", defaults[1], context)?)
+        .stderr(stdio(opts, "stderr", defaults[2], context)?);
+    Ok(command)
+}
+
 #[derive(Clone, Debug)]
 pub struct ProcExit;
 impl ReiCallable for ProcExit {
@@ -25,7 +192,7 @@ impl ReiCallable for ProcExit {
         _context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
         if let Some(Object::Number(code)) = arguments.get(0) {
-            process::exit(*code as i32);
+            output::exit(*code as i32);
         }
 
         let err = RuntimeErrorType::ErrorInNativeFn {
@@ -174,6 +341,584 @@ impl ReiCallable for ProcSetDir {
     }
 }
 
+/// Runs a shell command without blocking the event loop. The task resolves
+/// to its standard output, or fails when it exits with a non-zero code.
+#[derive(Clone, Debug)]
+pub struct ProcOutput;
+impl ReiCallable for ProcOutput {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Some(Object::Str(command)) = arguments.first() else {
+            let err = RuntimeErrorType::ErrorInNativeFn {
+                msg: "expected Str (command)".to_string(),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(err, context)));
+        };
+
+        let command = command.clone();
+        let job = ReiTask::job(
+            "output",
+            move || {
+                let (shell, flag) = if cfg!(windows) {
+                    ("cmd", "/C")
+                } else {
+                    ("sh", "-c")
+                };
+                let mut shell = process::Command::new(shell);
+                apply_env(&mut shell);
+                let output = shell
+                    .args([flag, &command])
+                    .output()
+                    .map_err(|e| format!("failed to run '{}': {}", command, e))?;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(format!(
+                        "'{}' failed ({}): {}",
+                        command,
+                        output.status,
+                        stderr.trim_end()
+                    ));
+                }
+                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                Ok(Box::new(move || Object::Str(stdout)))
+            },
+            context,
+        );
+        Ok(interpreter.event_loop.spawn(job))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_output".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Starts a process and returns right away. Its stdin, stdout and stderr
+/// are the script's unless the options pipe them.
+#[derive(Clone, Debug)]
+pub struct ProcSpawn;
+impl ReiCallable for ProcSpawn {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let mut command = command(arguments, ["inherit", "inherit", "inherit"], &context)?;
+        let child = command.spawn().map_err(|e| {
+            process_error(format!("failed to run '{}': {}", arguments[0], e), context)
+        })?;
+        Ok(Object::Child(Rc::new(Child::new(child))))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_spawn".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Runs a process to the end. The result has its exit `code` (null when
+/// a signal ended it), `ok` and the `stdout` and `stderr` it printed,
+/// which are captured unless the options say otherwise. An `input` option
+/// is written to its stdin.
+#[derive(Clone, Debug)]
+pub struct ProcRun;
+impl ReiCallable for ProcRun {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let input = match option(&arguments[2], "input") {
+            Object::Str(text) => Some(text.into_bytes()),
+            Object::Bytes(data) => Some(data.borrow().clone()),
+            Object::Null => None,
+            _ => {
+                return Err(native_error(
+                    "expected Str or Bytes (input)".into(),
+                    context,
+                ))
+            }
+        };
+        let stdin = if input.is_some() { "pipe" } else { "null" };
+        let mut command = command(arguments, [stdin, "pipe", "pipe"], &context)?;
+
+        let failed = |e: std::io::Error| {
+            process_error(
+                format!("failed to run '{}': {}", arguments[0], e),
+                context.clone(),
+            )
+        };
+        let mut child = command.spawn().map_err(failed)?;
+        // Written off this thread, the child may fill its stdout first
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            });
+        }
+        let output = child.wait_with_output().map_err(failed)?;
+
+        let code = match output.status.code() {
+            Some(code) => Object::Number(code as f64),
+            None => Object::Null,
+        };
+        let text = |data: &[u8]| Object::Str(String::from_utf8_lossy(data).to_string());
+        let fields = vec![
+            ("code".to_string(), code),
+            ("ok".to_string(), Object::Bool(output.status.success())),
+            ("stdout".to_string(), text(&output.stdout)),
+            ("stderr".to_string(), text(&output.stderr)),
+        ];
+        Ok(record("Output", fields, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_run".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcChildPid;
+impl ReiCallable for ProcChildPid {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        Ok(Object::Number(child.pid() as f64))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_child_pid".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Writes a string or bytes to a child's piped stdin.
+#[derive(Clone, Debug)]
+pub struct ProcWrite;
+impl ReiCallable for ProcWrite {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        let result = match &arguments[1] {
+            Object::Str(text) => child.write(text.as_bytes()),
+            Object::Bytes(data) => child.write(&data.borrow()),
+            _ => return Err(native_error("expected Str or Bytes".into(), context)),
+        };
+        result.map_err(|msg| process_error(msg, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_write".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcCloseStdin;
+impl ReiCallable for ProcCloseStdin {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        child_arg(&arguments[0], &context)?.close_stdin();
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_close_stdin".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Next line of a child's piped stdout, null once it's closed.
+#[derive(Clone, Debug)]
+pub struct ProcReadLine;
+impl ReiCallable for ProcReadLine {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        match child.read_line() {
+            Ok(line) => Ok(line.map(Object::Str).unwrap_or(Object::Null)),
+            Err(msg) => Err(process_error(msg, context)),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_read_line".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The rest of a child's piped stdout, read until it's closed.
+#[derive(Clone, Debug)]
+pub struct ProcRead;
+impl ReiCallable for ProcRead {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        match child.read_stdout() {
+            Ok(text) => Ok(Object::Str(text)),
+            Err(msg) => Err(process_error(msg, context)),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_read".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The rest of a child's piped stderr, read until it's closed.
+#[derive(Clone, Debug)]
+pub struct ProcReadErr;
+impl ReiCallable for ProcReadErr {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        match child.read_stderr() {
+            Ok(text) => Ok(Object::Str(text)),
+            Err(msg) => Err(process_error(msg, context)),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_read_err".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Waits for a child to exit, its exit code or null when a signal ended it.
+#[derive(Clone, Debug)]
+pub struct ProcWait;
+impl ReiCallable for ProcWait {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        let status = child.wait().map_err(|msg| process_error(msg, context))?;
+        match status.code() {
+            Some(code) => Ok(Object::Number(code as f64)),
+            None => Ok(Object::Null),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_wait".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcKill;
+impl ReiCallable for ProcKill {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let child = child_arg(&arguments[0], &context)?;
+        child.kill().map_err(|msg| process_error(msg, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_kill".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The script's path followed by the arguments it was given.
+#[derive(Clone, Debug)]
+pub struct ProcArgv;
+impl ReiCallable for ProcArgv {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let argv = ARGV
+            .get()
+            .map(|argv| argv.iter().cloned().map(Object::Str).collect())
+            .unwrap_or_default();
+        Ok(Object::Vec(Rc::new(RefCell::new(argv))))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_argv".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An environment variable, null when it isn't set.
+#[derive(Clone, Debug)]
+pub struct ProcEnvGet;
+impl ReiCallable for ProcEnvGet {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Str(name) = &arguments[0] else {
+            return Err(native_error("expected Str (name)".into(), context));
+        };
+        Ok(env_var(name).map_or(Object::Null, Object::Str))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_env_get".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Sets an environment variable for the script and the processes it
+/// starts, null removes it. The process's own environment stays as it was.
+#[derive(Clone, Debug)]
+pub struct ProcEnvSet;
+impl ReiCallable for ProcEnvSet {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Str(name) = &arguments[0] else {
+            return Err(native_error("expected Str (name)".into(), context));
+        };
+        // No process environment can hold these
+        if name.is_empty() || name.contains(['=', '\0']) {
+            return Err(native_error(
+                format!("invalid variable name '{}'", name),
+                context,
+            ));
+        }
+        let value = match &arguments[1] {
+            Object::Null => None,
+            Object::Str(value) if value.contains('\0') => {
+                return Err(native_error("value contains a NUL byte".into(), context));
+            }
+            value => Some(value.to_string()),
+        };
+        ENV.lock().unwrap().insert(name.clone(), value);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_env_set".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Record with a field per environment variable.
+#[derive(Clone, Debug)]
+pub struct ProcEnvVars;
+impl ReiCallable for ProcEnvVars {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let mut vars: HashMap<String, String> = env::vars_os()
+            .map(|(name, value)| {
+                let name = name.to_string_lossy().to_string();
+                (name, value.to_string_lossy().to_string())
+            })
+            .collect();
+        for (name, value) in ENV.lock().unwrap().iter() {
+            match value {
+                Some(value) => vars.insert(name.clone(), value.clone()),
+                None => vars.remove(name),
+            };
+        }
+        let fields = vars
+            .into_iter()
+            .map(|(name, value)| (name, Object::Str(value)))
+            .collect();
+        Ok(record("Env", fields, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_env_vars".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Calls a function between two statements whenever a signal arrives, null
+/// drops it.
+#[derive(Clone, Debug)]
+pub struct ProcOnSignal;
+impl ReiCallable for ProcOnSignal {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let Object::Str(name) = &arguments[0] else {
+            return Err(native_error("expected Str (signal name)".into(), context));
+        };
+        let handler = match &arguments[1] {
+            Object::Callable(handler) => Some(handler.clone()),
+            Object::Null => None,
+            _ => return Err(native_error("expected function or null".into(), context)),
+        };
+        signal::on_signal(name, handler).map_err(|msg| native_error(msg, context))?;
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_Proc_on_signal".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
     env.define(
         "_Proc_exit".to_string(),
@@ -192,6 +937,67 @@ pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
         "_Proc_set_dir".to_string(),
         Object::Callable(Rc::new(ProcSetDir)),
     )?;
+    env.define(
+        "_Proc_output".to_string(),
+        Object::Callable(Rc::new(ProcOutput)),
+    )?;
+    env.define(
+        "_Proc_spawn".to_string(),
+        Object::Callable(Rc::new(ProcSpawn)),
+    )?;
+    env.define("_Proc_run".to_string(), Object::Callable(Rc::new(ProcRun)))?;
+    env.define(
+        "_Proc_child_pid".to_string(),
+        Object::Callable(Rc::new(ProcChildPid)),
+    )?;
+    env.define(
+        "_Proc_write".to_string(),
+        Object::Callable(Rc::new(ProcWrite)),
+    )?;
+    env.define(
+        "_Proc_close_stdin".to_string(),
+        Object::Callable(Rc::new(ProcCloseStdin)),
+    )?;
+    env.define(
+        "_Proc_read_line".to_string(),
+        Object::Callable(Rc::new(ProcReadLine)),
+    )?;
+    env.define(
+        "_Proc_read".to_string(),
+        Object::Callable(Rc::new(ProcRead)),
+    )?;
+    env.define(
+        "_Proc_read_err".to_string(),
+        Object::Callable(Rc::new(ProcReadErr)),
+    )?;
+    env.define(
+        "_Proc_wait".to_string(),
+        Object::Callable(Rc::new(ProcWait)),
+    )?;
+    env.define(
+        "_Proc_kill".to_string(),
+        Object::Callable(Rc::new(ProcKill)),
+    )?;
+    env.define(
+        "_Proc_argv".to_string(),
+        Object::Callable(Rc::new(ProcArgv)),
+    )?;
+    env.define(
+        "_Proc_env_get".to_string(),
+        Object::Callable(Rc::new(ProcEnvGet)),
+    )?;
+    env.define(
+        "_Proc_env_set".to_string(),
+        Object::Callable(Rc::new(ProcEnvSet)),
+    )?;
+    env.define(
+        "_Proc_env_vars".to_string(),
+        Object::Callable(Rc::new(ProcEnvVars)),
+    )?;
+    env.define(
+        "_Proc_on_signal".to_string(),
+        Object::Callable(Rc::new(ProcOnSignal)),
+    )?;
 
     Ok(())
 }
@@ -0,0 +1,193 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Once;
+
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGUSR1};
+
+use crate::backend::event_loop;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::net::server;
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::{CallFrame, ExecContext};
+use crate::crux::token::Object;
+
+/// Signals scripts can handle, by the names they use for them
+const SIGNALS: [(&str, i32); 4] = [
+    ("SIGINT", SIGINT),
+    ("SIGTERM", SIGTERM),
+    ("SIGHUP", SIGHUP),
+    ("SIGUSR1", SIGUSR1),
+];
+
+/// Set by the OS-level handler, cleared once the interpreter handled the
+/// signal. Indexed like SIGNALS.
+static PENDING: [AtomicBool; 4] = [const { AtomicBool::new(false) }; 4];
+
+/// Scripts with a callback for the signal, indexed like SIGNALS. The signal
+/// is ignored while there are none.
+static HANDLED: [AtomicUsize; 4] = [const { AtomicUsize::new(0) }; 4];
+
+/// Set once SIGINT raises `Interrupted` on the thread that asked for it
+static INTERRUPTS: AtomicBool = AtomicBool::new(false);
+
+static HOOKED: [Once; 4] = [Once::new(), Once::new(), Once::new(), Once::new()];
+
+/// Callbacks of the script running on a thread, indexed like SIGNALS.
+/// Gives up its share of HANDLED when the thread ends.
+struct Handlers([Option<Rc<dyn ReiCallable>>; 4]);
+
+impl Drop for Handlers {
+    fn drop(&mut self) {
+        for (index, handler) in self.0.iter().enumerate() {
+            if handler.is_some() {
+                HANDLED[index].fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static HANDLERS: RefCell<Handlers> = RefCell::new(Handlers([None, None, None, None]));
+    /// Whether SIGINT without a callback interrupts the script on this thread
+    static INTERRUPTIBLE: Cell<bool> = const { Cell::new(false) };
+}
+
+fn index(name: &str) -> Option<usize> {
+    SIGNALS.iter().position(|(known, _)| *known == name)
+}
+
+/// Whether a callback or interrupt mode currently wants the signal. A
+/// running server takes Ctrl-C for itself.
+fn armed(index: usize) -> bool {
+    HANDLED[index].load(Ordering::SeqCst) > 0
+        || (index == 0 && INTERRUPTS.load(Ordering::SeqCst) && !server::serving())
+}
+
+/// Whether a script wants `signal`, safe to ask inside a signal handler
+pub fn wanted(signal: i32) -> bool {
+    SIGNALS
+        .iter()
+        .position(|(_, known)| *known == signal)
+        .is_some_and(armed)
+}
+
+/// From then on the signal only marks itself pending while armed, for
+/// `dispatch` to handle. A second one arriving before that ends the
+/// process, in case the script never gets to it.
+fn hook(index: usize) {
+    HOOKED[index].call_once(|| {
+        let (_, signal) = SIGNALS[index];
+        // Only touches atomics and exits, both safe in a signal handler
+        let _ = unsafe {
+            signal_hook::low_level::register(signal, move || {
+                if armed(index) && PENDING[index].swap(true, Ordering::SeqCst) {
+                    signal_hook::low_level::exit(128 + signal);
+                }
+            })
+        };
+    });
+}
+
+/// Ctrl-C raises `Interrupted` in the script running on this thread
+/// instead of killing the process. The CLI asks for it before running one.
+pub fn enable_interrupts() {
+    INTERRUPTIBLE.set(true);
+    INTERRUPTS.store(true, Ordering::SeqCst);
+    hook(0);
+}
+
+/// Calls `handler` whenever the signal arrives, in the script running on
+/// this thread. None drops the callback. Errors with the supported names
+/// when `name` isn't one of them.
+pub fn on_signal(name: &str, handler: Option<Rc<dyn ReiCallable>>) -> Result<(), String> {
+    let Some(index) = index(name) else {
+        let names: Vec<&str> = SIGNALS.iter().map(|(name, _)| *name).collect();
+        return Err(format!(
+            "Unknown signal '{}', expected one of {}",
+            name,
+            names.join(", ")
+        ));
+    };
+    if handler.is_some() {
+        hook(index);
+    }
+    HANDLERS.with_borrow_mut(|handlers| {
+        match (&handlers.0[index], &handler) {
+            (None, Some(_)) => HANDLED[index].fetch_add(1, Ordering::SeqCst),
+            (Some(_), None) => HANDLED[index].fetch_sub(1, Ordering::SeqCst),
+            _ => 0,
+        };
+        handlers.0[index] = handler;
+    });
+    Ok(())
+}
+
+/// Whether any signal arrived that may need handling, cheap enough to ask
+/// before every statement.
+pub fn pending() -> bool {
+    PENDING.iter().any(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Handles the signals that arrived since the last statement. Runs between
+/// statements, never inside the OS-level handler.
+pub fn dispatch(
+    interpreter: &mut Interpreter,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<(), ExecSignal> {
+    for (index, (name, _)) in SIGNALS.iter().enumerate() {
+        if !PENDING[index].load(Ordering::Relaxed) {
+            continue;
+        }
+        let handler = HANDLERS.with_borrow(|handlers| handlers.0[index].clone());
+        match handler {
+            Some(handler) => {
+                PENDING[index].store(false, Ordering::SeqCst);
+                run(&handler, name, interpreter, context)?;
+            }
+            // A server stops on Ctrl-C by itself, once its requests are done
+            None if index == 0 && INTERRUPTIBLE.get() => {
+                PENDING[index].store(false, Ordering::SeqCst);
+                if !server::serving() {
+                    let err = RuntimeErrorType::Interrupted;
+                    return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                        err,
+                        context.clone(),
+                    )));
+                }
+            }
+            // The thread that asked for interrupts raises it
+            None if index == 0 && INTERRUPTS.load(Ordering::SeqCst) => {}
+            // Another thread's script has a callback for it
+            None if HANDLED[index].load(Ordering::SeqCst) > 0 => {}
+            None => PENDING[index].store(false, Ordering::SeqCst),
+        }
+    }
+    Ok(())
+}
+
+/// A callback that fails raises its error where the script was.
+fn run(
+    handler: &Rc<dyn ReiCallable>,
+    name: &str,
+    interpreter: &mut Interpreter,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<(), ExecSignal> {
+    let arguments = [Object::Str(name.to_string())];
+    let arguments = arguments[..handler.arity().min(1)].to_vec();
+    let depth = context.borrow().call_stack.len();
+    context.borrow_mut().push_call(CallFrame::new(
+        handler.to_string(),
+        format!("handling {}", name),
+    ));
+    handler
+        .call(interpreter, &arguments, context.clone())
+        .and_then(|value| match value {
+            Object::Task(task) => event_loop::block_on(interpreter, &task),
+            value => Ok(value),
+        })?;
+    context.borrow_mut().call_stack.truncate(depth);
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::backend::environment::Environment;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::stack_trace::ExecContext;
+
+use crate::crux::token::Object;
+
+/// Assertion failures are plain thrown messages, so `do`/`fail` can catch
+/// them and the test runner reports them like any other runtime error.
+fn failure(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err_type = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err_type, context))
+}
+
+fn values_equal(interpreter: &Interpreter, a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Vec(a), Object::Vec(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(x, y)| values_equal(interpreter, x, y))
+        }
+        (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
+        _ => interpreter.is_equal(a.clone(), b.clone()),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Assert;
+impl ReiCallable for Assert {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        match &arguments[0] {
+            Object::Null | Object::Bool(false) => {
+                let msg = interpreter.stringify(&arguments[1]);
+                Err(failure(format!("Assertion failed: {}", msg), context))
+            }
+            _ => Ok(Object::Null),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_T_assert".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AssertEq;
+impl ReiCallable for AssertEq {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let (actual, expected) = (&arguments[0], &arguments[1]);
+        if values_equal(interpreter, actual, expected) {
+            return Ok(Object::Null);
+        }
+
+        let msg = format!(
+            "Assertion failed: expected {}, got {}",
+            interpreter.stringify(expected),
+            interpreter.stringify(actual)
+        );
+        Err(failure(msg, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_T_assert_eq".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Calls a zero-argument function and succeeds only if it raised a runtime
+/// error, which is handed back as an exception object.
+#[derive(Clone, Debug)]
+pub struct AssertThrows;
+impl ReiCallable for AssertThrows {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let function = match &arguments[0] {
+            Object::Callable(f) if f.arity() == 0 => f.clone(),
+            _ => {
+                let err_type = RuntimeErrorType::ErrorInNativeFn {
+                    msg: "expected a function without parameters".to_string(),
+                };
+                return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type, context,
+                )));
+            }
+        };
+
+        // A failing call leaves its frames on the stack for the trace
+        let depth = context.borrow().call_stack.len();
+        match function.call(interpreter, &vec![], context.clone()) {
+            Ok(_) => Err(failure(
+                "Assertion failed: expected an error to be thrown".to_string(),
+                context,
+            )),
+            Err(ExecSignal::RuntimeError(err)) => {
+                context.borrow_mut().call_stack.truncate(depth);
+                Ok(Object::Exception(Box::new(err)))
+            }
+            Err(other) => Err(other),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_T_assert_throws".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define("_T_assert".to_string(), Object::Callable(Rc::new(Assert)))?;
+    env.define(
+        "_T_assert_eq".to_string(),
+        Object::Callable(Rc::new(AssertEq)),
+    )?;
+    env.define(
+        "_T_assert_throws".to_string(),
+        Object::Callable(Rc::new(AssertThrows)),
+    )?;
+
+    Ok(())
+}
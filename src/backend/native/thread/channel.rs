@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::backend::native::misc::clone::Detached;
+
+/// One end of a channel. Each thread holding the channel has an end of its
+/// own, dropped with the last reference to it there.
+#[derive(Debug)]
+pub struct Channel {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<Queue>,
+    arrived: Condvar,
+}
+
+#[derive(Debug)]
+struct Queue {
+    values: VecDeque<Detached>,
+    /// Ends alive, those on their way to another thread included
+    ends: usize,
+}
+
+impl Channel {
+    pub fn new() -> Self {
+        let queue = Queue {
+            values: VecDeque::new(),
+            ends: 1,
+        };
+        Channel {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(queue),
+                arrived: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn send(&self, value: Detached) {
+        self.shared.queue.lock().unwrap().values.push_back(value);
+        self.shared.arrived.notify_one();
+    }
+
+    /// Blocks until a value arrives. None once nothing is queued and no
+    /// other end is left to send one.
+    pub fn recv(&self) -> Option<Detached> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        loop {
+            if let Some(value) = queue.values.pop_front() {
+                return Some(value);
+            }
+            if queue.ends == 1 {
+                return None;
+            }
+            queue = self.shared.arrived.wait(queue).unwrap();
+        }
+    }
+}
+
+/// Another end of the same channel.
+impl Clone for Channel {
+    fn clone(&self) -> Self {
+        self.shared.queue.lock().unwrap().ends += 1;
+        Channel {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().ends -= 1;
+        // A receiver may be the last end now
+        self.shared.arrived.notify_all();
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queue = self.shared.queue.lock().unwrap();
+        write!(f, "<channel | queued: {}>", queue.values.len())
+    }
+}
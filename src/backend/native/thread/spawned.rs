@@ -0,0 +1,48 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::thread::JoinHandle;
+
+use crate::backend::native::misc::clone::Detached;
+
+/// What a thread's function returned, or why it failed.
+pub type Outcome = Result<Detached, String>;
+
+/// Thread a script spawned. Dropping it lets the thread run on unjoined.
+#[derive(Debug)]
+pub struct Spawned {
+    name: String,
+    /// None once joined
+    handle: RefCell<Option<JoinHandle<Outcome>>>,
+}
+
+impl Spawned {
+    pub fn new(name: String, handle: JoinHandle<Outcome>) -> Self {
+        Spawned {
+            name,
+            handle: RefCell::new(Some(handle)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits for the thread to end. None when it was joined before.
+    pub fn join(&self) -> Option<Outcome> {
+        let handle = self.handle.borrow_mut().take()?;
+        Some(
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("it panicked".to_string())),
+        )
+    }
+}
+
+impl fmt::Display for Spawned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.handle.borrow() {
+            Some(_) => write!(f, "<thread {}>", self.name),
+            None => write!(f, "<thread {} | joined>", self.name),
+        }
+    }
+}
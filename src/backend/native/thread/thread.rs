@@ -0,0 +1,331 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::thread;
+
+use crate::backend::environment::Environment;
+use crate::backend::event_loop;
+use crate::backend::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::native::misc::clone::{self, Detached};
+use crate::backend::native::thread::channel::Channel;
+use crate::backend::native::thread::spawned::{Outcome, Spawned};
+use crate::backend::rei_callable::ReiCallable;
+use crate::backend::rei_function::ReiFunction;
+use crate::backend::resolver::Resolver;
+use crate::backend::stack_trace::ExecContext;
+use crate::backend::stmt::Stmt;
+use crate::crux::output;
+use crate::crux::token::Object;
+
+/// Declarations on their way to a new thread.
+struct Declarations(Vec<Stmt>);
+
+// SAFETY: the only values a parsed statement holds are the literals of its
+// tokens and expressions, which the lexer and parser only make from
+// numbers, strings, bools and null. Those own everything they hold, and
+// the statements are a fresh clone nothing else refers to.
+unsafe impl Send for Declarations {}
+
+fn native_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::ErrorInNativeFn { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+/// Failures of the thread rather than of the call, which scripts can catch.
+fn thread_error(msg: String, context: Rc<RefCell<ExecContext>>) -> ExecSignal {
+    let err = RuntimeErrorType::CustomMsg { msg };
+    ExecSignal::RuntimeError(RuntimeError::new(err, context))
+}
+
+fn thread_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<Spawned>, ExecSignal> {
+    match object {
+        Object::Thread(spawned) => Ok(spawned.clone()),
+        _ => Err(native_error("Expected thread".into(), context.clone())),
+    }
+}
+
+fn channel_arg(
+    object: &Object,
+    context: &Rc<RefCell<ExecContext>>,
+) -> Result<Rc<Channel>, ExecSignal> {
+    match object {
+        Object::Channel(channel) => Ok(channel.clone()),
+        _ => Err(native_error("Expected channel".into(), context.clone())),
+    }
+}
+
+/// Declares what the script declared in a fresh interpreter and calls
+/// `function`, so the thread sees the script's functions, classes, enums
+/// and traits but none of its variables.
+fn run_isolated(
+    declarations: Declarations,
+    path: String,
+    function: String,
+    argument: Option<Detached>,
+) -> Outcome {
+    let declarations = declarations.0;
+    let name = declarations
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Function { name, .. } if name.lexeme == function && name.path == path => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            format!(
+                "Function '{}' must be declared at the top of '{}' to run on a thread",
+                function, path
+            )
+        })?;
+
+    let mut interpreter = Interpreter::new().map_err(|e| e.to_string())?;
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve(&declarations);
+    if resolver.has_errors() {
+        let errors: Vec<String> = resolver
+            .diagnostics
+            .iter()
+            .filter(|d| d.is_error())
+            .map(|d| d.to_string())
+            .collect();
+        return Err(errors.join("\n"));
+    }
+
+    let describe = |signal: ExecSignal| match signal {
+        ExecSignal::RuntimeError(err) => match err.err_type {
+            RuntimeErrorType::CustomMsg { msg } | RuntimeErrorType::CustomMsgFatal { msg } => msg,
+            err_type => err_type.to_string(),
+        },
+        ExecSignal::ControlFlow(flow) => format!("Unexpected control flow: {:?}", flow),
+    };
+
+    interpreter.declare(declarations).map_err(describe)?;
+
+    let context = interpreter.environment.borrow().context.clone();
+    let arguments = match argument {
+        Some(argument) => vec![argument.attach(&interpreter, context)?],
+        None => vec![],
+    };
+
+    let mut result = interpreter
+        .call_global(&name, arguments)
+        .map_err(describe)?;
+    if let Object::Task(task) = &result {
+        result = event_loop::block_on(&mut interpreter, task).map_err(describe)?;
+    }
+    event_loop::run(&mut interpreter);
+
+    clone::detach(&result)
+}
+
+/// spawn(function, argument) -> thread
+#[derive(Clone, Debug)]
+pub struct Spawn;
+impl ReiCallable for Spawn {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let function = match &arguments[0] {
+            Object::Callable(c) => c.as_any().downcast_ref::<ReiFunction>(),
+            _ => None,
+        };
+        let Some(function) = function else {
+            return Err(native_error(
+                "Only a function declared in the script can run on a thread".to_string(),
+                context,
+            ));
+        };
+
+        // The argument is only passed on to functions that take one
+        let argument = match function.arity() {
+            0 => None,
+            1 => Some(clone::detach(&arguments[1]).map_err(|e| native_error(e, context.clone()))?),
+            _ => {
+                return Err(native_error(
+                    "A thread function takes at most one argument".to_string(),
+                    context,
+                ))
+            }
+        };
+
+        let name = function.name().lexeme.clone();
+        let path = function.name().path.clone();
+        let function_name = name.clone();
+        let declarations = Declarations(interpreter.declarations().to_vec());
+        let handle = thread::Builder::new()
+            .name(name.clone())
+            .spawn(move || {
+                // Exiting ends only the thread, and join reports it
+                output::contain(|| run_isolated(declarations, path, function_name, argument))
+                    .unwrap_or_else(|code| Err(format!("it exited with status {}", code)))
+            })
+            .map_err(|e| native_error(e.to_string(), context))?;
+
+        Ok(Object::Thread(Rc::new(Spawned::new(name, handle))))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_TH_spawn".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// join(thread) -> what the thread's function returned
+#[derive(Clone, Debug)]
+pub struct Join;
+impl ReiCallable for Join {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let spawned = thread_arg(&arguments[0], &context)?;
+        let Some(outcome) = spawned.join() else {
+            return Err(native_error(
+                format!("Thread '{}' was already joined", spawned.name()),
+                context,
+            ));
+        };
+
+        outcome
+            .and_then(|value| value.attach(interpreter, context.clone()))
+            .map_err(|msg| {
+                thread_error(
+                    format!("Thread '{}' failed: {}", spawned.name(), msg),
+                    context,
+                )
+            })
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_TH_join".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// channel() -> channel
+#[derive(Clone, Debug)]
+pub struct NewChannel;
+impl ReiCallable for NewChannel {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        Ok(Object::Channel(Rc::new(Channel::new())))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_TH_channel".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// send(channel, value) -> null, the receiver gets a deep copy
+#[derive(Clone, Debug)]
+pub struct SendValue;
+impl ReiCallable for SendValue {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let channel = channel_arg(&arguments[0], &context)?;
+        let value = clone::detach(&arguments[1]).map_err(|e| native_error(e, context))?;
+        channel.send(value);
+        Ok(Object::Null)
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_TH_send".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// recv(channel) -> the next value sent, blocking until there is one
+#[derive(Clone, Debug)]
+pub struct RecvValue;
+impl ReiCallable for RecvValue {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let channel = channel_arg(&arguments[0], &context)?;
+        let Some(value) = channel.recv() else {
+            return Err(thread_error(
+                "Channel is closed, no other end is left to send".to_string(),
+                context,
+            ));
+        };
+        value
+            .attach(interpreter, context.clone())
+            .map_err(|e| native_error(e, context))
+    }
+
+    fn to_string(&self) -> String {
+        "<native_fn>_TH_recv".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub fn register(env: &mut Environment) -> Result<(), ExecSignal> {
+    env.define("_TH_spawn".to_string(), Object::Callable(Rc::new(Spawn)))?;
+    env.define("_TH_join".to_string(), Object::Callable(Rc::new(Join)))?;
+    env.define(
+        "_TH_channel".to_string(),
+        Object::Callable(Rc::new(NewChannel)),
+    )?;
+    env.define("_TH_send".to_string(), Object::Callable(Rc::new(SendValue)))?;
+    env.define("_TH_recv".to_string(), Object::Callable(Rc::new(RecvValue)))?;
+
+    Ok(())
+}
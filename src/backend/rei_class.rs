@@ -1,8 +1,9 @@
 use std::any::Any;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
+use super::environment::EnvRef;
 use super::exec_signal::ExecSignal;
 
 use super::interpreter::Interpreter;
@@ -14,58 +15,202 @@ use super::rei_function::ReiFunction;
 use super::rei_instance::ReiInstance;
 
 use crate::crux::token::Object;
+use crate::frontend::expr::Expr;
 
 use crate::backend::stack_trace::ExecContext;
 
+/// Instance field declared in a class body. The initializer runs for every
+/// new instance, in the scope the class was declared in.
+#[derive(Debug, Clone)]
+pub struct ReiField {
+    pub name: String,
+    pub initializer: Option<Expr>,
+    pub constant: bool,
+    pub closure: EnvRef,
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticField {
+    pub value: Object,
+    pub constant: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReiClass {
     pub name: String,
     pub superclass_refs: Vec<Rc<ReiClass>>,
+    /// C3 linearization of the ancestors, nearest first (the class itself excluded)
+    pub mro: Vec<Rc<ReiClass>>,
     pub methods: HashMap<String, ReiFunction>,
     pub static_methods: HashMap<String, ReiFunction>,
+    pub fields: Vec<ReiField>,
+    /// Shared so that every copy of the class sees the same values
+    pub static_fields: Rc<RefCell<HashMap<String, StaticField>>>,
+    pub getters: HashMap<String, ReiFunction>,
+    pub setters: HashMap<String, ReiFunction>,
+    /// Names of the traits the class declared with `impl`
+    pub traits: Vec<String>,
+    /// Members only reachable from code inside this class or its subclasses
+    pub private_members: HashSet<String>,
 }
 
 impl ReiClass {
     pub fn new(
         name: String,
         superclass_refs: Vec<Rc<ReiClass>>,
+        mro: Vec<Rc<ReiClass>>,
         methods: HashMap<String, ReiFunction>,
         static_methods: HashMap<String, ReiFunction>,
+        fields: Vec<ReiField>,
+        static_fields: HashMap<String, StaticField>,
     ) -> Self {
         ReiClass {
             name,
             superclass_refs,
+            mro,
             methods,
             static_methods,
+            fields,
+            static_fields: Rc::new(RefCell::new(static_fields)),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+            traits: Vec::new(),
+            private_members: HashSet::new(),
         }
     }
 
-    pub fn find_method(&self, name: &str) -> Option<ReiFunction> {
-        if let Some(method) = self.methods.get(name) {
-            return Some(method.clone());
-        }
+    pub fn with_accessors(
+        mut self,
+        getters: HashMap<String, ReiFunction>,
+        setters: HashMap<String, ReiFunction>,
+    ) -> Self {
+        self.getters = getters;
+        self.setters = setters;
+        self
+    }
+
+    pub fn with_private_members(mut self, private_members: HashSet<String>) -> Self {
+        self.private_members = private_members;
+        self
+    }
+
+    /// Whether both are copies of the same class declaration. Copies share
+    /// their static fields, classes that merely have the same name don't.
+    pub fn same_class(&self, other: &ReiClass) -> bool {
+        Rc::ptr_eq(&self.static_fields, &other.static_fields)
+    }
 
-        for superclass in &self.superclass_refs {
-            if let Some(method) = superclass.find_method(name) {
-                return Some(method);
+    /// C3 linearization over the given bases: every class comes before its
+    /// parents and the bases keep their declared order. Returns None when no
+    /// order satisfies both rules.
+    pub fn linearize(superclass_refs: &[Rc<ReiClass>]) -> Option<Vec<Rc<ReiClass>>> {
+        let mut sequences: Vec<Vec<Rc<ReiClass>>> = superclass_refs
+            .iter()
+            .map(|base| {
+                let mut sequence = vec![base.clone()];
+                sequence.extend(base.mro.iter().cloned());
+                sequence
+            })
+            .collect();
+        sequences.push(superclass_refs.to_vec());
+
+        let mut mro = vec![];
+        loop {
+            sequences.retain(|sequence| !sequence.is_empty());
+            if sequences.is_empty() {
+                return Some(mro);
             }
+
+            // The first head that doesn't appear in the tail of any sequence
+            let candidate = sequences.iter().map(|s| s[0].clone()).find(|head| {
+                sequences
+                    .iter()
+                    .all(|s| !s[1..].iter().any(|c| c.same_class(head)))
+            })?;
+
+            for sequence in sequences.iter_mut() {
+                if sequence[0].same_class(&candidate) {
+                    sequence.remove(0);
+                }
+            }
+            mro.push(candidate);
         }
+    }
 
-        None
+    /// The class followed by its ancestors, in method resolution order.
+    pub fn resolution_order(&self) -> impl Iterator<Item = &ReiClass> {
+        std::iter::once(self).chain(self.mro.iter().map(|c| c.as_ref()))
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<ReiFunction> {
+        self.resolution_order()
+            .find_map(|class| class.methods.get(name).cloned())
     }
 
     pub fn find_static_method(&self, name: &str) -> Option<ReiFunction> {
-        if let Some(method) = self.static_methods.get(name) {
-            return Some(method.clone());
-        }
+        self.resolution_order()
+            .find_map(|class| class.static_methods.get(name).cloned())
+    }
+
+    pub fn find_getter(&self, name: &str) -> Option<ReiFunction> {
+        self.resolution_order()
+            .find_map(|class| class.getters.get(name).cloned())
+    }
+
+    pub fn find_setter(&self, name: &str) -> Option<ReiFunction> {
+        self.resolution_order()
+            .find_map(|class| class.setters.get(name).cloned())
+    }
+
+    /// Whether the nearest declaration of an instance field is `const`.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.resolution_order()
+            .find_map(|class| class.fields.iter().find(|f| f.name == name))
+            .is_some_and(|field| field.constant)
+    }
 
-        for superclass in &self.superclass_refs {
-            if let Some(method) = superclass.find_static_method(name) {
-                return Some(method);
+    /// The nearest `static let`/`static const` with that name.
+    pub fn static_field(&self, name: &str) -> Option<StaticField> {
+        self.resolution_order()
+            .find_map(|class| class.static_fields.borrow().get(name).cloned())
+    }
+
+    /// Assigns a declared, non-constant static field in the class that
+    /// declares it. Returns false when the class has no such field.
+    pub fn set_static_field(&self, name: &str, value: Object) -> bool {
+        for class in self.resolution_order() {
+            if let Some(field) = class.static_fields.borrow_mut().get_mut(name) {
+                field.value = value;
+                return true;
             }
         }
+        false
+    }
+
+    /// The class that declares the member, when it declares it `private`.
+    /// Only the nearest declaration counts, so an override decides for itself.
+    pub fn private_owner(&self, name: &str) -> Option<&ReiClass> {
+        self.resolution_order()
+            .find(|class| class.declares(name))
+            .filter(|class| class.private_members.contains(name))
+    }
 
-        None
+    fn declares(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+            || self.static_methods.contains_key(name)
+            || self.fields.iter().any(|field| field.name == name)
+            || self.static_fields.borrow().contains_key(name)
+            || self.getters.contains_key(name)
+            || self.setters.contains_key(name)
+    }
+
+    /// Finds a method in the classes that come after `after` in this class's
+    /// resolution order. This is what `base.method` resolves to.
+    pub fn find_method_after(&self, after: &ReiClass, name: &str) -> Option<ReiFunction> {
+        self.resolution_order()
+            .skip_while(|class| !class.same_class(after))
+            .skip(1)
+            .find_map(|class| class.methods.get(name).cloned())
     }
 }
 
@@ -76,7 +221,19 @@ impl ReiCallable for ReiClass {
         arguments: &Vec<Object>,
         context: Rc<RefCell<ExecContext>>,
     ) -> Result<Object, ExecSignal> {
-        let instance = ReiInstance::new(self.clone(), context.clone());
+        let mut instance = ReiInstance::new(self.clone(), context.clone());
+
+        // Declared fields, from the farthest ancestor down so overrides win
+        let order: Vec<&ReiClass> = self.resolution_order().collect();
+        for class in order.into_iter().rev() {
+            for field in &class.fields {
+                let value = match &field.initializer {
+                    Some(expr) => interpreter.evaluate_in(expr, field.closure.clone())?,
+                    None => Object::Null,
+                };
+                instance.set_field(&field.name, value);
+            }
+        }
 
         let init = self.find_method("init");
         match init {
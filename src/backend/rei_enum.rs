@@ -0,0 +1,152 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use super::exec_signal::ExecSignal;
+
+use super::interpreter::Interpreter;
+
+use super::rei_callable::ReiCallable;
+
+use crate::crux::token::Object;
+
+use crate::backend::stack_trace::ExecContext;
+
+/// Value of an enum: the variant it was made from and its payload.
+#[derive(Debug, Clone)]
+pub struct ReiVariant {
+    pub enum_name: String,
+    pub name: String,
+    /// Payload field names and values, in declaration order
+    pub fields: Vec<(String, Object)>,
+}
+
+impl ReiVariant {
+    pub fn get(&self, name: &str) -> Option<Object> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// `Enum.Variant`, the name `@typeof` also accepts
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.enum_name, self.name)
+    }
+}
+
+impl fmt::Display for ReiVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.qualified_name())?;
+        if !self.fields.is_empty() {
+            let values: Vec<String> = self.fields.iter().map(|(_, v)| v.to_string()).collect();
+            write!(f, "({})", values.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Declared enum. Variants without fields are read as values (`Color.Red`),
+/// variants with fields are called to build one (`Color.Rgb(1, 2, 3)`).
+#[derive(Debug, Clone)]
+pub struct ReiEnum {
+    pub name: String,
+    /// Variant names with their field names, in declaration order
+    pub variants: Vec<(String, Vec<String>)>,
+}
+
+impl ReiEnum {
+    pub fn new(name: String, variants: Vec<(String, Vec<String>)>) -> Self {
+        ReiEnum { name, variants }
+    }
+
+    /// Looks up `Enum.name`: the value itself for a unit variant, its
+    /// constructor otherwise.
+    pub fn variant(&self, name: &str) -> Option<Object> {
+        let (variant, fields) = self.variants.iter().find(|(v, _)| v == name)?;
+        if fields.is_empty() {
+            let value = ReiVariant {
+                enum_name: self.name.clone(),
+                name: variant.clone(),
+                fields: vec![],
+            };
+            return Some(Object::Variant(Rc::new(value)));
+        }
+
+        let constructor = VariantConstructor {
+            enum_name: self.name.clone(),
+            name: variant.clone(),
+            fields: fields.clone(),
+        };
+        Some(Object::Callable(Rc::new(constructor)))
+    }
+}
+
+impl ReiCallable for ReiEnum {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let err_type = RuntimeErrorType::EnumError {
+            msg: format!(
+                "Enum '{}' can't be instantiated, use one of its variants",
+                self.name
+            ),
+        };
+        Err(ExecSignal::RuntimeError(RuntimeError::new(
+            err_type, context,
+        )))
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn to_string(&self) -> String {
+        format!("<enum {}>", self.name)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Builds a variant that carries fields, one argument per field.
+#[derive(Debug, Clone)]
+pub struct VariantConstructor {
+    pub enum_name: String,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl ReiCallable for VariantConstructor {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let value = ReiVariant {
+            enum_name: self.enum_name.clone(),
+            name: self.name.clone(),
+            fields: self.fields.iter().cloned().zip(arguments.clone()).collect(),
+        };
+        Ok(Object::Variant(Rc::new(value)))
+    }
+
+    fn arity(&self) -> usize {
+        self.fields.len()
+    }
+
+    fn to_string(&self) -> String {
+        format!("<variant {}.{}>", self.enum_name, self.name)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
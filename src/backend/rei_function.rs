@@ -4,6 +4,8 @@ use std::rc::Rc;
 
 use super::environment::{EnvRef, Environment};
 
+use super::event_loop::ReiTask;
+
 use super::exec_signal::control_flow::ControlFlow;
 use super::exec_signal::ExecSignal;
 
@@ -11,6 +13,8 @@ use super::interpreter::Interpreter;
 
 use super::rei_callable::ReiCallable;
 
+use super::rei_generator::ReiGenerator;
+
 use super::rei_instance::ReiInstance;
 
 use super::stmt;
@@ -25,6 +29,10 @@ pub struct ReiFunction {
     params: Vec<Token>,
     body: Vec<stmt::Stmt>,
     is_initializer: bool,
+    /// The body yields, so calling the function creates a generator
+    is_generator: bool,
+    /// Declared `async`, so calling the function starts a task
+    is_async: bool,
     closure: EnvRef,
 }
 
@@ -45,6 +53,24 @@ impl ReiCallable for ReiFunction {
             env.borrow_mut().define(param.lexeme.clone(), arg.clone())?;
         }
 
+        if self.is_async {
+            let body = ReiGenerator::new(
+                self.name.lexeme.clone(),
+                self.body.clone(),
+                env,
+                context.clone(),
+            );
+            return Ok(interpreter
+                .event_loop
+                .spawn(ReiTask::coroutine(body, context)));
+        }
+
+        if self.is_generator {
+            let generator =
+                ReiGenerator::new(self.name.lexeme.clone(), self.body.clone(), env, context);
+            return Ok(Object::Generator(Rc::new(generator)));
+        }
+
         match interpreter.execute_block(&self.body, env) {
             Ok(_) => {
                 if self.is_initializer {
@@ -84,16 +110,24 @@ impl ReiFunction {
         body: Vec<stmt::Stmt>,
         closure: EnvRef,
         is_initializer: bool,
+        is_async: bool,
     ) -> Self {
+        let is_generator = stmt::contains_yield(&body);
         Self {
             name,
             params,
             body,
             closure,
             is_initializer,
+            is_generator,
+            is_async,
         }
     }
 
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
     pub fn bind(
         &self,
         instance: ReiInstance,
@@ -110,6 +144,8 @@ impl ReiFunction {
             body: self.body.clone(),
             closure: env,
             is_initializer: self.is_initializer,
+            is_generator: self.is_generator,
+            is_async: self.is_async,
         })
     }
 }
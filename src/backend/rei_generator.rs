@@ -0,0 +1,706 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use super::environment::{EnvRef, Environment};
+use super::exec_signal::control_flow::ControlFlow;
+use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use super::exec_signal::ExecSignal;
+
+use super::interpreter::Interpreter;
+
+use super::rei_callable::ReiCallable;
+
+use super::stmt::{self, Stmt};
+
+use crate::crux::token::{Object, Token};
+use crate::frontend::expr::Expr;
+
+use crate::backend::stack_trace::ExecContext;
+
+/// Compound statement a suspended generator is inside of. Statements that
+/// can't yield or await run through the interpreter as usual, so only the
+/// statements around a `yield` or an `await` need a frame to resume from.
+#[derive(Debug)]
+enum Frame {
+    /// Runs `statements` in order, `index` is the next one
+    Block {
+        statements: Vec<Stmt>,
+        index: usize,
+        env: EnvRef,
+    },
+    While {
+        condition: Expr,
+        body: Stmt,
+        env: EnvRef,
+    },
+    For {
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Stmt,
+        env: EnvRef,
+        started: bool,
+    },
+    Loop {
+        keyword: Token,
+        name: Token,
+        end: Expr,
+        step: Expr,
+        body: Stmt,
+        env: EnvRef,
+        /// Step of the iteration that is running, added once it's done
+        last_step: Option<f64>,
+    },
+    Each {
+        name: Token,
+        items: ReiIterator,
+        body: Stmt,
+        env: EnvRef,
+    },
+    /// A do/fail/finish whose `do` (or `fail`, once `failed`) is running
+    Try {
+        fail_stmts: Stmt,
+        fail_binding: Option<Stmt>,
+        finish_stmts: Option<Stmt>,
+        env: EnvRef,
+        failed: bool,
+    },
+    /// Statement waiting on an `await`, finished with the awaited result
+    Await { statement: Stmt, env: EnvRef },
+}
+
+impl Frame {
+    fn is_loop(&self) -> bool {
+        matches!(
+            self,
+            Frame::While { .. } | Frame::For { .. } | Frame::Loop { .. } | Frame::Each { .. }
+        )
+    }
+}
+
+/// Suspended call of a function that contains `yield`. Each `next()` runs
+/// the body up to the next `yield` and returns its value. The body of an
+/// `async fn` runs the same way, suspending on `await` instead.
+#[derive(Debug)]
+pub struct ReiGenerator {
+    pub name: String,
+    frames: RefCell<Vec<Frame>>,
+    running: Cell<bool>,
+    done: Cell<bool>,
+    /// Value of the `return` that ended the body
+    returned: RefCell<Object>,
+    context: Rc<RefCell<ExecContext>>,
+}
+
+impl ReiGenerator {
+    /// `env` already holds the arguments the function was called with.
+    pub fn new(
+        name: String,
+        body: Vec<Stmt>,
+        env: EnvRef,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Self {
+        let frame = Frame::Block {
+            statements: body,
+            index: 0,
+            env,
+        };
+        ReiGenerator {
+            name,
+            frames: RefCell::new(vec![frame]),
+            running: Cell::new(false),
+            done: Cell::new(false),
+            returned: RefCell::new(Object::Null),
+            context,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.get()
+    }
+
+    pub fn returned(&self) -> Object {
+        self.returned.borrow().clone()
+    }
+
+    /// Looks up `generator.name`: `next()` and `done()`.
+    pub fn method(self: &Rc<Self>, name: &str) -> Option<Object> {
+        let kind = match name {
+            "next" => GeneratorMethodKind::Next,
+            "done" => GeneratorMethodKind::Done,
+            _ => return None,
+        };
+        let method = GeneratorMethod {
+            generator: self.clone(),
+            kind,
+        };
+        Some(Object::Callable(Rc::new(method)))
+    }
+
+    /// Runs until the next `yield`. None once the body has finished.
+    pub fn resume(&self, interpreter: &mut Interpreter) -> Result<Option<Object>, ExecSignal> {
+        self.resume_with(interpreter, Ok(Object::Null))
+    }
+
+    /// Runs until the next `yield` or `await`, first finishing the `await`
+    /// it is suspended on with `sent`: its result, or the error to raise.
+    pub fn resume_with(
+        &self,
+        interpreter: &mut Interpreter,
+        sent: Result<Object, ExecSignal>,
+    ) -> Result<Option<Object>, ExecSignal> {
+        if self.done.get() {
+            return Ok(None);
+        }
+        if self.running.replace(true) {
+            let err_type = RuntimeErrorType::GeneratorError {
+                msg: format!("Generator '{}' is already running", self.name),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        }
+
+        // Taken out so the body can use the generator without a borrow held
+        let mut frames = std::mem::take(&mut *self.frames.borrow_mut());
+        let result = self.send(&mut frames, sent, interpreter);
+        let result = result.and_then(|()| self.run(&mut frames, interpreter));
+        self.running.set(false);
+
+        match result {
+            Ok(Some(value)) => {
+                *self.frames.borrow_mut() = frames;
+                Ok(Some(value))
+            }
+            other => {
+                self.done.set(true);
+                other
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        frames: &mut Vec<Frame>,
+        sent: Result<Object, ExecSignal>,
+        interpreter: &mut Interpreter,
+    ) -> Result<(), ExecSignal> {
+        let Some(Frame::Await { statement, env }) =
+            frames.pop_if(|f| matches!(f, Frame::Await { .. }))
+        else {
+            return Ok(());
+        };
+        let result = sent.and_then(|value| {
+            let statement = with_awaited(&statement, value);
+            interpreter.with_env(env, |interpreter| interpreter.execute(&statement))
+        });
+        match result {
+            Ok(()) => Ok(()),
+            Err(signal) => self.unwind(frames, signal, interpreter),
+        }
+    }
+
+    fn run(
+        &self,
+        frames: &mut Vec<Frame>,
+        interpreter: &mut Interpreter,
+    ) -> Result<Option<Object>, ExecSignal> {
+        while let Some(frame) = frames.pop() {
+            let result = match frame {
+                Frame::Block {
+                    statements,
+                    index,
+                    env,
+                } => {
+                    let Some(statement) = statements.get(index).cloned() else {
+                        continue;
+                    };
+                    frames.push(Frame::Block {
+                        statements,
+                        index: index + 1,
+                        env: env.clone(),
+                    });
+                    self.enter(frames, &statement, env, interpreter)
+                }
+                Frame::While {
+                    condition,
+                    body,
+                    env,
+                } => {
+                    let condition_value = interpreter.evaluate_in(&condition, env.clone());
+                    match condition_value {
+                        Ok(value) if interpreter.is_truthy(&value) => {
+                            frames.push(Frame::While {
+                                condition,
+                                body: body.clone(),
+                                env: env.clone(),
+                            });
+                            self.enter(frames, &body, env, interpreter)
+                        }
+                        Ok(_) => Ok(None),
+                        Err(signal) => Err(signal),
+                    }
+                }
+                // Counting loops update their frame in place
+                frame @ (Frame::For { .. } | Frame::Loop { .. }) => {
+                    frames.push(frame);
+                    self.next_count(frames, interpreter)
+                }
+                Frame::Each {
+                    name,
+                    mut items,
+                    body,
+                    env,
+                } => match items.next(interpreter) {
+                    Ok(Some(item)) => match Self::assign(&env, &name, item) {
+                        Ok(()) => {
+                            frames.push(Frame::Each {
+                                name,
+                                items,
+                                body: body.clone(),
+                                env: env.clone(),
+                            });
+                            self.enter(frames, &body, env, interpreter)
+                        }
+                        Err(signal) => Err(signal),
+                    },
+                    Ok(None) => Ok(None),
+                    Err(signal) => Err(signal),
+                },
+                // The do or fail block is over, only finish is left
+                Frame::Try {
+                    finish_stmts, env, ..
+                } => {
+                    if let Some(finish) = finish_stmts {
+                        frames.push(Frame::Block {
+                            statements: vec![finish],
+                            index: 0,
+                            env,
+                        });
+                    }
+                    Ok(None)
+                }
+                Frame::Await { .. } => unreachable!("await frames are finished by send"),
+            };
+
+            match result {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => {}
+                Err(signal) => self.unwind(frames, signal, interpreter)?,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Starts a statement. Statements without a `yield` run to completion,
+    /// the others push the frame that steps through them.
+    fn enter(
+        &self,
+        frames: &mut Vec<Frame>,
+        statement: &Stmt,
+        env: EnvRef,
+        interpreter: &mut Interpreter,
+    ) -> Result<Option<Object>, ExecSignal> {
+        let nested = std::slice::from_ref(statement);
+        if !stmt::contains_yield(nested) && !stmt::contains_await(nested) {
+            interpreter.with_env(env, |interpreter| interpreter.execute(statement))?;
+            return Ok(None);
+        }
+
+        if let Some(Expr::Await { value, .. }) = stmt::awaited(statement) {
+            let awaited = interpreter.evaluate_in(value, env.clone())?;
+            frames.push(Frame::Await {
+                statement: statement.clone(),
+                env,
+            });
+            return Ok(Some(awaited));
+        }
+
+        match statement {
+            Stmt::Yield { value, .. } => {
+                let value = match value {
+                    Some(value) => interpreter.evaluate_in(value, env)?,
+                    None => Object::Null,
+                };
+                return Ok(Some(value));
+            }
+            Stmt::Block { statements } => frames.push(Frame::Block {
+                statements: statements.clone(),
+                index: 0,
+                env: Environment::from_enclosing(env, self.context.clone()),
+            }),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = interpreter.evaluate_in(condition, env.clone())?;
+                let branch = if interpreter.is_truthy(&condition) {
+                    Some(then_branch)
+                } else {
+                    else_branch.as_ref()
+                };
+                if let Some(branch) = branch {
+                    frames.push(Frame::Block {
+                        statements: vec![branch.as_ref().clone()],
+                        index: 0,
+                        env,
+                    });
+                }
+            }
+            Stmt::While { condition, body } => frames.push(Frame::While {
+                condition: condition.as_ref().clone(),
+                body: body.as_ref().clone(),
+                env,
+            }),
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                let env = Environment::from_enclosing(env, self.context.clone());
+                if let Some(initializer) = initializer {
+                    interpreter.with_env(env.clone(), |i| i.execute(initializer))?;
+                }
+                frames.push(Frame::For {
+                    condition: condition.as_deref().cloned(),
+                    increment: increment.as_deref().cloned(),
+                    body: body.as_ref().clone(),
+                    env,
+                    started: false,
+                });
+            }
+            Stmt::Loop {
+                keyword,
+                name,
+                start,
+                end,
+                step,
+                body,
+            } => {
+                let env = Environment::from_enclosing(env, self.context.clone());
+                let start = interpreter.evaluate_in(start, env.clone())?;
+                env.borrow_mut().define(name.lexeme.clone(), start)?;
+                frames.push(Frame::Loop {
+                    keyword: keyword.clone(),
+                    name: name.clone(),
+                    end: end.as_ref().clone(),
+                    step: step.as_ref().clone(),
+                    body: body.as_ref().clone(),
+                    env,
+                    last_step: None,
+                });
+            }
+            Stmt::Each {
+                keyword,
+                name,
+                iterable,
+                body,
+            } => {
+                let iterable = interpreter.evaluate_in(iterable, env.clone())?;
+                let items = ReiIterator::from_object(iterable, keyword, &self.context)?;
+                let env = Environment::from_enclosing(env, self.context.clone());
+                env.borrow_mut().define(name.lexeme.clone(), Object::Null)?;
+                frames.push(Frame::Each {
+                    name: name.clone(),
+                    items,
+                    body: body.as_ref().clone(),
+                    env,
+                });
+            }
+            Stmt::Exception {
+                do_stmts,
+                fail_stmts,
+                fail_binding,
+                finish_stmts,
+                ..
+            } => {
+                frames.push(Frame::Try {
+                    fail_stmts: fail_stmts.as_ref().clone(),
+                    fail_binding: fail_binding.as_deref().cloned(),
+                    finish_stmts: finish_stmts.as_deref().cloned(),
+                    env: env.clone(),
+                    failed: false,
+                });
+                frames.push(Frame::Block {
+                    statements: vec![do_stmts.as_ref().clone()],
+                    index: 0,
+                    env,
+                });
+            }
+            // No other statement can hold a `yield` or `await` of this function
+            _ => interpreter.with_env(env, |interpreter| interpreter.execute(statement))?,
+        }
+        Ok(None)
+    }
+
+    fn assign(env: &EnvRef, name: &Token, value: Object) -> Result<(), ExecSignal> {
+        env.borrow_mut().assign(name, value)
+    }
+
+    /// Starts the next iteration of the `for` or `loop` frame on top, or
+    /// pops it when its condition no longer holds.
+    fn next_count(
+        &self,
+        frames: &mut Vec<Frame>,
+        interpreter: &mut Interpreter,
+    ) -> Result<Option<Object>, ExecSignal> {
+        let keep_going = match frames.last_mut() {
+            Some(Frame::For {
+                condition,
+                increment,
+                env,
+                started,
+                ..
+            }) => {
+                if let (true, Some(increment)) = (*started, &increment) {
+                    interpreter.evaluate_in(increment, env.clone())?;
+                }
+                *started = true;
+                match condition {
+                    Some(condition) => {
+                        let value = interpreter.evaluate_in(condition, env.clone())?;
+                        interpreter.is_truthy(&value)
+                    }
+                    None => true,
+                }
+            }
+            Some(Frame::Loop {
+                keyword,
+                name,
+                end,
+                step,
+                env,
+                last_step,
+                ..
+            }) => {
+                if let Some(last_step) = *last_step {
+                    let current = env.borrow().get(name)?;
+                    let next = interpreter.binary_number_operation(
+                        current,
+                        Object::Number(last_step),
+                        keyword.clone(),
+                        |a, b| a + b,
+                    )?;
+                    env.borrow_mut().assign(name, next)?;
+                }
+
+                let current = env.borrow().get(name)?;
+                let end = interpreter.evaluate_in(end, env.clone())?;
+                let step_value = interpreter.evaluate_in(step, env.clone())?;
+                *last_step = interpreter.range_step(keyword, current, end, step, step_value)?;
+                last_step.is_some()
+            }
+            _ => unreachable!("next_count runs on for and loop frames"),
+        };
+
+        if !keep_going {
+            frames.pop();
+            return Ok(None);
+        }
+        let (body, env) = match frames.last() {
+            Some(Frame::For { body, env, .. } | Frame::Loop { body, env, .. }) => {
+                (body.clone(), env.clone())
+            }
+            _ => unreachable!("next_count runs on for and loop frames"),
+        };
+        self.enter(frames, &body, env, interpreter)
+    }
+
+    /// Passes a break, continue, return or error up through the frames, the
+    /// way the interpreter would through nested statements. Returns the
+    /// signal if nothing inside the generator handles it.
+    fn unwind(
+        &self,
+        frames: &mut Vec<Frame>,
+        signal: ExecSignal,
+        interpreter: &mut Interpreter,
+    ) -> Result<(), ExecSignal> {
+        match signal {
+            ExecSignal::ControlFlow(ControlFlow::Break) => {
+                while let Some(frame) = frames.pop() {
+                    if frame.is_loop() {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            ExecSignal::ControlFlow(ControlFlow::Continue) => {
+                while frames.last().is_some_and(|frame| !frame.is_loop()) {
+                    frames.pop();
+                }
+                Ok(())
+            }
+            // Returning ends the generator
+            ExecSignal::ControlFlow(ControlFlow::Return(value)) => {
+                frames.clear();
+                *self.returned.borrow_mut() = value;
+                Ok(())
+            }
+            ExecSignal::RuntimeError(err) => {
+                while let Some(frame) = frames.pop() {
+                    let Frame::Try {
+                        fail_stmts,
+                        fail_binding,
+                        finish_stmts,
+                        env,
+                        failed,
+                    } = frame
+                    else {
+                        continue;
+                    };
+
+                    let catchable = err.err_type.is_catchable();
+                    if failed || !catchable {
+                        // Still run finish, then keep going up
+                        if let Some(finish) = finish_stmts {
+                            interpreter.with_env(env, |i| i.execute(&finish))?;
+                        }
+                        continue;
+                    }
+
+                    if let Some(Stmt::Let { name, .. }) = &fail_binding {
+                        let exception = Object::Exception(Box::new(err));
+                        env.borrow_mut().define(name.lexeme.clone(), exception)?;
+                    }
+                    frames.push(Frame::Try {
+                        fail_stmts: fail_stmts.clone(),
+                        fail_binding,
+                        finish_stmts,
+                        env: env.clone(),
+                        failed: true,
+                    });
+                    frames.push(Frame::Block {
+                        statements: vec![fail_stmts],
+                        index: 0,
+                        env,
+                    });
+                    return Ok(());
+                }
+                Err(ExecSignal::RuntimeError(err))
+            }
+        }
+    }
+}
+
+/// `statement` with its `await` replaced by the awaited result.
+fn with_awaited(statement: &Stmt, result: Object) -> Stmt {
+    let literal = |awaited: &Expr| {
+        Box::new(Expr::Literal {
+            id: awaited.id(),
+            value: result.clone(),
+        })
+    };
+    match statement {
+        Stmt::Expression { expression } => match expression.as_ref() {
+            Expr::Assign { id, name, value } => Stmt::Expression {
+                expression: Box::new(Expr::Assign {
+                    id: *id,
+                    name: name.clone(),
+                    value: literal(value),
+                }),
+            },
+            // The result of a plain `await x;` isn't used
+            _ => Stmt::Block { statements: vec![] },
+        },
+        Stmt::Let { name, initializer } => Stmt::Let {
+            name: name.clone(),
+            initializer: literal(initializer),
+        },
+        Stmt::Return {
+            keyword,
+            value: Some(value),
+        } => Stmt::Return {
+            keyword: keyword.clone(),
+            value: Some(literal(value)),
+        },
+        _ => unreachable!("only statements with an await are suspended on"),
+    }
+}
+
+/// What `loop (let x in ...)` walks over.
+#[derive(Debug)]
+pub enum ReiIterator {
+    Generator(Rc<ReiGenerator>),
+    /// Items of a vector as they were when the loop started
+    Items(std::vec::IntoIter<Object>),
+}
+
+impl ReiIterator {
+    pub fn from_object(
+        object: Object,
+        keyword: &Token,
+        context: &Rc<RefCell<ExecContext>>,
+    ) -> Result<Self, ExecSignal> {
+        match object {
+            Object::Generator(generator) => Ok(ReiIterator::Generator(generator)),
+            Object::Vec(items) => Ok(ReiIterator::Items(items.borrow().clone().into_iter())),
+            _ => {
+                let err_type = RuntimeErrorType::NotIterable {
+                    token: keyword.clone(),
+                };
+                Err(ExecSignal::RuntimeError(RuntimeError::new(
+                    err_type,
+                    context.clone(),
+                )))
+            }
+        }
+    }
+
+    pub fn next(&mut self, interpreter: &mut Interpreter) -> Result<Option<Object>, ExecSignal> {
+        match self {
+            ReiIterator::Generator(generator) => generator.resume(interpreter),
+            ReiIterator::Items(items) => Ok(items.next()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GeneratorMethodKind {
+    Next,
+    Done,
+}
+
+/// `next()` or `done()` of a generator.
+#[derive(Debug)]
+struct GeneratorMethod {
+    generator: Rc<ReiGenerator>,
+    kind: GeneratorMethodKind,
+}
+
+impl ReiCallable for GeneratorMethod {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        _context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        match self.kind {
+            // A finished generator keeps returning null
+            GeneratorMethodKind::Next => {
+                Ok(self.generator.resume(interpreter)?.unwrap_or(Object::Null))
+            }
+            GeneratorMethodKind::Done => Ok(Object::Bool(self.generator.is_done())),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn to_string(&self) -> String {
+        let method = match self.kind {
+            GeneratorMethodKind::Next => "next",
+            GeneratorMethodKind::Done => "done",
+        };
+        format!("<generator {}>.{}", self.generator.name, method)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -5,6 +5,8 @@ use std::rc::Rc;
 use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
 use super::exec_signal::ExecSignal;
 
+use super::interpreter::Interpreter;
+
 use super::rei_callable::ReiCallable;
 
 use super::rei_class::ReiClass;
@@ -30,7 +32,13 @@ impl ReiInstance {
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<Object, ExecSignal> {
+    /// Reads a property. A getter wins over a stored field of the same name.
+    pub fn get(&self, interpreter: &mut Interpreter, name: &Token) -> Result<Object, ExecSignal> {
+        if let Some(getter) = self.class.find_getter(&name.lexeme) {
+            let getter = getter.bind(self.clone(), self.context.clone())?;
+            return getter.call(interpreter, &vec![], self.context.clone());
+        }
+
         if let Some(value) = self.fields.borrow().get(&name.lexeme) {
             return Ok(value.clone());
         }
@@ -50,7 +58,36 @@ impl ReiInstance {
         )))
     }
 
-    pub fn set(&mut self, name: &str, value: Object) {
+    /// Writes a property through its setter when the class has one. A
+    /// property with only a getter can't be assigned.
+    pub fn set(
+        &mut self,
+        interpreter: &mut Interpreter,
+        name: &Token,
+        value: Object,
+    ) -> Result<(), ExecSignal> {
+        if let Some(setter) = self.class.find_setter(&name.lexeme) {
+            let setter = setter.bind(self.clone(), self.context.clone())?;
+            setter.call(interpreter, &vec![value], self.context.clone())?;
+            return Ok(());
+        }
+
+        if self.class.find_getter(&name.lexeme).is_some() {
+            let err_type = RuntimeErrorType::ReadOnlyProperty {
+                token: name.clone(),
+            };
+            return Err(ExecSignal::RuntimeError(RuntimeError::new(
+                err_type,
+                self.context.clone(),
+            )));
+        }
+
+        self.set_field(&name.lexeme, value);
+        Ok(())
+    }
+
+    /// Stores a field directly, skipping any setter.
+    pub fn set_field(&mut self, name: &str, value: Object) {
         self.fields.borrow_mut().insert(name.to_string(), value);
     }
 
@@ -59,24 +96,18 @@ impl ReiInstance {
     }
 
     pub fn to_string(&self) -> String {
-        let mut properties = String::new();
-        let mut methods = String::new();
-        let mut static_methods = String::new();
-
-        for i in self.fields.borrow().keys() {
-            let s = format!(" {} ", i);
-            properties.push_str(&s);
-        }
-
-        for i in self.class.methods.keys() {
-            let s = format!(" {}() ", i);
-            methods.push_str(&s);
-        }
-
-        for i in self.class.static_methods.keys() {
-            let s = format!(" {}() ", i);
-            static_methods.push_str(&s);
-        }
+        // Sorted, so the same instance always prints the same way
+        let list = |names: Vec<&String>, suffix: &str| {
+            let mut names = names;
+            names.sort();
+            names
+                .iter()
+                .map(|name| format!(" {}{} ", name, suffix))
+                .collect::<String>()
+        };
+        let properties = list(self.fields.borrow().keys().collect(), "");
+        let methods = list(self.class.methods.keys().collect(), "()");
+        let static_methods = list(self.class.static_methods.keys().collect(), "()");
 
         format!(
             "<Instance of {}>\n  properties --> {}\n  static methods --> {}\n  methods --> {}",
@@ -0,0 +1,105 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::exec_signal::runtime_error::{RuntimeError, RuntimeErrorType};
+use super::exec_signal::ExecSignal;
+
+use super::interpreter::Interpreter;
+
+use super::rei_callable::ReiCallable;
+
+use super::rei_class::ReiClass;
+
+use super::rei_function::ReiFunction;
+
+use crate::crux::token::Object;
+
+use crate::backend::stack_trace::ExecContext;
+
+/// Set of methods a class promises to have. Traits live in the environment
+/// like classes do, but can't be called.
+#[derive(Debug, Clone)]
+pub struct ReiTrait {
+    pub name: String,
+    /// Method name to number of parameters
+    pub required: HashMap<String, usize>,
+    /// Default implementations, copied into classes that lack the method
+    pub methods: HashMap<String, ReiFunction>,
+}
+
+impl ReiTrait {
+    pub fn new(
+        name: String,
+        required: HashMap<String, usize>,
+        methods: HashMap<String, ReiFunction>,
+    ) -> Self {
+        ReiTrait {
+            name,
+            required,
+            methods,
+        }
+    }
+
+    /// Fills in default methods the class doesn't have, then checks every
+    /// required method exists with a matching number of parameters.
+    pub fn apply(&self, class: &mut ReiClass) -> Result<(), String> {
+        for (name, method) in &self.methods {
+            if class.find_method(name).is_none() {
+                class.methods.insert(name.clone(), method.clone());
+            }
+        }
+
+        let mut required: Vec<(&String, &usize)> = self.required.iter().collect();
+        required.sort();
+        for (name, arity) in required {
+            match class.find_method(name) {
+                Some(method) if method.arity() == *arity => {}
+                Some(method) => return Err(format!(
+                    "Method '{}' of class '{}' takes {} parameter(s) but trait '{}' requires {}",
+                    name,
+                    class.name,
+                    method.arity(),
+                    self.name,
+                    arity
+                )),
+                None => {
+                    return Err(format!(
+                        "Class '{}' is missing method '{}' required by trait '{}'",
+                        class.name, name, self.name
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ReiCallable for ReiTrait {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: &Vec<Object>,
+        context: Rc<RefCell<ExecContext>>,
+    ) -> Result<Object, ExecSignal> {
+        let err_type = RuntimeErrorType::TraitError {
+            msg: format!("Trait '{}' can't be instantiated", self.name),
+        };
+        Err(ExecSignal::RuntimeError(RuntimeError::new(
+            err_type, context,
+        )))
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn to_string(&self) -> String {
+        format!("<trait {}>", self.name)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
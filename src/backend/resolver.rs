@@ -1,17 +1,34 @@
 use std::collections::HashMap;
 
-use super::interpreter::Interpreter;
-use super::stmt::Stmt;
+use super::interpreter::{Interpreter, META_METHODS};
+use super::stmt::{self, Stmt};
 
-use crate::crux::token::Token;
-use crate::frontend::expr::Expr;
+use crate::crux::error::Diagnostic;
+use crate::crux::token::{Object, Token, TokenType};
+use crate::frontend::expr::{Expr, ExprId};
 
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
-    scopes: Vec<HashMap<String, bool>>, // stack of scopes
+    scopes: Vec<HashMap<String, Local>>, // stack of scopes
+    globals: HashMap<String, Local>,
+    class_statics: HashMap<String, HashMap<String, usize>>,
+    /// Members each top-level class declares `private`
+    class_privates: HashMap<String, Vec<String>>,
     current_function: FunctionType,
     current_class: ClassType,
+    /// Name of the class whose body is being resolved
+    enclosing_class: Option<String>,
     loop_depth: usize,
+    /// The function being resolved contains `yield`
+    in_generator: bool,
+    /// The function being resolved is declared `async`
+    in_async: bool,
+    /// The one `await` the current statement may hold, see `stmt::awaited`
+    allowed_await: Option<ExprId>,
+    lint: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Every variable use paired with the token that declared it (lint mode only)
+    pub definitions: Vec<(Token, Token)>,
 }
 
 #[derive(Clone, Debug)]
@@ -27,6 +44,39 @@ enum FunctionType {
 enum ClassType {
     None,
     Class,
+    Subclass,
+    Static,
+    Field,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LocalKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    This,
+}
+
+#[derive(Clone, Debug)]
+struct Local {
+    token: Token,
+    kind: LocalKind,
+    defined: bool,
+    used: bool,
+    arity: Option<usize>,
+}
+
+impl Local {
+    fn new(token: &Token, kind: LocalKind, arity: Option<usize>) -> Self {
+        Local {
+            token: token.clone(),
+            kind,
+            defined: false,
+            used: false,
+            arity,
+        }
+    }
 }
 
 impl<'a> Resolver<'a> {
@@ -34,25 +84,69 @@ impl<'a> Resolver<'a> {
         Resolver {
             interpreter,
             scopes: Vec::new(),
+            globals: HashMap::new(),
+            class_statics: HashMap::new(),
+            class_privates: HashMap::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            enclosing_class: None,
             loop_depth: 0,
+            in_generator: false,
+            in_async: false,
+            allowed_await: None,
+            lint: false,
+            diagnostics: Vec::new(),
+            definitions: Vec::new(),
         }
     }
 
+    /// Resolver used by `rei check`, which also reports lints that are
+    /// harmless at runtime (unused locals, unreachable code, arity...).
+    pub fn with_lints(interpreter: &'a mut Interpreter) -> Self {
+        let mut resolver = Resolver::new(interpreter);
+        resolver.lint = true;
+        resolver
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
     pub fn resolve(&mut self, statements: &Vec<Stmt>) {
+        if self.scopes.is_empty() {
+            self.collect_globals(statements);
+        }
+
+        let mut terminator: Option<(&str, Option<Token>)> = None;
+        let mut reported = false;
         for stmt in statements {
+            if let Some((keyword, keyword_token)) = &terminator {
+                if !reported {
+                    if let Some(token) = stmt_token(stmt).or_else(|| keyword_token.clone()) {
+                        let msg = format!("Unreachable code after '{}'", keyword);
+                        self.warn(&token, &msg);
+                    }
+                    reported = true;
+                }
+            }
+
             self.resolve_stmt(stmt);
+
+            if terminator.is_none() {
+                terminator = terminator_keyword(stmt).map(|k| (k, stmt_token(stmt)));
+            }
         }
     }
 
     fn resolve_stmt(&mut self, stmt: &Stmt) {
+        self.allowed_await = stmt::awaited(stmt).map(Expr::id);
         match stmt {
             Stmt::Exception {
                 do_stmts,
                 fail_stmts,
                 fail_binding,
                 finish_stmts,
+                ..
             } => {
                 self.resolve_stmt(do_stmts);
 
@@ -78,69 +172,160 @@ impl<'a> Resolver<'a> {
                 superclass_refs,
                 methods,
                 static_methods,
+                fields,
+                accessors,
+                private_members: _,
+                traits,
                 expose: _,
             } => {
                 let enclosing_class = self.current_class.clone();
-                self.current_class = ClassType::Class;
+                let enclosing_name = self.enclosing_class.replace(name.lexeme.clone());
+                let class_type = if superclass_refs.is_empty() {
+                    ClassType::Class
+                } else {
+                    ClassType::Subclass
+                };
+                self.current_class = class_type.clone();
 
-                self.declare(name);
+                self.declare(
+                    name,
+                    LocalKind::Class,
+                    class_arity(superclass_refs, methods),
+                );
                 self.define(name);
 
-                if !superclass_refs.is_empty() {
-                    for superclass in superclass_refs {
-                        if let Expr::Variable {
-                            name: super_name, ..
-                        } = superclass
-                        {
-                            if name.lexeme == super_name.lexeme {
-                                panic!("A class cannot inherit from itself.");
-                            }
-                        }
+                for implemented in traits {
+                    self.resolve_expr(implemented);
+                }
 
-                        self.resolve_expr(superclass);
+                for superclass in superclass_refs {
+                    if let Expr::Variable {
+                        name: super_name, ..
+                    } = superclass
+                    {
+                        if name.lexeme == super_name.lexeme {
+                            self.error(super_name, "A class cannot inherit from itself.");
+                        }
                     }
-                    self.begin_scope();
+
+                    self.resolve_expr(superclass);
                 }
 
-                // Begin scope for `this`
+                // Scope for `base`, which every class has so that private
+                // access can find the class it's checked from
                 self.begin_scope();
                 if let Some(scope) = self.scopes.last_mut() {
-                    scope.insert("this".to_string(), true);
+                    let mut base = Local::new(&Token::fake(TokenType::Base), LocalKind::This, None);
+                    base.defined = true;
+                    base.used = true;
+                    scope.insert("base".to_string(), base);
+                }
+
+                // Static methods are never bound, so they don't see `this`
+                self.current_class = ClassType::Static;
+                for static_method in static_methods {
+                    if let Stmt::Function {
+                        params,
+                        body,
+                        is_async,
+                        ..
+                    } = static_method
+                    {
+                        self.resolve_function(params, body, FunctionType::Static, *is_async);
+                    }
                 }
 
+                // Field initializers run before any instance is bound
+                self.current_class = ClassType::Field;
+                for field in fields {
+                    if let Stmt::Field {
+                        initializer: Some(initializer),
+                        ..
+                    } = field
+                    {
+                        self.resolve_expr(initializer);
+                    }
+                }
+                self.current_class = class_type;
+
+                self.begin_this_scope();
+
                 for method in methods {
-                    if let Stmt::Function { name, params, body } = method {
+                    if let Stmt::Function {
+                        name,
+                        params,
+                        body,
+                        is_async,
+                    } = method
+                    {
                         let prev_function = self.current_function.clone();
                         self.current_function = if name.lexeme == "init" {
                             FunctionType::Initializer
                         } else {
                             FunctionType::Method
                         };
+                        if *is_async && name.lexeme == "init" {
+                            self.error(name, "An initializer can't be async.");
+                        }
 
-                        self.resolve_function(params, body, self.current_function.clone());
+                        self.resolve_function(
+                            params,
+                            body,
+                            self.current_function.clone(),
+                            *is_async,
+                        );
                         self.current_function = prev_function;
                     }
                 }
 
-                for static_method in static_methods {
-                    if let Stmt::Function { params, body, .. } = static_method {
-                        self.resolve_function(params, body, FunctionType::Static);
+                for accessor in accessors {
+                    if let Stmt::Accessor { params, body, .. } = accessor {
+                        self.resolve_function(params, body, FunctionType::Method, false);
                     }
                 }
 
-                if !superclass_refs.is_empty() {
-                    self.end_scope();
-                }
+                self.end_scope();
+                self.end_scope();
 
+                self.current_class = enclosing_class;
+                self.enclosing_class = enclosing_name;
+            }
+            Stmt::Trait { name, methods, .. } => {
+                self.declare(name, LocalKind::Class, None);
+                self.define(name);
+
+                // Default methods are bound like methods of the implementing class
+                let enclosing_class = self.current_class.clone();
+                let enclosing_name = self.enclosing_class.replace(name.lexeme.clone());
+                self.current_class = ClassType::Class;
+                self.begin_this_scope();
+                for method in methods {
+                    if let Stmt::Function {
+                        params,
+                        body,
+                        is_async,
+                        ..
+                    } = method
+                    {
+                        self.resolve_function(params, body, FunctionType::Method, *is_async);
+                    }
+                }
                 self.end_scope();
                 self.current_class = enclosing_class;
+                self.enclosing_class = enclosing_name;
+            }
+            Stmt::Enum { name, .. } => {
+                self.declare(name, LocalKind::Class, None);
+                self.define(name);
             }
             Stmt::Expression { expression } => {
                 self.resolve_expr(expression);
             }
+            // Resolved with their class
+            Stmt::Field { .. } | Stmt::Accessor { .. } => {}
             Stmt::Let { name, initializer } => {
                 // Variable declaration
-                self.declare(name);
+                self.declare(name, LocalKind::Variable, None);
                 self.resolve_expr(initializer);
                 self.define(name);
             }
@@ -161,45 +346,135 @@ impl<'a> Resolver<'a> {
                 self.resolve_stmt(body);
                 self.loop_depth -= 1;
             }
-            Stmt::Function { name, params, body } => {
-                self.declare(name);
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                self.begin_scope();
+                if let Some(init) = initializer {
+                    self.resolve_stmt(init);
+                }
+                self.loop_depth += 1;
+                if let Some(cond) = condition {
+                    self.resolve_expr(cond);
+                }
+                self.resolve_stmt(body);
+                if let Some(inc) = increment {
+                    self.resolve_expr(inc);
+                }
+                self.loop_depth -= 1;
+                self.end_scope();
+            }
+            Stmt::Loop {
+                name,
+                start,
+                end,
+                step,
+                body,
+                ..
+            } => {
+                self.begin_scope();
+                self.resolve_expr(start);
+                self.declare(name, LocalKind::Variable, None);
+                self.define(name);
+                // The counter is read on every iteration
+                if let Some(local) = self
+                    .scopes
+                    .last_mut()
+                    .and_then(|scope| scope.get_mut(&name.lexeme))
+                {
+                    local.used = true;
+                }
+                self.resolve_expr(end);
+                self.resolve_expr(step);
+                self.loop_depth += 1;
+                self.resolve_stmt(body);
+                self.loop_depth -= 1;
+                self.end_scope();
+            }
+            Stmt::Each {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                self.resolve_expr(iterable);
+                self.begin_scope();
+                self.declare(name, LocalKind::Variable, None);
                 self.define(name);
-                self.resolve_function(params, body, FunctionType::Function);
+                self.loop_depth += 1;
+                self.resolve_stmt(body);
+                self.loop_depth -= 1;
+                self.end_scope();
+            }
+            Stmt::Use { class, .. } => {
+                if let Some(class) = class {
+                    self.resolve_stmt(class);
+                }
+            }
+            Stmt::Function {
+                name,
+                params,
+                body,
+                is_async,
+            } => {
+                self.declare(name, LocalKind::Function, Some(params.len()));
+                self.define(name);
+                self.resolve_function(params, body, FunctionType::Function, *is_async);
             }
-            Stmt::Return { keyword: _, value } => {
+            Stmt::Return { keyword, value } => {
                 if let FunctionType::None = self.current_function {
-                    panic!("Cannot return from top-level code.");
+                    self.error(keyword, "Cannot return from top-level code.");
                 }
                 if let Some(val) = value {
-                    match self.current_function {
-                        FunctionType::Initializer => {
-                            panic!("aaARRRRR")
-                        }
-                        _ => {}
+                    if let FunctionType::Initializer = self.current_function {
+                        self.error(keyword, "Cannot return a value from an initializer.");
+                    }
+                    // An async function with a `yield` is reported at the yield
+                    if self.in_generator && !self.in_async {
+                        self.error(keyword, "Cannot return a value from a generator.");
+                    }
+                    self.resolve_expr(val);
+                }
+            }
+            Stmt::Yield { keyword, value } => {
+                match self.current_function {
+                    FunctionType::None => self.error(keyword, "Cannot yield from top-level code."),
+                    FunctionType::Initializer => {
+                        self.error(keyword, "Cannot yield from an initializer.")
                     }
+                    _ => {}
+                }
+                if self.in_async {
+                    self.error(keyword, "Cannot yield from an async function.");
+                }
+                if let Some(val) = value {
                     self.resolve_expr(val);
                 }
             }
-            Stmt::Print { expression } => {
+            Stmt::Print { expression, .. } => {
                 self.resolve_expr(expression);
             }
-            Stmt::PrintLn { expression } => {
+            Stmt::PrintLn { expression, .. } => {
                 self.resolve_expr(expression);
             }
-            Stmt::Throw { expression } => {
+            Stmt::Throw { expression, .. } => {
                 self.resolve_expr(expression);
             }
-            Stmt::Fatal { expression } => {
+            Stmt::Fatal { expression, .. } => {
                 self.resolve_expr(expression);
             }
-            Stmt::Break => {
+            Stmt::Break { keyword } => {
                 if self.loop_depth == 0 {
-                    panic!("Cannot use 'break' outside of a loop.");
+                    self.error(keyword, "Cannot use 'break' outside of a loop.");
                 }
             }
-            Stmt::Continue => {
+            Stmt::Continue { keyword } => {
                 if self.loop_depth == 0 {
-                    panic!("Cannot use 'continue' outside of a loop.");
+                    self.error(keyword, "Cannot use 'continue' outside of a loop.");
                 }
             }
         }
@@ -211,18 +486,48 @@ impl<'a> Resolver<'a> {
                 id,
                 keyword: _,
                 method,
-                ..
+                args,
             } => {
+                if !META_METHODS.contains(&method.lexeme.as_str()) {
+                    let msg = format!("Unknown meta method '@{}'", method.lexeme);
+                    self.error(method, &msg);
+                }
+
+                self.record_enclosing_class(*id);
                 if let Some(distance) = self.resolve_this_distance() {
                     self.interpreter.resolve(*id, distance);
-                } else {
-                    panic!("Bad: {}", method);
+                } else if !matches!(method.lexeme.as_str(), "typeof" | "mro") {
+                    let msg = format!(
+                        "Cannot use '@{}' outside of instance methods.",
+                        method.lexeme
+                    );
+                    self.error(method, &msg);
+                }
+
+                for arg in args {
+                    self.resolve_expr(arg);
                 }
             }
             Expr::Range { id: _, start, end } => {
                 self.resolve_expr(start);
                 self.resolve_expr(end);
             }
+            Expr::Await { id, keyword, value } => {
+                // At top level `await` blocks, so it can go anywhere. Tasks are
+                // resumed statement by statement, so there it can't sit in the
+                // middle of an expression.
+                let whole = self.allowed_await.take() == Some(*id);
+                let top_level = matches!(self.current_function, FunctionType::None);
+                if !top_level && !self.in_async {
+                    self.error(keyword, "Cannot use 'await' outside of an async function.");
+                } else if !top_level && !whole {
+                    self.error(
+                        keyword,
+                        "'await' must be a whole statement, initializer, assignment or return value.",
+                    );
+                }
+                self.resolve_expr(value);
+            }
             Expr::Assign { id: _, name, value } => {
                 self.resolve_expr(value);
                 self.resolve_local(expr, name);
@@ -248,34 +553,70 @@ impl<'a> Resolver<'a> {
             Expr::Call {
                 id: _,
                 callee,
-                paren: _,
+                paren,
                 arguments,
             } => {
                 self.resolve_expr(callee);
                 for arg in arguments {
                     self.resolve_expr(arg);
                 }
+                if self.lint {
+                    self.check_arity(callee, paren, arguments.len());
+                }
             }
             Expr::This { id: _, keyword } => {
                 match self.current_class {
-                    ClassType::None => panic!(), // Bad Code, add a custom error thrower for this
-                    ClassType::Class => {}
+                    ClassType::None => {
+                        self.error(keyword, "Cannot use 'this' outside of a class.");
+                        return;
+                    }
+                    ClassType::Static => {
+                        self.error(keyword, "Cannot use 'this' in a static method.");
+                        return;
+                    }
+                    ClassType::Field => {
+                        self.error(keyword, "Cannot use 'this' in a field initializer.");
+                        return;
+                    }
+                    ClassType::Class | ClassType::Subclass => {}
                 }
                 self.resolve_local(expr, keyword);
             }
-            Expr::Get {
-                id: _,
-                object,
-                name: _,
-            } => {
+            Expr::Base { keyword, .. } => {
+                match self.current_class {
+                    ClassType::None => {
+                        self.error(keyword, "Cannot use 'base' outside of a class.");
+                        return;
+                    }
+                    ClassType::Static => {
+                        self.error(keyword, "Cannot use 'base' in a static method.");
+                        return;
+                    }
+                    ClassType::Field => {
+                        self.error(keyword, "Cannot use 'base' in a field initializer.");
+                        return;
+                    }
+                    ClassType::Class => {
+                        self.error(keyword, "Cannot use 'base' in a class with no superclass.");
+                        return;
+                    }
+                    ClassType::Subclass => {}
+                }
+                self.resolve_local(expr, keyword);
+            }
+            Expr::Get { id, object, name } => {
+                self.record_enclosing_class(*id);
+                self.check_private_access(object, name);
                 self.resolve_expr(object);
             }
             Expr::Set {
-                id: _,
+                id,
                 object,
-                name: _,
+                name,
                 value,
             } => {
+                self.record_enclosing_class(*id);
+                self.check_private_access(object, name);
                 self.resolve_expr(value);
                 self.resolve_expr(object);
             }
@@ -295,8 +636,10 @@ impl<'a> Resolver<'a> {
             Expr::Variable { id: _, name } => {
                 // Check for use in own initializer.
                 if let Some(scope) = self.scopes.last() {
-                    if let Some(false) = scope.get(&name.lexeme) {
-                        panic!("Cannot read local variable in its own initializer.");
+                    if let Some(local) = scope.get(&name.lexeme) {
+                        if !local.defined {
+                            self.error(name, "Cannot read local variable in its own initializer.");
+                        }
                     }
                 }
                 self.resolve_local(expr, name);
@@ -309,53 +652,117 @@ impl<'a> Resolver<'a> {
     }
 
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        if let Some(scope) = self.scopes.pop() {
+            if !self.lint {
+                return;
+            }
+
+            let mut unused: Vec<&Local> = scope
+                .values()
+                .filter(|l| l.kind == LocalKind::Variable)
+                .filter(|l| !l.used && !l.token.lexeme.starts_with('_'))
+                .collect();
+            unused.sort_by_key(|l| (l.token.line, l.token.place));
+
+            let warnings: Vec<Diagnostic> = unused
+                .into_iter()
+                .map(|l| {
+                    let msg = format!("Local variable '{}' is never used", l.token.lexeme);
+                    Diagnostic::warning(&l.token, &msg)
+                })
+                .collect();
+            self.diagnostics.extend(warnings);
+        }
     }
 
-    fn declare(&mut self, name: &Token) {
+    fn declare(&mut self, name: &Token, kind: LocalKind, arity: Option<usize>) {
         if self.scopes.is_empty() {
             return;
         }
         let scope = self.scopes.last_mut().unwrap();
         if scope.contains_key(&name.lexeme) {
-            panic!("Variable with this name already declared in this scope.");
+            self.error(
+                name,
+                "Variable with this name already declared in this scope.",
+            );
+            return;
         }
-        scope.insert(name.lexeme.clone(), false);
+        scope.insert(name.lexeme.clone(), Local::new(name, kind, arity));
     }
 
     fn define(&mut self, name: &Token) {
-        if self.scopes.is_empty() {
-            return;
-        }
-        self.scopes
+        if let Some(local) = self
+            .scopes
             .last_mut()
-            .unwrap()
-            .insert(name.lexeme.clone(), true);
+            .and_then(|scope| scope.get_mut(&name.lexeme))
+        {
+            local.defined = true;
+        }
+    }
+
+    fn begin_this_scope(&mut self) {
+        self.begin_scope();
+        if let Some(scope) = self.scopes.last_mut() {
+            let mut this = Local::new(&Token::fake(TokenType::This), LocalKind::This, None);
+            this.defined = true;
+            this.used = true;
+            scope.insert("this".to_string(), this);
+        }
     }
 
-    fn resolve_function(&mut self, params: &Vec<Token>, body: &Vec<Stmt>, ty: FunctionType) {
+    fn resolve_function(
+        &mut self,
+        params: &Vec<Token>,
+        body: &Vec<Stmt>,
+        ty: FunctionType,
+        is_async: bool,
+    ) {
         let enclosing_function = self.current_function.clone();
+        let enclosing_loop_depth = self.loop_depth;
+        let enclosing_generator = self.in_generator;
+        let enclosing_async = self.in_async;
         self.current_function = ty;
+        self.loop_depth = 0;
+        self.in_generator = stmt::contains_yield(body);
+        self.in_async = is_async;
 
         self.begin_scope();
         for param in params {
-            self.declare(param);
+            self.declare(param, LocalKind::Parameter, None);
             self.define(param);
         }
         self.resolve(body);
         self.end_scope();
 
         self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+        self.in_generator = enclosing_generator;
+        self.in_async = enclosing_async;
     }
 
     fn resolve_local(&mut self, expr: &Expr, name: &Token) {
-        for (i, scope) in self.scopes.iter().rev().enumerate() {
-            if scope.contains_key(&name.lexeme) {
+        for (i, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(local) = scope.get_mut(&name.lexeme) {
+                local.used = true;
+                if self.lint && local.kind != LocalKind::This {
+                    self.definitions.push((name.clone(), local.token.clone()));
+                }
                 self.interpreter.resolve(expr.id(), i);
                 return;
             }
         }
+
+        if self.lint {
+            if let Some(global) = self.globals.get(&name.lexeme) {
+                self.definitions.push((name.clone(), global.token.clone()));
+            }
+        }
+
         // Not found: leave as global.
+        if self.lint && !self.is_known_global(&name.lexeme) {
+            let msg = format!("Undefined variable '{}'", name.lexeme);
+            self.error(name, &msg);
+        }
     }
 
     fn resolve_this_distance(&self) -> Option<usize> {
@@ -366,4 +773,244 @@ impl<'a> Resolver<'a> {
         }
         None
     }
+
+    /// Remembers how far the nearest class body is, whose `base` holds the
+    /// class at runtime.
+    fn record_enclosing_class(&mut self, id: ExprId) {
+        for (i, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key("base") {
+                self.interpreter.resolve_enclosing_class(id, i);
+                return;
+            }
+        }
+    }
+
+    /// Reports `Class.member` and `Class(...).member` outside of any class
+    /// body when that class declares the member `private`. Other accesses
+    /// depend on the value at runtime and are checked by the interpreter.
+    fn check_private_access(&mut self, object: &Expr, name: &Token) {
+        if self.enclosing_class.is_some() {
+            return;
+        }
+
+        let class = match object {
+            Expr::Variable { name, .. } => name,
+            Expr::Call { callee, .. } => match callee.as_ref() {
+                Expr::Variable { name, .. } => name,
+                _ => return,
+            },
+            _ => return,
+        };
+        if self.scopes.iter().any(|s| s.contains_key(&class.lexeme)) {
+            return;
+        }
+
+        let is_private = self
+            .class_privates
+            .get(&class.lexeme)
+            .is_some_and(|privates| privates.contains(&name.lexeme));
+        if is_private {
+            let msg = format!(
+                "Cannot access private member '{}' outside of class '{}'.",
+                name.lexeme, class.lexeme
+            );
+            self.error(name, &msg);
+        }
+    }
+
+    fn collect_globals(&mut self, statements: &Vec<Stmt>) {
+        for stmt in statements {
+            match stmt {
+                Stmt::Let { name, .. } => {
+                    let local = Local::new(name, LocalKind::Variable, None);
+                    self.globals.insert(name.lexeme.clone(), local);
+                }
+                Stmt::Function { name, params, .. } => {
+                    let local = Local::new(name, LocalKind::Function, Some(params.len()));
+                    self.globals.insert(name.lexeme.clone(), local);
+                }
+                Stmt::Trait { name, .. } | Stmt::Enum { name, .. } => {
+                    let local = Local::new(name, LocalKind::Class, None);
+                    self.globals.insert(name.lexeme.clone(), local);
+                }
+                Stmt::Use {
+                    class: Some(class), ..
+                } => self.collect_globals(&vec![class.as_ref().clone()]),
+                Stmt::Class {
+                    name,
+                    superclass_refs,
+                    methods,
+                    static_methods,
+                    private_members,
+                    ..
+                } => {
+                    let arity = class_arity(superclass_refs, methods);
+                    let local = Local::new(name, LocalKind::Class, arity);
+                    self.globals.insert(name.lexeme.clone(), local);
+
+                    let mut statics = HashMap::new();
+                    for static_method in static_methods {
+                        if let Stmt::Function { name, params, .. } = static_method {
+                            statics.insert(name.lexeme.clone(), params.len());
+                        }
+                    }
+                    self.class_statics.insert(name.lexeme.clone(), statics);
+
+                    let privates = private_members.iter().map(|m| m.lexeme.clone()).collect();
+                    self.class_privates.insert(name.lexeme.clone(), privates);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_known_global(&self, name: &str) -> bool {
+        self.globals.contains_key(name)
+            || self
+                .interpreter
+                .environment
+                .borrow()
+                .values
+                .contains_key(name)
+    }
+
+    fn lookup_arity(&self, name: &str) -> Option<usize> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(local) = scope.get(name) {
+                return local.arity;
+            }
+        }
+
+        if let Some(global) = self.globals.get(name) {
+            return global.arity;
+        }
+
+        match self.interpreter.environment.borrow().values.get(name) {
+            Some(Object::Callable(native)) => Some(native.arity()),
+            _ => None,
+        }
+    }
+
+    fn check_arity(&mut self, callee: &Expr, paren: &Token, given: usize) {
+        let (name, expected) = match callee {
+            Expr::Variable { name, .. } => (name.lexeme.clone(), self.lookup_arity(&name.lexeme)),
+            Expr::Get { object, name, .. } => match object.as_ref() {
+                // Static call on a class declared in this file, e.g. `Math.pow(a, b)`
+                Expr::Variable {
+                    name: class_name, ..
+                } if !self.is_local(&class_name.lexeme) => {
+                    let expected = self
+                        .class_statics
+                        .get(&class_name.lexeme)
+                        .and_then(|statics| statics.get(&name.lexeme))
+                        .copied();
+                    (format!("{}.{}", class_name.lexeme, name.lexeme), expected)
+                }
+                _ => return,
+            },
+            _ => return,
+        };
+
+        if let Some(expected) = expected {
+            if expected != given {
+                let msg = format!(
+                    "'{}' expects {} argument(s) but got {}",
+                    name, expected, given
+                );
+                self.error(paren, &msg);
+            }
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains_key(name))
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.diagnostics.push(Diagnostic::error(token, message));
+    }
+
+    fn warn(&mut self, token: &Token, message: &str) {
+        self.diagnostics.push(Diagnostic::warning(token, message));
+    }
+}
+
+/// Arity of a class constructor, when it can be known without running the code.
+fn class_arity(superclass_refs: &[Expr], methods: &[Stmt]) -> Option<usize> {
+    for method in methods {
+        if let Stmt::Function { name, params, .. } = method {
+            if name.lexeme == "init" {
+                return Some(params.len());
+            }
+        }
+    }
+
+    if superclass_refs.is_empty() {
+        Some(0)
+    } else {
+        // `init` may be inherited
+        None
+    }
+}
+
+fn terminator_keyword(stmt: &Stmt) -> Option<&'static str> {
+    match stmt {
+        Stmt::Return { .. } => Some("return"),
+        Stmt::Break { .. } => Some("break"),
+        Stmt::Continue { .. } => Some("continue"),
+        Stmt::Throw { .. } => Some("throw"),
+        Stmt::Fatal { .. } => Some("fatal"),
+        _ => None,
+    }
+}
+
+/// First token of a statement that can be used to point at it in a report.
+pub fn stmt_token(stmt: &Stmt) -> Option<Token> {
+    match stmt {
+        Stmt::Block { statements } => statements.iter().find_map(stmt_token),
+        Stmt::Class { name, .. }
+        | Stmt::Function { name, .. }
+        | Stmt::Let { name, .. }
+        | Stmt::Field { name, .. }
+        | Stmt::Accessor { name, .. }
+        | Stmt::Trait { name, .. }
+        | Stmt::Enum { name, .. } => Some(name.clone()),
+        Stmt::Expression { expression } => expr_token(expression).cloned(),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => {
+            expr_token(condition).cloned()
+        }
+        Stmt::Print { keyword, .. }
+        | Stmt::PrintLn { keyword, .. }
+        | Stmt::Throw { keyword, .. }
+        | Stmt::Fatal { keyword, .. }
+        | Stmt::Exception { keyword, .. }
+        | Stmt::For { keyword, .. }
+        | Stmt::Loop { keyword, .. }
+        | Stmt::Each { keyword, .. }
+        | Stmt::Use { keyword, .. }
+        | Stmt::Return { keyword, .. }
+        | Stmt::Yield { keyword, .. }
+        | Stmt::Break { keyword }
+        | Stmt::Continue { keyword } => Some(keyword.clone()),
+    }
+}
+
+pub fn expr_token(expr: &Expr) -> Option<&Token> {
+    match expr {
+        Expr::Assign { name, .. } | Expr::Variable { name, .. } => Some(name),
+        Expr::Binary { left, operator, .. } | Expr::Logical { left, operator, .. } => {
+            expr_token(left).or(Some(operator))
+        }
+        Expr::Call { callee, paren, .. } => expr_token(callee).or(Some(paren)),
+        Expr::Get { object, name, .. } | Expr::Set { object, name, .. } => {
+            expr_token(object).or(Some(name))
+        }
+        Expr::Meta { keyword, .. } => Some(keyword),
+        Expr::Grouping { expression, .. } => expr_token(expression),
+        Expr::Literal { .. } => None,
+        Expr::This { keyword, .. } | Expr::Base { keyword, .. } => Some(keyword),
+        Expr::Unary { operator, .. } => Some(operator),
+        Expr::Range { start, end, .. } => expr_token(start).or_else(|| expr_token(end)),
+        Expr::Await { keyword, .. } => Some(keyword),
+    }
 }
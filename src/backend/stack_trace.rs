@@ -1,3 +1,4 @@
+use crate::crux::output;
 use crate::crux::util;
 
 #[derive(Clone, Debug)]
@@ -47,7 +48,7 @@ impl ExecContext {
 
     pub fn pop_call(&mut self) {
         if self.call_stack.pop().is_none() {
-            eprintln!("[exec_ctx] Warning: tried to pop from empty stack trace 🤡");
+            output::err("[exec_ctx] Warning: tried to pop from empty stack trace 🤡\n");
         }
     }
 }
@@ -6,37 +6,103 @@ use crate::frontend::expr::Expr;
 pub trait Visitor<T> {
     fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> T;
     fn visit_expression_stmt(&mut self, expression: &Expr) -> T;
+    #[allow(clippy::too_many_arguments)]
     fn visit_class_stmt(
         &mut self,
         name: &Token,
         superclass_refs: &Vec<Expr>,
         methods: &Vec<Stmt>,
         static_methods: &Vec<Stmt>,
+        fields: &[Stmt],
+        accessors: &[Stmt],
+        private_members: &[Token],
+        traits: &[Expr],
         expose: &bool,
     ) -> T;
-    fn visit_function_stmt(&mut self, name: &Token, params: &Vec<Token>, body: &Vec<Stmt>) -> T;
+    fn visit_trait_stmt(
+        &mut self,
+        name: &Token,
+        required: &[Stmt],
+        methods: &[Stmt],
+        expose: bool,
+    ) -> T;
+    fn visit_enum_stmt(
+        &mut self,
+        name: &Token,
+        variants: &[(Token, Vec<Token>)],
+        expose: bool,
+    ) -> T;
+    fn visit_field_stmt(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Box<Expr>>,
+        constant: bool,
+        is_static: bool,
+    ) -> T;
+    fn visit_accessor_stmt(
+        &mut self,
+        kind: &Token,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> T;
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        is_async: bool,
+    ) -> T;
     fn visit_if_stmt(
         &mut self,
         condition: &Expr,
         then_branch: &Stmt,
         else_branch: &Option<Box<Stmt>>,
     ) -> T;
-    fn visit_print_stmt(&mut self, expression: &Expr) -> T;
-    fn visit_println_stmt(&mut self, expression: &Expr) -> T;
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) -> T;
+    fn visit_println_stmt(&mut self, keyword: &Token, expression: &Expr) -> T;
     fn visit_let_stmt(&mut self, name: &Token, initializer: &Expr) -> T;
     fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> T;
+    fn visit_for_stmt(
+        &mut self,
+        keyword: &Token,
+        initializer: &Option<Box<Stmt>>,
+        condition: &Option<Box<Expr>>,
+        increment: &Option<Box<Expr>>,
+        body: &Stmt,
+    ) -> T;
+    fn visit_loop_stmt(
+        &mut self,
+        keyword: &Token,
+        name: &Token,
+        start: &Expr,
+        end: &Expr,
+        step: &Expr,
+        body: &Stmt,
+    ) -> T;
+    fn visit_each_stmt(&mut self, keyword: &Token, name: &Token, iterable: &Expr, body: &Stmt)
+        -> T;
+    fn visit_use_stmt(
+        &mut self,
+        keyword: &Token,
+        path: &str,
+        alias: &Token,
+        class: &Option<Box<Stmt>>,
+    ) -> T;
     fn visit_exception_stmt(
         &mut self,
+        keyword: &Token,
         do_stmts: &Stmt,
         fail_stmts: &Stmt,
         fail_binding: &Option<Box<Stmt>>,
         finish_stmts: &Option<Box<Stmt>>,
     ) -> T;
     fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) -> T;
-    fn visit_throw_stmt(&mut self, expression: &Box<Expr>) -> T;
-    fn visit_fatal_stmt(&mut self, expression: &Box<Expr>) -> T;
-    fn visit_break_stmt(&mut self) -> T;
-    fn visit_continue_stmt(&mut self) -> T;
+    fn visit_yield_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) -> T;
+    fn visit_throw_stmt(&mut self, keyword: &Token, expression: &Expr) -> T;
+    fn visit_fatal_stmt(&mut self, keyword: &Token, expression: &Expr) -> T;
+    fn visit_break_stmt(&mut self, keyword: &Token) -> T;
+    fn visit_continue_stmt(&mut self, keyword: &Token) -> T;
 }
 
 #[derive(Clone, Debug)]
@@ -50,9 +116,46 @@ pub enum Stmt {
         superclass_refs: Vec<Expr>,
         methods: Vec<Stmt>,
         static_methods: Vec<Stmt>,
+        fields: Vec<Stmt>,
+        accessors: Vec<Stmt>,
+        /// Names of the members declared `private`
+        private_members: Vec<Token>,
+        /// Traits listed after `impl`
+        traits: Vec<Expr>,
+        expose: bool,
+    },
+
+    /// `required` holds body-less signatures, `methods` the default implementations
+    Trait {
+        name: Token,
+        required: Vec<Stmt>,
+        methods: Vec<Stmt>,
+        expose: bool,
+    },
+
+    /// Each variant comes with the names of its payload fields, empty for unit variants
+    Enum {
+        name: Token,
+        variants: Vec<(Token, Vec<Token>)>,
         expose: bool,
     },
 
+    /// `let`/`const` member of a class body, optionally `static`
+    Field {
+        name: Token,
+        initializer: Option<Box<Expr>>,
+        constant: bool,
+        is_static: bool,
+    },
+
+    /// `get name() {}` or `set name(value) {}` in a class body
+    Accessor {
+        kind: Token,
+        name: Token,
+        params: Vec<Token>,
+        body: Vec<Stmt>,
+    },
+
     Expression {
         expression: Box<Expr>,
     },
@@ -61,6 +164,8 @@ pub enum Stmt {
         name: Token,
         params: Vec<Token>,
         body: Vec<Stmt>,
+        /// Declared `async fn`, calling it starts a task
+        is_async: bool,
     },
 
     If {
@@ -70,6 +175,7 @@ pub enum Stmt {
     },
 
     Print {
+        keyword: Token,
         expression: Box<Expr>,
     },
 
@@ -78,7 +184,14 @@ pub enum Stmt {
         value: Option<Box<Expr>>,
     },
 
+    /// Suspends the generator the statement is in, handing out `value`
+    Yield {
+        keyword: Token,
+        value: Option<Box<Expr>>,
+    },
+
     Exception {
+        keyword: Token,
         do_stmts: Box<Stmt>,
         fail_stmts: Box<Stmt>,
         fail_binding: Option<Box<Stmt>>,
@@ -86,14 +199,17 @@ pub enum Stmt {
     },
 
     Throw {
+        keyword: Token,
         expression: Box<Expr>,
     },
 
     Fatal {
+        keyword: Token,
         expression: Box<Expr>,
     },
 
     PrintLn {
+        keyword: Token,
         expression: Box<Expr>,
     },
 
@@ -107,11 +223,61 @@ pub enum Stmt {
         body: Box<Stmt>,
     },
 
-    Break,
-    Continue,
+    For {
+        keyword: Token,
+        initializer: Option<Box<Stmt>>,
+        condition: Option<Box<Expr>>,
+        increment: Option<Box<Expr>>,
+        body: Box<Stmt>,
+    },
+
+    Loop {
+        keyword: Token,
+        name: Token,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        step: Box<Expr>,
+        body: Box<Stmt>,
+    },
+
+    /// `loop (let name in iterable)`, over a generator or a vector
+    Each {
+        keyword: Token,
+        name: Token,
+        iterable: Box<Expr>,
+        body: Box<Stmt>,
+    },
+
+    Use {
+        keyword: Token,
+        path: String,
+        alias: Token,
+        class: Option<Box<Stmt>>,
+    },
+
+    Break {
+        keyword: Token,
+    },
+
+    Continue {
+        keyword: Token,
+    },
 }
 
 impl Stmt {
+    /// Whether the statement only declares something a thread can declare
+    /// again: functions, classes, traits, enums and imports.
+    pub fn is_declaration(&self) -> bool {
+        matches!(
+            self,
+            Stmt::Function { .. }
+                | Stmt::Class { .. }
+                | Stmt::Trait { .. }
+                | Stmt::Enum { .. }
+                | Stmt::Use { .. }
+        )
+    }
+
     pub fn accept<T>(&self, visitor: &mut dyn Visitor<T>) -> T {
         match self {
             Stmt::Block { statements } => visitor.visit_block_stmt(statements),
@@ -120,32 +286,180 @@ impl Stmt {
                 superclass_refs,
                 methods,
                 static_methods,
+                fields,
+                accessors,
+                private_members,
+                traits,
+                expose,
+            } => visitor.visit_class_stmt(
+                name,
+                superclass_refs,
+                methods,
+                static_methods,
+                fields,
+                accessors,
+                private_members,
+                traits,
+                expose,
+            ),
+            Stmt::Trait {
+                name,
+                required,
+                methods,
                 expose,
-            } => visitor.visit_class_stmt(name, superclass_refs, methods, static_methods, expose),
+            } => visitor.visit_trait_stmt(name, required, methods, *expose),
+            Stmt::Enum {
+                name,
+                variants,
+                expose,
+            } => visitor.visit_enum_stmt(name, variants, *expose),
+            Stmt::Field {
+                name,
+                initializer,
+                constant,
+                is_static,
+            } => visitor.visit_field_stmt(name, initializer, *constant, *is_static),
+            Stmt::Accessor {
+                kind,
+                name,
+                params,
+                body,
+            } => visitor.visit_accessor_stmt(kind, name, params, body),
             Stmt::Expression { expression } => visitor.visit_expression_stmt(expression),
-            Stmt::Function { name, params, body } => {
-                visitor.visit_function_stmt(name, params, body)
-            }
+            Stmt::Function {
+                name,
+                params,
+                body,
+                is_async,
+            } => visitor.visit_function_stmt(name, params, body, *is_async),
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
             } => visitor.visit_if_stmt(condition, then_branch, else_branch),
-            Stmt::Print { expression } => visitor.visit_print_stmt(expression),
+            Stmt::Print {
+                keyword,
+                expression,
+            } => visitor.visit_print_stmt(keyword, expression),
             Stmt::Return { keyword, value } => visitor.visit_return_stmt(keyword, value),
+            Stmt::Yield { keyword, value } => visitor.visit_yield_stmt(keyword, value),
             Stmt::Exception {
+                keyword,
+                do_stmts,
+                fail_stmts,
+                fail_binding,
+                finish_stmts,
+            } => visitor.visit_exception_stmt(
+                keyword,
                 do_stmts,
                 fail_stmts,
                 fail_binding,
                 finish_stmts,
-            } => visitor.visit_exception_stmt(do_stmts, fail_stmts, fail_binding, finish_stmts),
-            Stmt::Throw { expression } => visitor.visit_throw_stmt(expression),
-            Stmt::Fatal { expression } => visitor.visit_fatal_stmt(expression),
-            Stmt::PrintLn { expression } => visitor.visit_println_stmt(expression),
+            ),
+            Stmt::Throw {
+                keyword,
+                expression,
+            } => visitor.visit_throw_stmt(keyword, expression),
+            Stmt::Fatal {
+                keyword,
+                expression,
+            } => visitor.visit_fatal_stmt(keyword, expression),
+            Stmt::PrintLn {
+                keyword,
+                expression,
+            } => visitor.visit_println_stmt(keyword, expression),
             Stmt::Let { name, initializer } => visitor.visit_let_stmt(name, initializer),
             Stmt::While { condition, body } => visitor.visit_while_stmt(condition, body),
-            Stmt::Break => visitor.visit_break_stmt(),
-            Stmt::Continue => visitor.visit_continue_stmt(),
+            Stmt::For {
+                keyword,
+                initializer,
+                condition,
+                increment,
+                body,
+            } => visitor.visit_for_stmt(keyword, initializer, condition, increment, body),
+            Stmt::Loop {
+                keyword,
+                name,
+                start,
+                end,
+                step,
+                body,
+            } => visitor.visit_loop_stmt(keyword, name, start, end, step, body),
+            Stmt::Each {
+                keyword,
+                name,
+                iterable,
+                body,
+            } => visitor.visit_each_stmt(keyword, name, iterable, body),
+            Stmt::Use {
+                keyword,
+                path,
+                alias,
+                class,
+            } => visitor.visit_use_stmt(keyword, path, alias, class),
+            Stmt::Break { keyword } => visitor.visit_break_stmt(keyword),
+            Stmt::Continue { keyword } => visitor.visit_continue_stmt(keyword),
         }
     }
 }
+
+/// True when a `yield` in these statements belongs to the function they are
+/// the body of. Nested functions and classes don't count.
+pub fn contains_yield(statements: &[Stmt]) -> bool {
+    contains(statements, &|statement| {
+        matches!(statement, Stmt::Yield { .. })
+    })
+}
+
+/// True when these statements `await` on behalf of the function they are
+/// the body of, see `awaited`.
+pub fn contains_await(statements: &[Stmt]) -> bool {
+    contains(statements, &|statement| awaited(statement).is_some())
+}
+
+/// The `await` a statement suspends on: `await x;`, `let a = await x;`,
+/// `a = await x;` or `return await x;`. The resolver rejects it anywhere else.
+pub fn awaited(statement: &Stmt) -> Option<&Expr> {
+    let expression = match statement {
+        Stmt::Expression { expression } => match expression.as_ref() {
+            Expr::Assign { value, .. } => value.as_ref(),
+            expression => expression,
+        },
+        Stmt::Let { initializer, .. } => initializer.as_ref(),
+        Stmt::Return {
+            value: Some(value), ..
+        } => value.as_ref(),
+        _ => return None,
+    };
+    matches!(expression, Expr::Await { .. }).then_some(expression)
+}
+
+fn contains(statements: &[Stmt], found: &dyn Fn(&Stmt) -> bool) -> bool {
+    let nested = |statement: &Stmt| contains(std::slice::from_ref(statement), found);
+    statements.iter().any(|statement| {
+        found(statement)
+            || match statement {
+                Stmt::Block { statements } => contains(statements, found),
+                Stmt::If {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => nested(then_branch) || else_branch.as_deref().is_some_and(nested),
+                Stmt::While { body, .. }
+                | Stmt::For { body, .. }
+                | Stmt::Loop { body, .. }
+                | Stmt::Each { body, .. } => nested(body),
+                Stmt::Exception {
+                    do_stmts,
+                    fail_stmts,
+                    finish_stmts,
+                    ..
+                } => {
+                    nested(do_stmts)
+                        || nested(fail_stmts)
+                        || finish_stmts.as_deref().is_some_and(nested)
+                }
+                _ => false,
+            }
+    })
+}
@@ -1,6 +1,7 @@
-use std::{fmt, process};
+use std::fmt;
 
 use super::token::Token;
+use crate::crux::output;
 use crate::crux::util;
 
 pub trait ReiError<T> {
@@ -32,10 +33,65 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by static analysis (resolver / `rei check`), reported without executing code.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub token: Token,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(token: &Token, message: &str) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn warning(token: &Token, message: &str) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Error => {
+                let fmt_err = format!(
+                    "Resolve Error | {} {}",
+                    self.message,
+                    self.token.get_location()
+                );
+                write!(f, "{}", util::red_colored(&fmt_err))
+            }
+            Severity::Warning => {
+                let fmt_warn = format!("Warning | {} {}", self.message, self.token.get_location());
+                write!(f, "{}", util::yellow_colored(&fmt_warn))
+            }
+        }
+    }
+}
+
 impl ReiError<(usize, usize)> for SyntaxError {
     fn throw_error(pos: &(usize, usize), msg: &str) -> ! {
         Self::error(pos, msg);
-        process::exit(65)
+        output::exit(65)
     }
 
     fn error(pos: &(usize, usize), msg: &str) {
@@ -44,6 +100,9 @@ impl ReiError<(usize, usize)> for SyntaxError {
     }
 
     fn report(line: usize, place: &str, msg: &str) {
-        eprintln!("[Unexpected character at -> {}:{}] {}", line, place, msg);
+        output::err(&format!(
+            "[Unexpected character at -> {}:{}] {}\n",
+            line, place, msg
+        ));
     }
 }
@@ -1,9 +1,13 @@
+use std::path::Path;
 use std::{env, process};
 
+use crate::backend::native;
 use crate::tools;
 
 pub mod error;
+pub mod output;
 pub mod runner;
+pub mod tester;
 pub mod token;
 pub mod util;
 
@@ -15,7 +19,41 @@ impl Rei {
         let args: Vec<String> = env::args().collect();
         let args_size = args.len();
 
-        if args_size == 2 {
+        if args_size >= 2 && args[1] == "test" {
+            let mut filter = None;
+            let mut paths = vec![];
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--filter" {
+                    filter = rest.next().cloned();
+                } else {
+                    paths.push(arg.clone());
+                }
+            }
+
+            // `rei test <n>` still runs ./src/tests/code/<n>.reix as a script
+            if let [name] = paths.as_slice() {
+                let script = format!("./src/tests/code/{}.reix", name);
+                if !Path::new(name).exists() && Path::new(&script).is_file() {
+                    let source = runner::Runner::read_file(&script)?;
+                    runner::Runner::run(&source, &script);
+                    return Ok(());
+                }
+            }
+
+            if paths.is_empty() {
+                paths.push("tests".to_string());
+            }
+            let files = tester::collect_files(&paths);
+            if files.is_empty() {
+                eprintln!("No test files found in {}", paths.join(", "));
+                process::exit(1);
+            }
+            if !tester::run(&files, filter.as_deref()) {
+                process::exit(1);
+            }
+            Ok(())
+        } else if args_size == 2 {
             if &args[1] == "gen" {
                 tools::ast_generator::define_ast(
                     "./src/frontend",
@@ -52,34 +90,86 @@ impl Rei {
                         "While : Expr condition, Stmt body",
                     ],
                 )?;
+            } else if &args[1] == "lsp" {
+                tools::lsp::serve_stdio()?;
             } else if &args[1] == "setup" {
                 let runner = runner::Runner;
                 runner.install_stdlib()?;
             } else {
-                let source = runner::Runner::read_file(&args[1]).unwrap_or_else(|_| {
-                    eprintln!("File not found");
+                Self::run_script(&args[1..]);
+            }
+
+            Ok(())
+        } else if args_size >= 3 && args[1] == "check" {
+            let mut clean = true;
+            for file in &args[2..] {
+                let source = runner::Runner::read_file(file).unwrap_or_else(|_| {
+                    eprintln!("File not found: {}", file);
                     process::exit(65);
                 });
-                runner::Runner::run(&source, &args[1]);
+                clean &= runner::Runner::check(&source, file);
             }
 
+            if !clean {
+                process::exit(1);
+            }
+            Ok(())
+        } else if args_size >= 3 && args[1] == "fmt" {
+            let check = args[2] == "--check";
+            let files = if check { &args[3..] } else { &args[2..] };
+
+            let mut ok = true;
+            for file in files {
+                let source = runner::Runner::read_file(file).unwrap_or_else(|_| {
+                    eprintln!("File not found: {}", file);
+                    process::exit(65);
+                });
+                let Some(formatted) = runner::Runner::format(&source, file) else {
+                    ok = false;
+                    continue;
+                };
+
+                if formatted == source {
+                    continue;
+                }
+                if check {
+                    eprintln!(
+                        "{}",
+                        util::yellow_colored(&format!("{} is not formatted", file))
+                    );
+                    ok = false;
+                } else {
+                    std::fs::write(file, formatted)?;
+                    println!("Formatted {}", file);
+                }
+            }
+
+            if !ok {
+                process::exit(1);
+            }
             Ok(())
         } else if args_size == 3 && args[1] == "new" {
             let runner = runner::Runner;
             runner.new_project(&args[2])?;
             Ok(())
-        } else if args_size == 3 && args[1] == "test" {
-            let test_file_location = format!("./src/tests/code/{}.reix", &args[2]);
-            let source = runner::Runner::read_file(&test_file_location).unwrap_or_else(|_| {
-                eprintln!("File not found: {}", test_file_location);
-                process::exit(65);
-            });
-            runner::Runner::run(&source, &test_file_location);
-
+        } else if args_size >= 3 {
+            Self::run_script(&args[1..]);
             Ok(())
         } else {
             runner::Runner::run_prompt();
             Ok(())
         }
     }
+
+    /// `rei <file> [args...]`, the script finds them in `Process.argv()`.
+    /// Ctrl-C interrupts it with an error it can catch.
+    fn run_script(argv: &[String]) {
+        let source = runner::Runner::read_file(&argv[0]).unwrap_or_else(|_| {
+            eprintln!("File not found");
+            process::exit(65);
+        });
+        native::process::process::set_argv(argv.to_vec());
+        native::process::signal::enable_interrupts();
+        runner::Runner::run(&source, &argv[0]);
+    }
 }
@@ -0,0 +1,96 @@
+use std::cell::{Cell, RefCell};
+use std::io::{self, Write};
+use std::panic;
+use std::process;
+
+/// Everything a script wrote, plus the status it exited with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: i32,
+}
+
+/// Unwinds out of a captured or contained run instead of ending the
+/// process.
+struct Exit(i32);
+
+thread_local! {
+    static CAPTURE: RefCell<Option<Output>> = const { RefCell::new(None) };
+    static CONTAINED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Writes script output to stdout, or to the capture buffer when running
+/// under `capture`.
+pub fn out(text: &str) {
+    let captured = CAPTURE.with_borrow_mut(|capture| match capture {
+        Some(output) => {
+            output.stdout.push_str(text);
+            true
+        }
+        None => false,
+    });
+    if !captured {
+        print!("{}", text);
+    }
+}
+
+/// Like `out`, for errors and diagnostics.
+pub fn err(text: &str) {
+    let captured = CAPTURE.with_borrow_mut(|capture| match capture {
+        Some(output) => {
+            output.stderr.push_str(text);
+            true
+        }
+        None => false,
+    });
+    if !captured {
+        eprint!("{}", text);
+    }
+}
+
+/// Ends the script with a status code. Outside of `capture` and `contain`
+/// this exits the process, so anything printed without a newline is
+/// flushed first.
+pub fn exit(code: i32) -> ! {
+    let captured = CAPTURE.with_borrow_mut(|capture| match capture {
+        Some(output) => {
+            output.status = code;
+            true
+        }
+        None => false,
+    });
+    if captured || CONTAINED.get() {
+        panic::resume_unwind(Box::new(Exit(code)));
+    }
+
+    let _ = io::stdout().flush();
+    process::exit(code)
+}
+
+/// Runs `f` so that a call to `exit` only stops `f`, whose status becomes
+/// the error. Threads a script spawns run this way.
+pub fn contain<R, F: FnOnce() -> R>(f: F) -> Result<R, i32> {
+    CONTAINED.set(true);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    CONTAINED.set(false);
+
+    result.map_err(|payload| match payload.downcast::<Exit>() {
+        Ok(exit) => exit.0,
+        Err(payload) => panic::resume_unwind(payload),
+    })
+}
+
+/// Runs `f` with all script output captured on this thread. A call to `exit`
+/// stops `f` and becomes the returned status; other panics propagate.
+#[cfg(test)]
+pub fn capture<F: FnOnce()>(f: F) -> Output {
+    CAPTURE.set(Some(Output::default()));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    let output = CAPTURE.take().unwrap_or_default();
+
+    match result {
+        Err(payload) if !payload.is::<Exit>() => panic::resume_unwind(payload),
+        _ => output,
+    }
+}
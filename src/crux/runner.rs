@@ -8,6 +8,10 @@ use std::{
     path::PathBuf,
 };
 
+use crate::crux::error::Diagnostic;
+use crate::crux::output;
+use crate::crux::util;
+use crate::frontend::formatter::Formatter;
 use crate::frontend::lexer;
 use crate::frontend::parser::Parser;
 
@@ -43,7 +47,7 @@ impl Runner {
         // }
 
         if parser.is_error {
-            return;
+            output::exit(65);
         }
 
         let mut interpreter = match Interpreter::new() {
@@ -56,10 +60,104 @@ impl Runner {
 
         let mut resolver = Resolver::new(&mut interpreter);
         resolver.resolve(&stmts);
+        if resolver.has_errors() {
+            for diagnostic in resolver.diagnostics.iter().filter(|d| d.is_error()) {
+                output::err(&format!("{}\n", diagnostic));
+            }
+            output::exit(65);
+        }
 
         interpreter.interpret(stmts);
     }
 
+    /// Runs the lexer, parser and resolver over a file without executing it.
+    /// Returns false when an error (not just a warning) was found.
+    pub fn check(source: &str, location: &str) -> bool {
+        let (parse_failed, diagnostics) = Self::analyze(source, location);
+        if parse_failed {
+            return false;
+        }
+
+        for diagnostic in &diagnostics {
+            output::err(&format!("{}\n", diagnostic));
+        }
+
+        let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+        let warnings = diagnostics.len() - errors;
+        let summary = format!("{}: {} error(s), {} warning(s)", location, errors, warnings);
+        if errors > 0 {
+            output::err(&format!("{}\n", util::red_colored(&summary)));
+        } else if warnings > 0 {
+            output::err(&format!("{}\n", util::yellow_colored(&summary)));
+        } else {
+            output::out(&format!("{}\n", util::green_colored(&summary)));
+        }
+
+        errors == 0
+    }
+
+    /// Collects resolver diagnostics, sorted by position. The first value is
+    /// true when parsing already failed (the parser reports those itself).
+    pub fn analyze(source: &str, location: &str) -> (bool, Vec<Diagnostic>) {
+        let current_file = Some(PathBuf::from(location));
+        let lexer = lexer::Lexer::new(source, String::from(location));
+        let tokens = lexer.scan_tokens();
+
+        let path_tracker = Rc::new(RefCell::new(String::from(location)));
+        let mut global_expr_id_counter = 0;
+
+        let mut parser = Parser::new(
+            tokens,
+            &current_file,
+            &mut global_expr_id_counter,
+            path_tracker,
+        );
+        let stmts = parser.parse();
+        if parser.is_error {
+            return (true, vec![]);
+        }
+
+        let mut interpreter = match Interpreter::new() {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("{}", e);
+                panic!();
+            }
+        };
+
+        let mut resolver = Resolver::with_lints(&mut interpreter);
+        resolver.resolve(&stmts);
+
+        let mut diagnostics = resolver.diagnostics;
+        diagnostics.sort_by_key(|d| (d.token.line, d.token.place));
+        (false, diagnostics)
+    }
+
+    /// Formats a file into canonical REI source. Returns None when the file
+    /// doesn't parse, so a broken file is never rewritten.
+    pub fn format(source: &str, location: &str) -> Option<String> {
+        let current_file = Some(PathBuf::from(location));
+        let lexer = lexer::Lexer::new(source, String::from(location));
+        let (tokens, comments) = lexer.scan_with_trivia();
+
+        let path_tracker = Rc::new(RefCell::new(String::from(location)));
+        let mut global_expr_id_counter = 0;
+
+        let mut parser = Parser::new(
+            tokens.clone(),
+            &current_file,
+            &mut global_expr_id_counter,
+            path_tracker,
+        );
+        parser.load_modules = false;
+        let stmts = parser.parse();
+        if parser.is_error {
+            return None;
+        }
+
+        Some(Formatter::new(tokens, comments).format(&stmts))
+    }
+
     pub fn read_file(path: &str) -> Result<String, Box<dyn std::error::Error>> {
         let content = fs::read_to_string(path)?;
         Ok(content)
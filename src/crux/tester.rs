@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use walkdir::WalkDir;
+
+use crate::backend::exec_signal::ExecSignal;
+use crate::backend::interpreter::Interpreter;
+use crate::backend::resolver::Resolver;
+use crate::backend::stmt::Stmt;
+use crate::crux::runner::Runner;
+use crate::crux::token::Token;
+use crate::crux::util;
+use crate::frontend::lexer;
+use crate::frontend::parser::Parser;
+
+/// Top-level functions whose name starts with this are tests.
+pub const TEST_PREFIX: &str = "test_";
+
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub name: String,
+    pub failure: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Finds the test functions declared at the top level of a file, keeping
+/// only those whose name contains the filter.
+pub fn discover<'a>(stmts: &'a [Stmt], filter: Option<&str>) -> Vec<&'a Token> {
+    stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function { name, .. } if name.lexeme.starts_with(TEST_PREFIX) => Some(name),
+            _ => None,
+        })
+        .filter(|name| filter.is_none_or(|f| name.lexeme.contains(f)))
+        .collect()
+}
+
+/// Runs every test in a file. Each test gets a fresh interpreter that first
+/// executes the file's top-level statements, so no state leaks between
+/// tests. Returns None when the file doesn't parse or resolve.
+pub fn run_file(source: &str, location: &str, filter: Option<&str>) -> Option<Vec<TestOutcome>> {
+    let current_file = Some(PathBuf::from(location));
+    let tokens = lexer::Lexer::new(source, String::from(location)).scan_tokens();
+
+    let path_tracker = Rc::new(RefCell::new(String::from(location)));
+    let mut global_expr_id_counter = 0;
+
+    let mut parser = Parser::new(
+        tokens,
+        &current_file,
+        &mut global_expr_id_counter,
+        path_tracker,
+    );
+    let stmts = parser.parse();
+    if parser.is_error {
+        return None;
+    }
+
+    let mut outcomes = vec![];
+    for name in discover(&stmts, filter) {
+        let mut interpreter = match Interpreter::new() {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("{}", e);
+                panic!();
+            }
+        };
+
+        let mut resolver = Resolver::new(&mut interpreter);
+        resolver.resolve(&stmts);
+        if resolver.has_errors() {
+            for diagnostic in resolver.diagnostics.iter().filter(|d| d.is_error()) {
+                eprintln!("{}", diagnostic);
+            }
+            return None;
+        }
+
+        let start = Instant::now();
+        let result = stmts
+            .iter()
+            .try_for_each(|stmt| interpreter.execute(stmt))
+            .and_then(|_| interpreter.call_global(name, vec![]));
+
+        outcomes.push(TestOutcome {
+            name: name.lexeme.clone(),
+            failure: result.err().map(|signal| describe_failure(&signal)),
+            elapsed: start.elapsed(),
+        });
+    }
+
+    Some(outcomes)
+}
+
+fn describe_failure(signal: &ExecSignal) -> String {
+    match signal {
+        ExecSignal::RuntimeError(err) => format!(
+            "{}\n  {}",
+            err.err_type,
+            err.stack_trace.borrow().format_stack_trace()
+        ),
+        ExecSignal::ControlFlow(flow) => format!("Unexpected control flow: {:?}", flow),
+    }
+}
+
+/// Collects the files to test. Explicit files are always used; directories
+/// are searched for `test_*.reix` and `*_test.reix` files.
+pub fn collect_files(paths: &[String]) -> Vec<String> {
+    let mut files = vec![];
+    for path in paths {
+        if Path::new(path).is_file() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut found: Vec<String> = WalkDir::new(path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                let path = entry.path();
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                path.extension().is_some_and(|ext| ext == "reix")
+                    && (stem.starts_with(TEST_PREFIX) || stem.ends_with("_test"))
+            })
+            .map(|entry| entry.path().to_string_lossy().to_string())
+            .collect();
+        found.sort();
+        files.extend(found);
+    }
+    files
+}
+
+/// Runs the tests in the given files and prints a report. Returns false when
+/// a test failed or a file couldn't be loaded.
+pub fn run(files: &[String], filter: Option<&str>) -> bool {
+    let start = Instant::now();
+    let mut failures = vec![];
+    let (mut passed, mut broken) = (0, 0);
+
+    for file in files {
+        let Ok(source) = Runner::read_file(file) else {
+            eprintln!("File not found: {}", file);
+            broken += 1;
+            continue;
+        };
+        let Some(outcomes) = run_file(&source, file, filter) else {
+            eprintln!("{}", util::red_colored(&format!("Couldn't load {}", file)));
+            broken += 1;
+            continue;
+        };
+
+        println!("\nrunning {} test(s) from {}", outcomes.len(), file);
+        for outcome in outcomes {
+            let status = if outcome.passed() {
+                passed += 1;
+                util::green_colored("ok")
+            } else {
+                util::red_colored("FAILED")
+            };
+            println!(
+                "test {} ... {} ({:.2?})",
+                outcome.name, status, outcome.elapsed
+            );
+
+            if let Some(failure) = outcome.failure {
+                failures.push((file, outcome.name, failure));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (file, name, failure) in &failures {
+            println!("\n---- {} ({}) ----\n{}", name, file, failure);
+        }
+    }
+
+    let ok = failures.is_empty() && broken == 0;
+    let result = if ok {
+        util::green_colored("ok")
+    } else {
+        util::red_colored("FAILED")
+    };
+    println!(
+        "\ntest result: {}. {} passed; {} failed; finished in {:.2?}",
+        result,
+        passed,
+        failures.len(),
+        start.elapsed()
+    );
+
+    ok
+}
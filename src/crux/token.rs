@@ -2,10 +2,20 @@ use once_cell::sync::Lazy;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
+use crate::backend::event_loop::ReiTask;
 use crate::backend::exec_signal::runtime_error;
+use crate::backend::native::net::socket::Socket;
+use crate::backend::native::process::child::Child;
+use crate::backend::native::thread::channel::Channel;
+use crate::backend::native::thread::spawned::Spawned;
 use crate::backend::rei_callable::ReiCallable;
+use crate::backend::rei_enum::ReiVariant;
+use crate::backend::rei_generator::ReiGenerator;
 use crate::backend::rei_instance::ReiInstance;
 
 use crate::crux::util;
@@ -60,10 +70,18 @@ pub enum TokenType {
     Print,
     PrintLn,
     Return,
+    Yield,
+    Async,
+    Await,
     Base,
     This,
     True,
     Let,
+    Const,
+    Private,
+    Trait,
+    Enum,
+    Impl,
     While,
     Loop,
     Break,
@@ -80,6 +98,9 @@ pub enum TokenType {
     Use,
     Expose,
     As,
+
+    // Trivia, only kept when the lexer is asked for it
+    Comment,
 }
 
 pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
@@ -99,11 +120,19 @@ pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     map.insert("print", TokenType::Print);
     map.insert("println", TokenType::PrintLn);
     map.insert("return", TokenType::Return);
+    map.insert("yield", TokenType::Yield);
+    map.insert("async", TokenType::Async);
+    map.insert("await", TokenType::Await);
     map.insert("base", TokenType::Base);
     map.insert("this", TokenType::This);
     map.insert("static", TokenType::Static);
     map.insert("true", TokenType::True);
     map.insert("let", TokenType::Let);
+    map.insert("const", TokenType::Const);
+    map.insert("private", TokenType::Private);
+    map.insert("trait", TokenType::Trait);
+    map.insert("impl", TokenType::Impl);
+    map.insert("enum", TokenType::Enum);
     map.insert("while", TokenType::While);
     map.insert("loop", TokenType::Loop);
     map.insert("break", TokenType::Break);
@@ -119,9 +148,45 @@ pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     map
 });
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Router {
-    pub routes: HashMap<(String, String), Rc<dyn ReiCallable>>,
+    /// Tried in the order they were added, the first match wins
+    pub routes: Vec<Route>,
+    /// Called with the request before routing, returning anything but null
+    /// answers it instead of the route
+    pub before: Vec<Rc<dyn ReiCallable>>,
+    /// Called with the request and the response, returning anything but
+    /// null replaces the response
+    pub after: Vec<Rc<dyn ReiCallable>>,
+    /// Answers requests no route or mount matches
+    pub not_found: Option<Rc<dyn ReiCallable>>,
+    /// URL prefixes served from a directory
+    pub mounts: Vec<(String, PathBuf)>,
+    /// Origin CORS allows, no CORS headers are sent without one
+    pub cors: Option<String>,
+    /// Set to stop serving once the requests being answered are done
+    pub stopping: Arc<AtomicBool>,
+    /// Set while a serve loop is accepting connections for it
+    pub serving: Arc<AtomicBool>,
+    /// Paths upgraded to WebSockets, tried before the routes
+    pub websockets: Vec<WsRoute>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub method: String,
+    /// Path where a `:name` segment matches any value, e.g. `/users/:id`
+    pub pattern: String,
+    pub handler: Rc<dyn ReiCallable>,
+}
+
+/// Handlers of a WebSocket path, each one optional
+#[derive(Clone, Debug)]
+pub struct WsRoute {
+    pub pattern: String,
+    pub on_open: Option<Rc<dyn ReiCallable>>,
+    pub on_message: Option<Rc<dyn ReiCallable>>,
+    pub on_close: Option<Rc<dyn ReiCallable>>,
 }
 
 #[derive(Clone, Debug)]
@@ -134,10 +199,22 @@ pub enum Object {
     Null,
     Callable(Rc<dyn ReiCallable>),
     Instance(Rc<RefCell<ReiInstance>>),
-    MBlock(*mut u8, usize),
+    Variant(Rc<ReiVariant>),
+    Generator(Rc<ReiGenerator>),
+    Task(Rc<ReiTask>),
+    /// Byte buffer, shared like a Vec
+    Bytes(Rc<RefCell<Vec<u8>>>),
     Vec(Rc<RefCell<Vec<Object>>>),
     Exception(Box<runtime_error::RuntimeError<Token>>),
     Router(Rc<RefCell<Router>>),
+    /// Closed once the last reference is dropped
+    Socket(Rc<Socket>),
+    /// Spawned process, which outlives its last reference
+    Child(Rc<Child>),
+    /// Thread running a script function, which outlives its last reference
+    Thread(Rc<Spawned>),
+    /// This thread's end of a channel, gone with its last reference
+    Channel(Rc<Channel>),
 }
 
 impl Object {
@@ -173,7 +250,10 @@ impl fmt::Display for Object {
             Object::Dummy => write!(f, "Dummy"),
             Object::Callable(c) => write!(f, "{}", c.to_string()),
             Object::Instance(i) => write!(f, "{}", i.borrow().to_string()),
-            Object::MBlock(p, s) => write!(f, "{:p} {}", p, s),
+            Object::Variant(v) => write!(f, "{}", v),
+            Object::Generator(g) => write!(f, "<generator {}>", g.name),
+            Object::Task(t) => write!(f, "<task {}>", t.name),
+            Object::Bytes(b) => write!(f, "<bytes | size: {}>", b.borrow().len()),
             Object::Null => write!(f, "Null"),
             Object::Vec(v) => {
                 let vec_borrow = v.borrow();
@@ -182,6 +262,10 @@ impl fmt::Display for Object {
             }
             Object::Exception(e) => write!(f, "{}", e),
             Object::Router(r) => write!(f, "{:?}", r),
+            Object::Socket(s) => write!(f, "{}", s),
+            Object::Child(c) => write!(f, "{}", c),
+            Object::Thread(t) => write!(f, "{}", t),
+            Object::Channel(c) => write!(f, "{}", c),
         }
     }
 }
@@ -249,11 +333,19 @@ impl fmt::Display for TokenType {
             TokenType::Print => "IDENTIFIER",
             TokenType::PrintLn => "IDENTIFIER",
             TokenType::Return => "IDENTIFIER",
+            TokenType::Yield => "IDENTIFIER",
+            TokenType::Async => "IDENTIFIER",
+            TokenType::Await => "IDENTIFIER",
             TokenType::Base => "IDENTIFIER",
             TokenType::This => "IDENTIFIER",
             TokenType::Static => "IDENTIFIER",
             TokenType::True => "IDENTIFIER",
             TokenType::Let => "IDENTIFIER",
+            TokenType::Const => "IDENTIFIER",
+            TokenType::Private => "IDENTIFIER",
+            TokenType::Trait => "IDENTIFIER",
+            TokenType::Impl => "IDENTIFIER",
+            TokenType::Enum => "IDENTIFIER",
             TokenType::Loop => "IDENTIFIER",
             TokenType::While => "IDENTIFIER",
             TokenType::Break => "IDENTIFIER",
@@ -265,6 +357,7 @@ impl fmt::Display for TokenType {
             TokenType::As => "IDENTIFIER",
             TokenType::At => "IDENTIFIER",
 
+            TokenType::Comment => "Comment",
             TokenType::Eof => "End of File",
         };
 
@@ -5,15 +5,17 @@ pub trait Visitor<T> {
     fn visit_assign_expr(&mut self, id: ExprId, name: &Token, value: &Expr) -> T;
     fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> T;
     fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &Vec<Expr>) -> T;
-    fn visit_get_expr(&mut self, object: &Box<Expr>, name: &Token) -> T;
+    fn visit_get_expr(&mut self, id: ExprId, object: &Expr, name: &Token) -> T;
     fn visit_grouping_expr(&mut self, expression: &Expr) -> T;
     fn visit_literal_expr(&mut self, value: &Object) -> T;
     fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> T;
-    fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> T;
+    fn visit_set_expr(&mut self, id: ExprId, object: &Expr, name: &Token, value: &Expr) -> T;
     fn visit_this_expr(&mut self, id: ExprId, keyword: &Token) -> T;
+    fn visit_base_expr(&mut self, id: ExprId, keyword: &Token, method: &Token) -> T;
     fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> T;
     fn visit_variable_expr(&mut self, id: ExprId, name: &Token) -> T;
     fn visit_range_expr(&mut self, start: &Expr, end: &Expr) -> T;
+    fn visit_await_expr(&mut self, keyword: &Token, value: &Expr) -> T;
     fn visit_meta_expr(
         &mut self,
         id: ExprId,
@@ -90,6 +92,12 @@ pub enum Expr {
         keyword: Token,
     },
 
+    Base {
+        id: ExprId,
+        keyword: Token,
+        method: Token,
+    },
+
     Unary {
         id: ExprId,
         operator: Token,
@@ -106,6 +114,14 @@ pub enum Expr {
         start: Box<Expr>,
         end: Box<Expr>,
     },
+
+    /// `await value`, only allowed as a whole statement, initializer,
+    /// assignment value or return value
+    Await {
+        id: ExprId,
+        keyword: Token,
+        value: Box<Expr>,
+    },
 }
 
 impl Expr {
@@ -121,9 +137,11 @@ impl Expr {
             | Expr::Set { id, .. }
             | Expr::Unary { id, .. }
             | Expr::This { id, .. }
+            | Expr::Base { id, .. }
             | Expr::Variable { id, .. }
             | Expr::Meta { id, .. }
-            | Expr::Range { id, .. } => id.clone(),
+            | Expr::Range { id, .. }
+            | Expr::Await { id, .. } => id.clone(),
         }
     }
 
@@ -142,11 +160,7 @@ impl Expr {
                 paren,
                 arguments,
             } => visitor.visit_call_expr(callee, paren, arguments),
-            Expr::Get {
-                id: _,
-                object,
-                name,
-            } => visitor.visit_get_expr(object, name),
+            Expr::Get { id, object, name } => visitor.visit_get_expr(*id, object, name),
             Expr::Grouping { id: _, expression } => visitor.visit_grouping_expr(expression),
             Expr::Literal { id: _, value } => visitor.visit_literal_expr(value),
             Expr::Logical {
@@ -156,12 +170,17 @@ impl Expr {
                 right,
             } => visitor.visit_logical_expr(left, operator, right),
             Expr::Set {
-                id: _,
+                id,
                 object,
                 name,
                 value,
-            } => visitor.visit_set_expr(object, name, value),
+            } => visitor.visit_set_expr(*id, object, name, value),
             Expr::This { id, keyword } => visitor.visit_this_expr(id.clone(), keyword),
+            Expr::Base {
+                id,
+                keyword,
+                method,
+            } => visitor.visit_base_expr(*id, keyword, method),
             Expr::Meta {
                 id,
                 keyword,
@@ -175,6 +194,11 @@ impl Expr {
             } => visitor.visit_unary_expr(operator, right),
             Expr::Variable { id, name } => visitor.visit_variable_expr(id.clone(), name),
             Expr::Range { id: _, start, end } => visitor.visit_range_expr(start, end),
+            Expr::Await {
+                id: _,
+                keyword,
+                value,
+            } => visitor.visit_await_expr(keyword, value),
         }
     }
 }
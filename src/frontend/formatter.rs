@@ -0,0 +1,856 @@
+use crate::backend::resolver;
+use crate::backend::stmt;
+use crate::backend::stmt::Stmt;
+use crate::crux::token::{Object, Token, TokenType};
+use crate::frontend::expr;
+use crate::frontend::expr::{Expr, ExprId};
+
+const INDENT: &str = "    ";
+
+type Position = (usize, usize);
+
+/// Pretty-prints a parsed file back into canonical REI source.
+///
+/// The AST doesn't carry comments, braces or the `.`/`->` spelling of an
+/// accessor, so the formatter keeps the file's tokens and comments around and
+/// uses source positions to put those back where they belong.
+pub struct Formatter {
+    tokens: Vec<Token>,
+    comments: Vec<Token>,
+    next_comment: usize,
+    /// Opening and closing brace of every block, in the order they appear
+    block_starts: Vec<Token>,
+    block_ends: Vec<Position>,
+    blocks_opened: usize,
+    indent: usize,
+    inline: bool,
+    out: String,
+}
+
+impl Formatter {
+    pub fn new(tokens: Vec<Token>, comments: Vec<Token>) -> Self {
+        let mut block_starts = vec![];
+        let mut block_ends = vec![];
+        let mut open = vec![];
+        for token in &tokens {
+            match token.token_type {
+                TokenType::LeftBrace => {
+                    open.push(block_ends.len());
+                    block_starts.push(token.clone());
+                    block_ends.push((usize::MAX, usize::MAX));
+                }
+                TokenType::RightBrace => {
+                    if let Some(index) = open.pop() {
+                        block_ends[index] = position(token);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Formatter {
+            tokens,
+            comments,
+            next_comment: 0,
+            block_starts,
+            block_ends,
+            blocks_opened: 0,
+            indent: 0,
+            inline: false,
+            out: String::new(),
+        }
+    }
+
+    pub fn format(mut self, statements: &[Stmt]) -> String {
+        for statement in statements {
+            statement.accept(&mut self);
+        }
+        self.flush_comments((usize::MAX, usize::MAX));
+
+        let mut out = self.out.trim_end().to_string();
+        out.push('\n');
+        out
+    }
+
+    fn begin_stmt(&mut self, anchor: Option<&Token>, force_blank: bool) {
+        if std::mem::take(&mut self.inline) {
+            return;
+        }
+
+        let mut blank = force_blank;
+        if let Some(start) = anchor.map(|a| self.statement_start(a)) {
+            blank = self.flush_comments_with(position(&start), force_blank);
+            blank |= self.gap_before(start.line, position(&start));
+        }
+
+        if blank {
+            self.blank_line();
+        }
+        self.write_indent();
+    }
+
+    /// Emits every pending comment that appears before `until`. Returns true
+    /// when a blank line should still separate the last comment from what follows.
+    fn flush_comments_with(&mut self, until: Position, force_blank: bool) -> bool {
+        let mut first = true;
+        while let Some(comment) = self.comments.get(self.next_comment).cloned() {
+            if position(&comment) >= until {
+                break;
+            }
+            self.next_comment += 1;
+
+            if self.is_trailing(&comment) && !self.out.is_empty() && !self.out.ends_with("\n\n") {
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(&comment.lexeme);
+                self.out.push('\n');
+                continue;
+            }
+
+            let start_line = comment.line - comment.lexeme.matches('\n').count();
+            if (first && force_blank) || self.gap_before(start_line, position(&comment)) {
+                self.blank_line();
+            }
+            first = false;
+
+            self.write_indent();
+            self.out.push_str(&comment.lexeme);
+            self.out.push('\n');
+        }
+
+        // Keep the forced blank line between the comments and the statement only
+        // when no comment took its place
+        first && force_blank
+    }
+
+    fn flush_comments(&mut self, until: Position) {
+        self.flush_comments_with(until, false);
+    }
+
+    fn blank_line(&mut self) {
+        let at_block_start = self.out.is_empty() || self.out.ends_with("{\n");
+        if !at_block_start && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Claims the next source block and returns the position of its closing brace.
+    fn open_block(&mut self) -> Position {
+        let end = self
+            .block_ends
+            .get(self.blocks_opened)
+            .copied()
+            .unwrap_or((usize::MAX, usize::MAX));
+        self.blocks_opened += 1;
+        end
+    }
+
+    fn has_comments_before(&self, end: Position) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|c| position(c) < end)
+    }
+
+    /// Writes the statements of a brace-delimited body. The opening brace is
+    /// expected to be written already; this writes the closing one.
+    fn block_body(&mut self, statements: &[Stmt]) {
+        let end = self.open_block();
+        if statements.is_empty() && !self.has_comments_before(end) {
+            self.out.push('}');
+            return;
+        }
+
+        self.out.push('\n');
+        self.indent += 1;
+        for statement in statements {
+            statement.accept(self);
+        }
+        self.flush_comments(end);
+        self.indent -= 1;
+
+        self.write_indent();
+        self.out.push('}');
+    }
+
+    /// Writes a statement that follows a header on the same line, such as the
+    /// body of an `if`. Returns true if it ended with a closing brace.
+    fn clause(&mut self, statement: &Stmt) -> bool {
+        if let Stmt::Block { statements } = statement {
+            self.out.push('{');
+            self.block_body(statements);
+            return true;
+        }
+
+        let previous = std::mem::take(&mut self.out);
+        self.inline = true;
+        statement.accept(self);
+        self.inline = false;
+        let rendered = std::mem::replace(&mut self.out, previous);
+        self.out
+            .push_str(rendered.trim_start().trim_end_matches('\n'));
+        false
+    }
+
+    fn function(&mut self, prefix: &str, name: &Token, params: &[Token], body: &[Stmt]) {
+        let params: Vec<&str> = params.iter().map(|p| p.lexeme.as_str()).collect();
+        self.out.push_str(&format!(
+            "{}{}({}) {{",
+            prefix,
+            name.lexeme,
+            params.join(", ")
+        ));
+        self.block_body(body);
+        self.out.push('\n');
+    }
+
+    /// Renders the `let`/expression clause used by `for` headers, including the `;`.
+    fn header_stmt(&mut self, statement: &Option<Box<Stmt>>) -> String {
+        match statement.as_deref() {
+            Some(Stmt::Let { name, initializer }) => self.let_text(name, initializer),
+            Some(Stmt::Expression { expression }) => format!("{};", self.expr(expression)),
+            _ => ";".to_string(),
+        }
+    }
+
+    fn let_text(&mut self, name: &Token, initializer: &Expr) -> String {
+        match initializer {
+            Expr::Literal {
+                value: Object::Null,
+                ..
+            } => format!("let {};", name.lexeme),
+            _ => format!("let {} = {};", name.lexeme, self.expr(initializer)),
+        }
+    }
+
+    fn expr(&mut self, expression: &Expr) -> String {
+        expression.accept(self)
+    }
+
+    fn exprs(&mut self, expressions: &[Expr]) -> String {
+        let parts: Vec<String> = expressions.iter().map(|e| self.expr(e)).collect();
+        parts.join(", ")
+    }
+
+    /// The token that introduced a property name: `.`, `->` or `<-`.
+    fn accessor_before(&self, name: &Token) -> TokenType {
+        let index = self
+            .tokens
+            .partition_point(|t| position(t) < position(name));
+        match index.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(token) => token.token_type,
+            None => TokenType::Dot,
+        }
+    }
+
+    /// Walks back from the anchor token of a statement to its first token.
+    fn statement_start(&self, anchor: &Token) -> Token {
+        let mut index = self
+            .tokens
+            .partition_point(|t| position(t) < position(anchor));
+        while index > 0 {
+            match self.tokens[index - 1].token_type {
+                TokenType::Semicolon | TokenType::LeftBrace | TokenType::RightBrace => break,
+                _ => index -= 1,
+            }
+        }
+        self.tokens.get(index).unwrap_or(anchor).clone()
+    }
+
+    /// True when the source had an empty line right before `pos`.
+    fn gap_before(&self, line: usize, pos: Position) -> bool {
+        let token_index = self.tokens.partition_point(|t| position(t) < pos);
+        let comment_index = self.comments.partition_point(|c| position(c) < pos);
+
+        let previous_token = token_index.checked_sub(1).map(|i| self.tokens[i].line);
+        let previous_comment = comment_index.checked_sub(1).map(|i| self.comments[i].line);
+
+        match previous_token.max(previous_comment) {
+            Some(previous) => line > previous + 1,
+            None => false,
+        }
+    }
+
+    /// A comment is trailing when it shares its line with the token before it.
+    fn is_trailing(&self, comment: &Token) -> bool {
+        let start_line = comment.line - comment.lexeme.matches('\n').count();
+        let index = self
+            .tokens
+            .partition_point(|t| position(t) < position(comment));
+        index
+            .checked_sub(1)
+            .is_some_and(|i| self.tokens[i].line == start_line)
+    }
+}
+
+impl stmt::Visitor<()> for Formatter {
+    fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) {
+        let anchor = self.block_starts.get(self.blocks_opened).cloned();
+        self.begin_stmt(anchor.as_ref(), false);
+        self.out.push('{');
+        self.block_body(statements);
+        self.out.push('\n');
+    }
+
+    fn visit_expression_stmt(&mut self, expression: &Expr) {
+        let anchor = resolver::expr_token(expression).cloned();
+        self.begin_stmt(anchor.as_ref(), false);
+        let text = self.expr(expression);
+        self.out.push_str(&format!("{};\n", text));
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass_refs: &Vec<Expr>,
+        methods: &Vec<Stmt>,
+        static_methods: &Vec<Stmt>,
+        fields: &[Stmt],
+        accessors: &[Stmt],
+        private_members: &[Token],
+        traits: &[Expr],
+        expose: &bool,
+    ) {
+        self.begin_stmt(Some(name), false);
+        if *expose {
+            self.out.push_str("expose ");
+        }
+        self.out.push_str(&format!("class {}", name.lexeme));
+        if !superclass_refs.is_empty() {
+            let parents = self.exprs(superclass_refs);
+            self.out.push_str(&format!(" < {}", parents));
+        }
+        if !traits.is_empty() {
+            let traits = self.exprs(traits);
+            self.out.push_str(&format!(" impl {}", traits));
+        }
+        self.out.push_str(" {");
+
+        // Each kind of member is stored apart; restore source order
+        let mut members: Vec<(bool, &Stmt)> = methods
+            .iter()
+            .map(|m| (false, m))
+            .chain(static_methods.iter().map(|m| (true, m)))
+            .chain(fields.iter().map(|f| (false, f)))
+            .chain(accessors.iter().map(|a| (false, a)))
+            .collect();
+        members.sort_by_key(|(_, m)| match m {
+            Stmt::Function { name, .. }
+            | Stmt::Field { name, .. }
+            | Stmt::Accessor { name, .. } => position(name),
+            _ => (0, 0),
+        });
+
+        let end = self.open_block();
+        if members.is_empty() && !self.has_comments_before(end) {
+            self.out.push_str("}\n");
+            return;
+        }
+
+        self.out.push('\n');
+        self.indent += 1;
+        let mut previous_field = false;
+        for (i, (is_static, member)) in members.into_iter().enumerate() {
+            let is_field = matches!(member, Stmt::Field { .. });
+            // Runs of fields keep their own spacing, everything else is apart
+            let force_blank = i > 0 && !(is_field && previous_field);
+            previous_field = is_field;
+
+            // `#name` members are private without saying so
+            let is_private = |name: &Token| {
+                !name.lexeme.starts_with('#')
+                    && private_members
+                        .iter()
+                        .any(|m| position(m) == position(name))
+            };
+            match member {
+                Stmt::Function {
+                    name,
+                    params,
+                    body,
+                    is_async,
+                } => {
+                    self.begin_stmt(Some(name), force_blank);
+                    let prefix = match (is_private(name), is_static) {
+                        (true, true) => "private static ",
+                        (true, false) => "private ",
+                        (false, true) => "static ",
+                        (false, false) => "",
+                    };
+                    let prefix = if *is_async {
+                        format!("{}async ", prefix)
+                    } else {
+                        prefix.to_string()
+                    };
+                    self.function(&prefix, name, params, body);
+                }
+                Stmt::Accessor {
+                    kind,
+                    name,
+                    params,
+                    body,
+                } => {
+                    self.begin_stmt(Some(name), force_blank);
+                    let private = if is_private(name) { "private " } else { "" };
+                    let prefix = format!("{}{} ", private, kind.lexeme);
+                    self.function(&prefix, name, params, body);
+                }
+                Stmt::Field { name, .. } => {
+                    self.begin_stmt(Some(name), force_blank);
+                    if is_private(name) {
+                        self.out.push_str("private ");
+                    }
+                    self.inline = true;
+                    member.accept(self);
+                    self.inline = false;
+                }
+                _ => {}
+            }
+        }
+        self.flush_comments(end);
+        self.indent -= 1;
+        self.write_indent();
+        self.out.push_str("}\n");
+    }
+
+    fn visit_trait_stmt(
+        &mut self,
+        name: &Token,
+        required: &[Stmt],
+        methods: &[Stmt],
+        expose: bool,
+    ) {
+        self.begin_stmt(Some(name), false);
+        if expose {
+            self.out.push_str("expose ");
+        }
+        self.out.push_str(&format!("trait {} {{", name.lexeme));
+
+        let mut members: Vec<(bool, &Stmt)> = required
+            .iter()
+            .map(|m| (true, m))
+            .chain(methods.iter().map(|m| (false, m)))
+            .collect();
+        members.sort_by_key(|(_, m)| match m {
+            Stmt::Function { name, .. } => position(name),
+            _ => (0, 0),
+        });
+
+        let end = self.open_block();
+        if members.is_empty() && !self.has_comments_before(end) {
+            self.out.push_str("}\n");
+            return;
+        }
+
+        self.out.push('\n');
+        self.indent += 1;
+        let mut previous_signature = false;
+        for (i, (is_signature, member)) in members.into_iter().enumerate() {
+            // Runs of signatures keep their own spacing, like fields in a class
+            let force_blank = i > 0 && !(is_signature && previous_signature);
+            previous_signature = is_signature;
+
+            if let Stmt::Function {
+                name,
+                params,
+                body,
+                is_async,
+            } = member
+            {
+                self.begin_stmt(Some(name), force_blank);
+                let prefix = if *is_async { "async " } else { "" };
+                if is_signature {
+                    let params: Vec<&str> = params.iter().map(|p| p.lexeme.as_str()).collect();
+                    self.out.push_str(&format!(
+                        "{}{}({});\n",
+                        prefix,
+                        name.lexeme,
+                        params.join(", ")
+                    ));
+                } else {
+                    self.function(prefix, name, params, body);
+                }
+            }
+        }
+        self.flush_comments(end);
+        self.indent -= 1;
+        self.write_indent();
+        self.out.push_str("}\n");
+    }
+
+    fn visit_enum_stmt(&mut self, name: &Token, variants: &[(Token, Vec<Token>)], expose: bool) {
+        self.begin_stmt(Some(name), false);
+        if expose {
+            self.out.push_str("expose ");
+        }
+        self.out.push_str(&format!("enum {} {{", name.lexeme));
+
+        let end = self.open_block();
+        if variants.is_empty() && !self.has_comments_before(end) {
+            self.out.push_str("}\n");
+            return;
+        }
+
+        // One variant per line, each followed by a comma
+        self.out.push('\n');
+        self.indent += 1;
+        for (variant, fields) in variants {
+            // Variants follow a comma, so they can't use `begin_stmt`
+            let start = position(variant);
+            if self.flush_comments_with(start, false) | self.gap_before(variant.line, start) {
+                self.blank_line();
+            }
+            self.write_indent();
+            self.out.push_str(&variant.lexeme);
+            if !fields.is_empty() {
+                let fields: Vec<&str> = fields.iter().map(|f| f.lexeme.as_str()).collect();
+                self.out.push_str(&format!("({})", fields.join(", ")));
+            }
+            self.out.push_str(",\n");
+        }
+        self.flush_comments(end);
+        self.indent -= 1;
+        self.write_indent();
+        self.out.push_str("}\n");
+    }
+
+    fn visit_field_stmt(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Box<Expr>>,
+        constant: bool,
+        is_static: bool,
+    ) {
+        self.begin_stmt(Some(name), false);
+        if is_static {
+            self.out.push_str("static ");
+        }
+        self.out.push_str(if constant { "const " } else { "let " });
+        self.out.push_str(&name.lexeme);
+        if let Some(initializer) = initializer {
+            let value = self.expr(initializer);
+            self.out.push_str(&format!(" = {}", value));
+        }
+        self.out.push_str(";\n");
+    }
+
+    fn visit_accessor_stmt(&mut self, kind: &Token, name: &Token, params: &[Token], body: &[Stmt]) {
+        self.begin_stmt(Some(name), false);
+        self.function(&format!("{} ", kind.lexeme), name, params, body);
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        is_async: bool,
+    ) {
+        self.begin_stmt(Some(name), false);
+        let prefix = if is_async { "async fn " } else { "fn " };
+        self.function(prefix, name, params, body);
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) {
+        let anchor = resolver::expr_token(condition).cloned();
+        self.begin_stmt(anchor.as_ref(), false);
+        let condition = self.expr(condition);
+        self.out.push_str(&format!("if ({}) ", condition));
+        let braced = self.clause(then_branch);
+
+        if let Some(else_branch) = else_branch {
+            if braced {
+                self.out.push_str(" else ");
+            } else {
+                self.out.push('\n');
+                self.write_indent();
+                self.out.push_str("else ");
+            }
+            self.clause(else_branch);
+        }
+        self.out.push('\n');
+    }
+
+    fn visit_print_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.begin_stmt(Some(keyword), false);
+        let text = self.expr(expression);
+        self.out.push_str(&format!("print {};\n", text));
+    }
+
+    fn visit_println_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.begin_stmt(Some(keyword), false);
+        let text = self.expr(expression);
+        self.out.push_str(&format!("println {};\n", text));
+    }
+
+    fn visit_let_stmt(&mut self, name: &Token, initializer: &Expr) {
+        self.begin_stmt(Some(name), false);
+        let text = self.let_text(name, initializer);
+        self.out.push_str(&text);
+        self.out.push('\n');
+    }
+
+    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) {
+        let anchor = resolver::expr_token(condition).cloned();
+        self.begin_stmt(anchor.as_ref(), false);
+        let condition = self.expr(condition);
+        self.out.push_str(&format!("while ({}) ", condition));
+        self.clause(body);
+        self.out.push('\n');
+    }
+
+    fn visit_for_stmt(
+        &mut self,
+        keyword: &Token,
+        initializer: &Option<Box<Stmt>>,
+        condition: &Option<Box<Expr>>,
+        increment: &Option<Box<Expr>>,
+        body: &Stmt,
+    ) {
+        self.begin_stmt(Some(keyword), false);
+        let initializer = self.header_stmt(initializer);
+        let condition = match condition {
+            Some(c) => format!(" {};", self.expr(c)),
+            None => ";".to_string(),
+        };
+        let increment = match increment {
+            Some(i) => format!(" {}", self.expr(i)),
+            None => String::new(),
+        };
+        self.out
+            .push_str(&format!("for ({}{}{}) ", initializer, condition, increment));
+        self.clause(body);
+        self.out.push('\n');
+    }
+
+    fn visit_loop_stmt(
+        &mut self,
+        keyword: &Token,
+        name: &Token,
+        start: &Expr,
+        end: &Expr,
+        step: &Expr,
+        body: &Stmt,
+    ) {
+        self.begin_stmt(Some(keyword), false);
+        let (start, end, step) = (self.expr(start), self.expr(end), self.expr(step));
+        self.out.push_str(&format!(
+            "loop (let {} = {}..{}; {}) ",
+            name.lexeme, start, end, step
+        ));
+        self.clause(body);
+        self.out.push('\n');
+    }
+
+    fn visit_each_stmt(&mut self, keyword: &Token, name: &Token, iterable: &Expr, body: &Stmt) {
+        self.begin_stmt(Some(keyword), false);
+        let iterable = self.expr(iterable);
+        self.out
+            .push_str(&format!("loop (let {} in {}) ", name.lexeme, iterable));
+        self.clause(body);
+        self.out.push('\n');
+    }
+
+    fn visit_use_stmt(
+        &mut self,
+        keyword: &Token,
+        path: &str,
+        alias: &Token,
+        _class: &Option<Box<Stmt>>,
+    ) {
+        self.begin_stmt(Some(keyword), false);
+        self.out
+            .push_str(&format!("use {} as {};\n", path, alias.lexeme));
+    }
+
+    fn visit_exception_stmt(
+        &mut self,
+        keyword: &Token,
+        do_stmts: &Stmt,
+        fail_stmts: &Stmt,
+        fail_binding: &Option<Box<Stmt>>,
+        finish_stmts: &Option<Box<Stmt>>,
+    ) {
+        self.begin_stmt(Some(keyword), false);
+        self.out.push_str("do ");
+        self.clause(do_stmts);
+
+        self.out.push_str(" fail ");
+        if let Some(binding) = fail_binding {
+            if let Stmt::Let { name, .. } = binding.as_ref() {
+                self.out.push_str(&format!("(let {}) ", name.lexeme));
+            }
+        }
+        self.clause(fail_stmts);
+
+        if let Some(finish) = finish_stmts {
+            self.out.push_str(" finish ");
+            self.clause(finish);
+        }
+        self.out.push('\n');
+    }
+
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) {
+        self.begin_stmt(Some(keyword), false);
+        match value {
+            Some(value) => {
+                let text = self.expr(value);
+                self.out.push_str(&format!("return {};\n", text));
+            }
+            None => self.out.push_str("return;\n"),
+        }
+    }
+
+    fn visit_yield_stmt(&mut self, keyword: &Token, value: &Option<Box<Expr>>) {
+        self.begin_stmt(Some(keyword), false);
+        match value {
+            Some(value) => {
+                let text = self.expr(value);
+                self.out.push_str(&format!("yield {};\n", text));
+            }
+            None => self.out.push_str("yield;\n"),
+        }
+    }
+
+    fn visit_throw_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.begin_stmt(Some(keyword), false);
+        let text = self.expr(expression);
+        self.out.push_str(&format!("throw {};\n", text));
+    }
+
+    fn visit_fatal_stmt(&mut self, keyword: &Token, expression: &Expr) {
+        self.begin_stmt(Some(keyword), false);
+        let text = self.expr(expression);
+        self.out.push_str(&format!("fatal {};\n", text));
+    }
+
+    fn visit_break_stmt(&mut self, keyword: &Token) {
+        self.begin_stmt(Some(keyword), false);
+        self.out.push_str("break;\n");
+    }
+
+    fn visit_continue_stmt(&mut self, keyword: &Token) {
+        self.begin_stmt(Some(keyword), false);
+        self.out.push_str("continue;\n");
+    }
+}
+
+impl expr::Visitor<String> for Formatter {
+    fn visit_assign_expr(&mut self, _id: ExprId, name: &Token, value: &Expr) -> String {
+        format!("{} = {}", name.lexeme, self.expr(value))
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        format!(
+            "{} {} {}",
+            self.expr(left),
+            operator.lexeme,
+            self.expr(right)
+        )
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, arguments: &Vec<Expr>) -> String {
+        format!("{}({})", self.expr(callee), self.exprs(arguments))
+    }
+
+    fn visit_get_expr(&mut self, _id: ExprId, object: &Expr, name: &Token) -> String {
+        let object = self.expr(object);
+        match self.accessor_before(name) {
+            TokenType::Getter => format!("{} -> {}", object, name.lexeme),
+            _ => format!("{}.{}", object, name.lexeme),
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> String {
+        format!("({})", self.expr(expression))
+    }
+
+    fn visit_literal_expr(&mut self, value: &Object) -> String {
+        match value {
+            Object::Str(s) => {
+                // The lexer has no escape for `"`, so a string can never contain one
+                let escaped = s
+                    .replace('\\', "\\\\")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t")
+                    .replace('\r', "\\r");
+                format!("\"{}\"", escaped)
+            }
+            Object::Number(n) => {
+                let mut s = n.to_string();
+                if s.ends_with(".0") {
+                    s.truncate(s.len() - 2);
+                }
+                s
+            }
+            Object::Bool(b) => b.to_string(),
+            _ => "null".to_string(),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        format!(
+            "{} {} {}",
+            self.expr(left),
+            operator.lexeme,
+            self.expr(right)
+        )
+    }
+
+    fn visit_set_expr(&mut self, _id: ExprId, object: &Expr, name: &Token, value: &Expr) -> String {
+        let (object, value) = (self.expr(object), self.expr(value));
+        match self.accessor_before(name) {
+            TokenType::Setter => format!("{} <- {} = {}", object, name.lexeme, value),
+            TokenType::Getter => format!("{} -> {} = {}", object, name.lexeme, value),
+            _ => format!("{}.{} = {}", object, name.lexeme, value),
+        }
+    }
+
+    fn visit_this_expr(&mut self, _id: ExprId, _keyword: &Token) -> String {
+        "this".to_string()
+    }
+
+    fn visit_base_expr(&mut self, _id: ExprId, _keyword: &Token, method: &Token) -> String {
+        format!("base.{}", method.lexeme)
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> String {
+        format!("{}{}", operator.lexeme, self.expr(right))
+    }
+
+    fn visit_variable_expr(&mut self, _id: ExprId, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+
+    fn visit_range_expr(&mut self, start: &Expr, end: &Expr) -> String {
+        format!("{}..{}", self.expr(start), self.expr(end))
+    }
+
+    fn visit_await_expr(&mut self, _keyword: &Token, value: &Expr) -> String {
+        format!("await {}", self.expr(value))
+    }
+
+    fn visit_meta_expr(
+        &mut self,
+        _id: ExprId,
+        _keyword: &Token,
+        method: &Token,
+        args: &Vec<Expr>,
+    ) -> String {
+        format!("@{}({})", method.lexeme, self.exprs(args))
+    }
+}
+
+fn position(token: &Token) -> Position {
+    (token.line, token.place)
+}
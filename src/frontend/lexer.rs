@@ -1,12 +1,14 @@
-use std::process;
-
-use crate::crux::error::{ReiError, SyntaxError};
+use crate::crux::error::{ParseError, ReiError, SyntaxError};
+use crate::crux::output;
 use crate::crux::token::{Object, Token, TokenType, KEYWORDS};
 
 pub struct Lexer<'a> {
     source: &'a str,
     current_path: String,
     tokens: Vec<Token>,
+    trivia: Vec<Token>,
+    errors: Vec<ParseError>,
+    recover: bool,
     start: usize,
     current: usize,
     line: usize,
@@ -21,31 +23,50 @@ impl<'a> Lexer<'a> {
             source,
             current_path,
             tokens,
+            trivia: vec![],
+            errors: vec![],
+            recover: false,
             start: 0,
             current: 0,
             line: 1,
-            place: 1,
+            place: 0,
             length: source.len(),
         }
     }
 
-    pub fn scan_tokens(mut self) -> Vec<Token> {
+    pub fn scan_tokens(self) -> Vec<Token> {
+        self.scan_with_trivia().0
+    }
+
+    /// Same as `scan_tokens`, but also hands back the comments that were skipped,
+    /// in source order, for tools that need to reproduce the original text.
+    pub fn scan_with_trivia(mut self) -> (Vec<Token>, Vec<Token>) {
+        self.scan();
+        (self.tokens, self.trivia)
+    }
+
+    /// Scans the whole source without ever exiting the process. Bad characters
+    /// and unterminated strings are skipped and reported as errors instead.
+    pub fn scan_recovering(mut self) -> (Vec<Token>, Vec<Token>, Vec<ParseError>) {
+        self.recover = true;
+        self.scan();
+        (self.tokens, self.trivia, self.errors)
+    }
+
+    fn scan(&mut self) {
         while !self.is_end() {
             self.start = self.current;
             self.scan_token();
         }
 
-        let path = self.current_path;
         self.tokens.push(Token::new(
             TokenType::Eof,
             String::from(""),
             Object::Null,
             self.line,
             self.place,
-            path,
+            self.current_path.clone(),
         ));
-
-        self.tokens
     }
 
     fn scan_token(&mut self) {
@@ -107,11 +128,14 @@ impl<'a> Lexer<'a> {
                 }
             }
             '@' => self.add_token(TokenType::At, Object::Null),
+            // `#name` is a private member, the `#` is part of its name
+            '#' if self.is_alpha(self.peek()) => self.identifier(),
             '/' => {
                 if self.match_next_char('/') {
                     while self.peek() != '\n' && !self.is_end() {
                         let _ = self.advance();
                     }
+                    self.add_trivia();
                 } else if self.match_next_char('*') {
                     while !self.is_end() {
                         if self.peek() == '*' && self.peek_next() == '/' {
@@ -125,6 +149,7 @@ impl<'a> Lexer<'a> {
                         }
                         self.advance();
                     }
+                    self.add_trivia();
                 } else {
                     self.add_token(TokenType::Slash, Object::Null)
                 }
@@ -142,6 +167,8 @@ impl<'a> Lexer<'a> {
                     self.number();
                 } else if self.is_alpha(c) {
                     self.identifier();
+                } else if self.recover {
+                    self.add_error(format!("Unexpected character '{}'", c));
                 } else {
                     SyntaxError::throw_error(&(self.line, self.current), "syntax error")
                 }
@@ -177,9 +204,13 @@ impl<'a> Lexer<'a> {
         }
 
         if self.is_end() {
+            if self.recover {
+                self.add_error("Unterminated string".to_string());
+                return;
+            }
             // Error handling needed
-            println!("Unterminated string");
-            process::exit(65);
+            output::out("Unterminated string\n");
+            output::exit(65);
         }
 
         self.advance();
@@ -221,7 +252,7 @@ impl<'a> Lexer<'a> {
         self.current >= self.length
     }
 
-    fn peek(&mut self) -> char {
+    fn peek(&self) -> char {
         if self.is_end() {
             '\0'
         } else {
@@ -260,6 +291,34 @@ impl<'a> Lexer<'a> {
         self.tokens.push(token);
     }
 
+    fn add_error(&mut self, message: String) {
+        let text = self.source[self.start..self.current].to_string();
+        let token = Token::new(
+            TokenType::Eof,
+            text,
+            Object::Null,
+            self.line,
+            self.place,
+            self.current_path.clone(),
+        );
+
+        self.errors.push(ParseError::SyntaxError { token, message });
+    }
+
+    fn add_trivia(&mut self) {
+        let text = self.source[self.start..self.current].to_string();
+        let token = Token::new(
+            TokenType::Comment,
+            text,
+            Object::Null,
+            self.line,
+            self.place,
+            self.current_path.clone(),
+        );
+
+        self.trivia.push(token);
+    }
+
     fn identifier(&mut self) {
         let mut c = self.peek();
         while self.is_alpha_numeric(c) {
@@ -294,6 +353,7 @@ impl<'a> Lexer<'a> {
         }
 
         self.current += 1;
+        self.place += 1;
         true
     }
 }
@@ -1,4 +1,5 @@
 pub mod expr;
 pub mod lexer;
 // pub mod ast_printer;
+pub mod formatter;
 pub mod parser;
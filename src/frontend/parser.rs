@@ -10,6 +10,7 @@ use super::expr::ExprId;
 use crate::backend::stmt;
 
 use crate::crux::error::ParseError;
+use crate::crux::output;
 use crate::crux::token::{Object, Token, TokenType};
 use crate::crux::util;
 
@@ -22,7 +23,9 @@ pub struct Parser<'a> {
     exposed: bool,
     current_file: &'a Option<PathBuf>,
     pub is_error: bool,
+    pub errors: Vec<ParseError>,
     pub path_tracker: Rc<RefCell<String>>,
+    pub load_modules: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -39,7 +42,9 @@ impl<'a> Parser<'a> {
             exposed: false,
             current_file,
             is_error: false,
+            errors: Vec::new(),
             path_tracker,
+            load_modules: true,
         }
     }
 
@@ -53,7 +58,8 @@ impl<'a> Parser<'a> {
                     self.is_error = true;
                     let location =
                         util::red_colored(&format!("({:?})", self.path_tracker.clone().borrow()));
-                    eprintln!("{} {}", e, location);
+                    output::err(&format!("{} {}\n", e, location));
+                    self.errors.push(e);
                     self.synchronize();
                 }
             }
@@ -72,6 +78,8 @@ impl<'a> Parser<'a> {
         // Return
         else if self.rmatch(&[TokenType::Return])? {
             self.return_statement()
+        } else if self.rmatch(&[TokenType::Yield])? {
+            self.yield_statement()
         }
         // Block
         else if self.rmatch(&[TokenType::LeftBrace])? {
@@ -106,12 +114,25 @@ impl<'a> Parser<'a> {
         // Class
         else if self.rmatch(&[TokenType::Class])? {
             self.class_declaration(false)
+        } else if self.rmatch(&[TokenType::Trait])? {
+            self.trait_declaration(false)
+        } else if self.rmatch(&[TokenType::Enum])? {
+            self.enum_declaration(false)
         }
         // Modules
         else if self.rmatch(&[TokenType::Use])? {
             self.use_module()
         } else if self.rmatch(&[TokenType::Expose])? {
-            self.consume(&TokenType::Class, "Expected 'class' after expose")?;
+            if self.rmatch(&[TokenType::Trait])? {
+                return self.trait_declaration(true);
+            }
+            if self.rmatch(&[TokenType::Enum])? {
+                return self.enum_declaration(true);
+            }
+            self.consume(
+                &TokenType::Class,
+                "Expected 'class', 'trait' or 'enum' after expose",
+            )?;
             self.class_declaration(true)
         } else {
             self.expression_statement()
@@ -119,6 +140,8 @@ impl<'a> Parser<'a> {
     }
 
     fn exception_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
+
         // do block
         let do_stmts = Box::new(self.statement()?);
 
@@ -163,6 +186,7 @@ impl<'a> Parser<'a> {
         };
 
         Ok(stmt::Stmt::Exception {
+            keyword,
             do_stmts,
             fail_stmts,
             fail_binding,
@@ -171,9 +195,11 @@ impl<'a> Parser<'a> {
     }
 
     fn throw_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
 
         let throw = stmt::Stmt::Throw {
+            keyword,
             expression: Box::new(value),
         };
 
@@ -187,9 +213,11 @@ impl<'a> Parser<'a> {
     }
 
     fn fatal_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
 
         let fatal = stmt::Stmt::Fatal {
+            keyword,
             expression: Box::new(value),
         };
 
@@ -203,6 +231,7 @@ impl<'a> Parser<'a> {
     }
 
     fn use_module(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let mut path_parts = vec![];
 
         if self.check(&TokenType::Number) {
@@ -225,6 +254,17 @@ impl<'a> Parser<'a> {
         self.consume(&TokenType::Semicolon, "Expected ';' after use statement")?;
 
         let path = path_parts.join("/");
+
+        // Formatting and other syntax-only passes don't need the module body
+        if !self.load_modules {
+            return Ok(stmt::Stmt::Use {
+                keyword,
+                path,
+                alias,
+                class: None,
+            });
+        }
+
         let resolved_path = self.resolve_path(&path)?;
         self.path_tracker = Rc::new(RefCell::new(resolved_path.clone()));
 
@@ -240,11 +280,55 @@ impl<'a> Parser<'a> {
 
         let stmts = parser.parse().clone();
         for stmt in stmts {
+            if let stmt::Stmt::Trait {
+                name: _,
+                required,
+                methods,
+                expose: true,
+            } = stmt
+            {
+                let trait_stmt = stmt::Stmt::Trait {
+                    name: alias.clone(),
+                    required,
+                    methods,
+                    expose: false,
+                };
+                return Ok(stmt::Stmt::Use {
+                    keyword,
+                    path,
+                    alias,
+                    class: Some(Box::new(trait_stmt)),
+                });
+            }
+
+            if let stmt::Stmt::Enum {
+                name: _,
+                variants,
+                expose: true,
+            } = stmt
+            {
+                let enum_stmt = stmt::Stmt::Enum {
+                    name: alias.clone(),
+                    variants,
+                    expose: false,
+                };
+                return Ok(stmt::Stmt::Use {
+                    keyword,
+                    path,
+                    alias,
+                    class: Some(Box::new(enum_stmt)),
+                });
+            }
+
             if let stmt::Stmt::Class {
                 name: _,
                 superclass_refs,
                 methods,
                 static_methods,
+                fields,
+                accessors,
+                private_members,
+                traits,
                 expose: true,
             } = stmt
             {
@@ -253,9 +337,18 @@ impl<'a> Parser<'a> {
                     superclass_refs: superclass_refs.clone(),
                     methods: methods.clone(),
                     static_methods: static_methods.clone(),
+                    fields: fields.clone(),
+                    accessors: accessors.clone(),
+                    private_members: private_members.clone(),
+                    traits: traits.clone(),
                     expose: false,
                 };
-                return Ok(class_stmt);
+                return Ok(stmt::Stmt::Use {
+                    keyword,
+                    path,
+                    alias,
+                    class: Some(Box::new(class_stmt)),
+                });
             }
         }
 
@@ -283,7 +376,10 @@ impl<'a> Parser<'a> {
 
         let mut superclass_refs = Vec::new();
         if self.rmatch(&[TokenType::Less])? {
-            while !self.check(&TokenType::LeftBrace) && !self.is_end() {
+            while !self.check(&TokenType::LeftBrace)
+                && !self.check(&TokenType::Impl)
+                && !self.is_end()
+            {
                 if self.check(&TokenType::Comma) {
                     self.consume(&TokenType::Comma, "Expected ','")?;
                 }
@@ -297,20 +393,69 @@ impl<'a> Parser<'a> {
             }
         }
 
+        let mut traits = Vec::new();
+        if self.rmatch(&[TokenType::Impl])? {
+            loop {
+                let name = self
+                    .consume(&TokenType::Identifier, "Expect trait name")?
+                    .clone();
+                traits.push(expr::Expr::Variable {
+                    id: self.next_id(),
+                    name,
+                });
+                if !self.rmatch(&[TokenType::Comma])? {
+                    break;
+                }
+            }
+        }
+
         self.consume(&TokenType::LeftBrace, "Expected { before class body")?;
 
         let mut methods = vec![];
         let mut static_methods = vec![];
+        let mut fields = vec![];
+        let mut accessors = vec![];
+        let mut private_members = vec![];
 
         while !self.check(&TokenType::RightBrace) && !self.is_end() {
-            if self.check(&TokenType::Static) {
-                self.consume(
-                    &TokenType::Static,
-                    "Expected 'static' keyword for static method",
-                )?;
-                static_methods.push(self.function("sfunction")?);
+            let is_private = self.rmatch(&[TokenType::Private])?;
+            let is_static = self.rmatch(&[TokenType::Static])?;
+            let is_async = self.rmatch(&[TokenType::Async])?;
+            if is_async && (self.check(&TokenType::Let) || self.check(&TokenType::Const)) {
+                return Err(ParseError::SyntaxError {
+                    token: self.peek().clone(),
+                    message: "Only methods can be async".into(),
+                });
+            }
+            let member = if self.rmatch(&[TokenType::Let, TokenType::Const])? {
+                fields.push(self.field_declaration(is_static)?);
+                fields.last()
+            } else if !is_async && self.check_accessor() {
+                if is_static {
+                    return Err(ParseError::SyntaxError {
+                        token: self.peek().clone(),
+                        message: "Accessors can't be static".into(),
+                    });
+                }
+                accessors.push(self.accessor()?);
+                accessors.last()
+            } else if is_static {
+                static_methods.push(self.function("sfunction", is_async)?);
+                static_methods.last()
             } else {
-                methods.push(self.function("function")?);
+                methods.push(self.function("function", is_async)?);
+                methods.last()
+            };
+
+            if let Some(
+                stmt::Stmt::Function { name, .. }
+                | stmt::Stmt::Field { name, .. }
+                | stmt::Stmt::Accessor { name, .. },
+            ) = member
+            {
+                if is_private || name.lexeme.starts_with('#') {
+                    private_members.push(name.clone());
+                }
             }
         }
 
@@ -320,12 +465,190 @@ impl<'a> Parser<'a> {
             superclass_refs,
             methods,
             static_methods,
+            fields,
+            accessors,
+            private_members,
+            traits,
             expose,
         };
 
         Ok(class)
     }
 
+    fn trait_declaration(&mut self, expose: bool) -> Result<stmt::Stmt, ParseError> {
+        if self.exposed {
+            return Err(ParseError::SyntaxError {
+                token: self.previous().clone(),
+                message: "Can't expose multiple classes from a single file".to_string(),
+            });
+        }
+        self.exposed = expose;
+
+        let name = self
+            .consume(&TokenType::Identifier, "Expected a trait name")?
+            .clone();
+        self.consume(&TokenType::LeftBrace, "Expected { before trait body")?;
+
+        let mut required = vec![];
+        let mut methods = vec![];
+        while !self.check(&TokenType::RightBrace) && !self.is_end() {
+            let is_async = self.rmatch(&[TokenType::Async])?;
+            let method = self
+                .consume(&TokenType::Identifier, "Expect method name")?
+                .clone();
+            self.consume(&TokenType::LeftParen, "Expect '(' after method name")?;
+            let params = self.parameters()?;
+            self.consume(&TokenType::RightParen, "Expect ')' after parameters")?;
+
+            // A signature ends with ';', a default implementation has a body
+            if self.rmatch(&[TokenType::Semicolon])? {
+                required.push(stmt::Stmt::Function {
+                    name: method,
+                    params,
+                    body: vec![],
+                    is_async,
+                });
+                continue;
+            }
+
+            self.consume(
+                &TokenType::LeftBrace,
+                "Expect ';' or '{' after trait method",
+            )?;
+            let stmt::Stmt::Block { statements } = self.block()? else {
+                unreachable!("block() always returns a block")
+            };
+            methods.push(stmt::Stmt::Function {
+                name: method,
+                params,
+                body: statements,
+                is_async,
+            });
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected } after trait body")?;
+        Ok(stmt::Stmt::Trait {
+            name,
+            required,
+            methods,
+            expose,
+        })
+    }
+
+    fn enum_declaration(&mut self, expose: bool) -> Result<stmt::Stmt, ParseError> {
+        if self.exposed {
+            return Err(ParseError::SyntaxError {
+                token: self.previous().clone(),
+                message: "Can't expose multiple classes from a single file".to_string(),
+            });
+        }
+        self.exposed = expose;
+
+        let name = self
+            .consume(&TokenType::Identifier, "Expected an enum name")?
+            .clone();
+        self.consume(&TokenType::LeftBrace, "Expected { before enum body")?;
+
+        let mut variants: Vec<(Token, Vec<Token>)> = vec![];
+        while !self.check(&TokenType::RightBrace) && !self.is_end() {
+            let variant = self
+                .consume(&TokenType::Identifier, "Expect variant name")?
+                .clone();
+            if variants.iter().any(|(v, _)| v.lexeme == variant.lexeme) {
+                return Err(ParseError::SyntaxError {
+                    token: variant,
+                    message: "Duplicate enum variant".into(),
+                });
+            }
+
+            let mut fields = vec![];
+            if self.rmatch(&[TokenType::LeftParen])? {
+                fields = self.parameters()?;
+                self.consume(&TokenType::RightParen, "Expect ')' after variant fields")?;
+            }
+            variants.push((variant, fields));
+
+            // The comma after the last variant is optional
+            if !self.rmatch(&[TokenType::Comma])? {
+                break;
+            }
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected } after enum body")?;
+        Ok(stmt::Stmt::Enum {
+            name,
+            variants,
+            expose,
+        })
+    }
+
+    /// `get` and `set` are only keywords when a property name follows, so
+    /// methods can still be called `get` or `set`.
+    fn check_accessor(&self) -> bool {
+        let next = self.tokens.get(self.current + 1);
+        self.check(&TokenType::Identifier)
+            && matches!(self.peek().lexeme.as_str(), "get" | "set")
+            && next.is_some_and(|t| t.token_type == TokenType::Identifier)
+    }
+
+    fn accessor(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let kind = self.advance().clone();
+        let (label, arity) = match kind.lexeme.as_str() {
+            "get" => ("getter", 0),
+            _ => ("setter", 1),
+        };
+
+        let stmt::Stmt::Function {
+            name, params, body, ..
+        } = self.function(label, false)?
+        else {
+            unreachable!("function() always returns a function")
+        };
+        if params.len() != arity {
+            let message = match arity {
+                0 => "A getter takes no parameters",
+                _ => "A setter takes exactly one parameter",
+            };
+            return Err(ParseError::SyntaxError {
+                token: name,
+                message: message.into(),
+            });
+        }
+
+        Ok(stmt::Stmt::Accessor {
+            kind,
+            name,
+            params,
+            body,
+        })
+    }
+
+    fn field_declaration(&mut self, is_static: bool) -> Result<stmt::Stmt, ParseError> {
+        let constant = self.previous().token_type == TokenType::Const;
+        let name = self
+            .consume(&TokenType::Identifier, "Expect field name")?
+            .clone();
+
+        let initializer = if self.rmatch(&[TokenType::Equal])? {
+            Some(Box::new(self.expression()?))
+        } else if constant {
+            return Err(ParseError::SyntaxError {
+                token: name,
+                message: "Expect a value for constant field".into(),
+            });
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::Semicolon, "Expect ';' after field declaration")?;
+        Ok(stmt::Stmt::Field {
+            name,
+            initializer,
+            constant,
+            is_static,
+        })
+    }
+
     fn return_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
         let keyword = self.previous().clone();
         let value = if !self.check(&TokenType::Semicolon) {
@@ -341,29 +664,44 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn yield_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let value = if !self.check(&TokenType::Semicolon) {
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::Semicolon, "Expect ';' after yield value.")?;
+        Ok(stmt::Stmt::Yield { keyword, value })
+    }
+
     fn break_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::Semicolon, "Expected ';'")?;
-        Ok(stmt::Stmt::Break)
+        Ok(stmt::Stmt::Break { keyword })
     }
 
     fn continue_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::Semicolon, "Expected ';'")?;
-        Ok(stmt::Stmt::Continue)
+        Ok(stmt::Stmt::Continue { keyword })
     }
 
     fn for_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LeftParen, "Expected a '(' after 'for'")?;
 
         let initializer = if self.rmatch(&[TokenType::Semicolon])? {
             None
         } else if self.rmatch(&[TokenType::Let])? {
-            Some(self.var_declaration()?)
+            Some(Box::new(self.var_declaration()?))
         } else {
-            Some(self.expression_statement()?)
+            Some(Box::new(self.expression_statement()?))
         };
 
         let condition = if !self.check(&TokenType::Semicolon) {
-            Some(self.expression()?)
+            Some(Box::new(self.expression()?))
         } else {
             None
         };
@@ -371,56 +709,56 @@ impl<'a> Parser<'a> {
         self.consume(&TokenType::Semicolon, "Expected a ';'")?;
 
         let increment = if !self.check(&TokenType::RightParen) {
-            Some(self.expression()?)
+            Some(Box::new(self.expression()?))
         } else {
             None
         };
 
         self.consume(&TokenType::RightParen, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-        if let Some(inc) = increment {
-            body = stmt::Stmt::Block {
-                statements: vec![
-                    body,
-                    stmt::Stmt::Expression {
-                        expression: Box::new(inc),
-                    },
-                ],
-            }
-        };
+        let body = self.statement()?;
 
-        let cond_expr = condition.unwrap_or(expr::Expr::Literal {
-            id: self.next_id(),
-            value: Object::Bool(true),
-        });
-        body = stmt::Stmt::While {
-            condition: Box::new(cond_expr),
+        Ok(stmt::Stmt::For {
+            keyword,
+            initializer,
+            condition,
+            increment,
             body: Box::new(body),
-        };
-
-        if let Some(init) = initializer {
-            body = stmt::Stmt::Block {
-                statements: vec![init, body],
-            }
-        };
-
-        Ok(body)
+        })
     }
 
     fn loop_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LeftParen, "Expected '(' after 'loop'")?;
         self.consume(&TokenType::Let, "Expected 'let' in loop declaration")?;
         let name = self
             .consume(&TokenType::Identifier, "Expected loop variable name")?
             .clone();
-        self.consume(&TokenType::Equal, "Expected '=' in loop declaration")?;
+
+        // `in` is only a keyword here, right after the loop variable
+        if self.check(&TokenType::Identifier) && self.peek().lexeme == "in" {
+            self.advance();
+            let iterable = self.expression()?;
+            self.consume(&TokenType::RightParen, "Expected ')' after loop iterable")?;
+            let body = self.statement()?;
+            return Ok(stmt::Stmt::Each {
+                keyword,
+                name,
+                iterable: Box::new(iterable),
+                body: Box::new(body),
+            });
+        }
+
+        self.consume(
+            &TokenType::Equal,
+            "Expected '=' or 'in' in loop declaration",
+        )?;
         let range_expr = self.expression()?;
         self.consume(&TokenType::Semicolon, "Expected ; for skipping")?;
-        let times = self.expression()?;
+        let step = self.expression()?;
 
-        let (start_expr, end_expr) = match range_expr {
-            expr::Expr::Range { id: _, start, end } => (*start, *end),
+        let (start, end) = match range_expr {
+            expr::Expr::Range { id: _, start, end } => (start, end),
             _ => {
                 return Err(ParseError::SyntaxError {
                     token: self.peek().clone(),
@@ -435,57 +773,13 @@ impl<'a> Parser<'a> {
 
         let body = self.statement()?;
 
-        // Same desugaring as `for`: convert to a while
-        let init = stmt::Stmt::Let {
-            name: name.clone(),
-            initializer: Box::new(start_expr),
-        };
-
-        let direction = match times {
-            expr::Expr::Literal {
-                id: _,
-                value: Object::Number(_),
-            } => TokenType::Less,
-            _ => TokenType::Greater,
-        };
-
-        let condition = expr::Expr::Binary {
-            id: self.next_id(),
-            left: Box::new(expr::Expr::Variable {
-                id: self.next_id(),
-                name: name.clone(),
-            }),
-            operator: Token::fake(direction),
-            right: Box::new(end_expr),
-        };
-
-        let increment = stmt::Stmt::Expression {
-            expression: Box::new(expr::Expr::Assign {
-                id: self.next_id(),
-                name: name.clone(),
-                value: Box::new(expr::Expr::Binary {
-                    id: self.next_id(),
-                    left: Box::new(expr::Expr::Variable {
-                        id: self.next_id(),
-                        name: name.clone(),
-                    }),
-                    operator: Token::fake(TokenType::Plus),
-                    right: Box::new(times),
-                }),
-            }),
-        };
-
-        let while_body = stmt::Stmt::Block {
-            statements: vec![body, increment],
-        };
-
-        let while_stmt = stmt::Stmt::While {
-            condition: Box::new(condition),
-            body: Box::new(while_body),
-        };
-
-        Ok(stmt::Stmt::Block {
-            statements: vec![init, while_stmt],
+        Ok(stmt::Stmt::Loop {
+            keyword,
+            name,
+            start,
+            end,
+            step: Box::new(step),
+            body: Box::new(body),
         })
     }
 
@@ -535,7 +829,11 @@ impl<'a> Parser<'a> {
     fn declaration(&mut self) -> Result<stmt::Stmt, ParseError> {
         let res = (|| {
             if self.rmatch(&[TokenType::Fn])? {
-                return self.function("function");
+                return self.function("function", false);
+            }
+            if self.rmatch(&[TokenType::Async])? {
+                self.consume(&TokenType::Fn, "Expected 'fn' after 'async'")?;
+                return self.function("function", true);
             }
             if self.rmatch(&[TokenType::Let])? {
                 return self.var_declaration();
@@ -553,14 +851,34 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn function(&mut self, kind: &str) -> Result<stmt::Stmt, ParseError> {
+    fn function(&mut self, kind: &str, is_async: bool) -> Result<stmt::Stmt, ParseError> {
         let err = format!("Expect {} name", kind);
         let err1 = format!("Expect '(' {} name", kind);
         let err2 = format!("Expect '{{' before {} name", kind);
 
         let name = self.consume(&TokenType::Identifier, &err)?.clone();
         self.consume(&TokenType::LeftParen, &err1)?;
+        let parameters = self.parameters()?;
+
+        self.consume(&TokenType::RightParen, &err2)?;
+        self.consume(&TokenType::LeftBrace, &err2)?;
+        let body = match self.block()? {
+            stmt::Stmt::Block { statements } => statements,
+            _ => {
+                panic!("AHHHHHHHHH");
+            }
+        };
+
+        Ok(stmt::Stmt::Function {
+            name: name.clone(),
+            params: parameters,
+            body,
+            is_async,
+        })
+    }
 
+    /// Comma separated parameter names, up to the closing parenthesis.
+    fn parameters(&mut self) -> Result<Vec<Token>, ParseError> {
         let mut parameters = vec![];
         if !self.check(&TokenType::RightParen) {
             loop {
@@ -580,23 +898,11 @@ impl<'a> Parser<'a> {
             }
         }
 
-        self.consume(&TokenType::RightParen, &err2)?;
-        self.consume(&TokenType::LeftBrace, &err2)?;
-        let body = match self.block()? {
-            stmt::Stmt::Block { statements } => statements,
-            _ => {
-                panic!("AHHHHHHHHH");
-            }
-        };
-
-        Ok(stmt::Stmt::Function {
-            name: name.clone(),
-            params: parameters,
-            body,
-        })
+        Ok(parameters)
     }
 
     fn print_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
 
         if self.peek().token_type == TokenType::DotDot {
@@ -613,12 +919,14 @@ impl<'a> Parser<'a> {
                 end: Box::new(end),
             };
             return Ok(stmt::Stmt::Print {
+                keyword,
                 expression: Box::new(range),
             });
         }
 
         match self.consume(&TokenType::Semicolon, "Expected ; after value") {
             Ok(_) => Ok(stmt::Stmt::Print {
+                keyword,
                 expression: Box::new(value),
             }),
             Err(e) => {
@@ -629,6 +937,7 @@ impl<'a> Parser<'a> {
     }
 
     fn println_statement(&mut self) -> Result<stmt::Stmt, ParseError> {
+        let keyword = self.previous().clone();
         let value = self.expression()?;
 
         if self.peek().token_type == TokenType::DotDot {
@@ -645,12 +954,14 @@ impl<'a> Parser<'a> {
                 end: Box::new(end),
             };
             return Ok(stmt::Stmt::PrintLn {
+                keyword,
                 expression: Box::new(range),
             });
         }
 
         match self.consume(&TokenType::Semicolon, "Expected ; after value") {
             Ok(_) => Ok(stmt::Stmt::PrintLn {
+                keyword,
                 expression: Box::new(value),
             }),
             Err(e) => {
@@ -903,6 +1214,15 @@ impl<'a> Parser<'a> {
                 right,
             });
         }
+        if self.rmatch(&[TokenType::Await])? {
+            let keyword = self.previous().clone();
+            let value = Box::new(self.unary()?);
+            return Ok(expr::Expr::Await {
+                id: self.next_id(),
+                keyword,
+                value,
+            });
+        }
 
         self.call()
     }
@@ -1018,6 +1338,19 @@ impl<'a> Parser<'a> {
             });
         }
 
+        if self.rmatch(&[TokenType::Base])? {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::Dot, "Expect '.' after 'base'")?;
+            let method = self
+                .consume(&TokenType::Identifier, "Expect superclass method name")?
+                .clone();
+            return Ok(expr::Expr::Base {
+                id: self.next_id(),
+                keyword,
+                method,
+            });
+        }
+
         if self.rmatch(&[TokenType::This])? {
             return Ok(expr::Expr::This {
                 id: self.next_id(),
@@ -1026,6 +1359,12 @@ impl<'a> Parser<'a> {
         }
 
         if self.rmatch(&[TokenType::Identifier])? {
+            if self.previous().lexeme.starts_with('#') {
+                return Err(ParseError::SyntaxError {
+                    token: self.previous().clone(),
+                    message: "Private names are only members, reached after '.'".into(),
+                });
+            }
             return Ok(expr::Expr::Variable {
                 id: self.next_id(),
                 name: self.previous().clone(),
@@ -1055,7 +1394,10 @@ impl<'a> Parser<'a> {
 
             match self.peek().token_type {
                 TokenType::Class
+                | TokenType::Trait
+                | TokenType::Enum
                 | TokenType::Fn
+                | TokenType::Async
                 | TokenType::Let
                 | TokenType::For
                 | TokenType::Loop
@@ -1064,6 +1406,7 @@ impl<'a> Parser<'a> {
                 | TokenType::Print
                 | TokenType::PrintLn
                 | TokenType::Throw
+                | TokenType::Yield
                 | TokenType::Return => return,
                 _ => {
                     self.advance();
@@ -1118,7 +1461,7 @@ impl<'a> Parser<'a> {
     }
 
     fn previous(&self) -> &Token {
-        self.tokens.get(self.current - 1).unwrap()
+        self.tokens.get(self.current.saturating_sub(1)).unwrap()
     }
 
     fn next_id(&mut self) -> ExprId {
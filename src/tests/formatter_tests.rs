@@ -0,0 +1,122 @@
+use std::fs;
+
+use crate::crux::runner::Runner;
+
+fn format(source: &str) -> String {
+    Runner::format(source, "formatter_test.reix").expect("test source should parse")
+}
+
+#[test]
+pub fn formats_to_canonical_source() {
+    let source = r#"
+use std/math as Math;
+class A < B,C {
+  static make( ) { return A(); }
+  init(x) {this.x=x;}
+  get() { return this -> x; }
+}
+fn f(a,b){
+  for(let i=0;i<10;i=i+1){ if(i==3) continue; else print i; }
+  loop (let j = 0..10; 2) {println j;}
+  this <- x = 3;
+  do { throw "x\n"; } fail(let e) { println e; }
+  if (a) { return -a; } else if (!b) { return (a+b)*2; } else { return @typeof(a, "A"); }
+}
+"#;
+
+    let expected = r#"use std/math as Math;
+class A < B, C {
+    static make() {
+        return A();
+    }
+
+    init(x) {
+        this.x = x;
+    }
+
+    get() {
+        return this -> x;
+    }
+}
+fn f(a, b) {
+    for (let i = 0; i < 10; i = i + 1) {
+        if (i == 3) continue;
+        else print i;
+    }
+    loop (let j = 0..10; 2) {
+        println j;
+    }
+    this <- x = 3;
+    do {
+        throw "x\n";
+    } fail (let e) {
+        println e;
+    }
+    if (a) {
+        return -a;
+    } else if (!b) {
+        return (a + b) * 2;
+    } else {
+        return @typeof(a, "A");
+    }
+}
+"#;
+
+    assert_eq!(format(source), expected);
+}
+
+#[test]
+pub fn keeps_comments_and_blank_lines() {
+    let source = r#"// header
+let a = 1; // one
+
+
+/* two */
+fn f() {
+  // inside
+  return a;
+  // last
+}
+
+{
+  // only a comment
+}
+// end
+"#;
+
+    let expected = r#"// header
+let a = 1; // one
+
+/* two */
+fn f() {
+    // inside
+    return a;
+    // last
+}
+
+{
+    // only a comment
+}
+// end
+"#;
+
+    assert_eq!(format(source), expected);
+}
+
+#[test]
+pub fn formatting_is_idempotent() {
+    for entry in fs::read_dir("src/tests/code").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "reix") {
+            continue;
+        }
+
+        let location = path.to_string_lossy().to_string();
+        let source = fs::read_to_string(&path).unwrap();
+        let Some(once) = Runner::format(&source, &location) else {
+            continue;
+        };
+        let twice = Runner::format(&once, &location).expect("formatted code should parse");
+        assert_eq!(once, twice, "{} is not stable under formatting", location);
+    }
+}
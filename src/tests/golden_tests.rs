@@ -0,0 +1,126 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use crate::crux::output::{self, Output};
+use crate::crux::runner::Runner;
+
+/// Every top-level script here runs against its `.expected` file.
+const SCRIPTS: &str = "./src/tests/code";
+/// Scripts starting with this line are not run (servers, endless loops).
+const SKIP_MARKER: &str = "// golden: skip";
+/// Set to rewrite the `.expected` files from the current output.
+const BLESS_VAR: &str = "REI_BLESS";
+
+/// Runs a script in-process on a thread named like the CLI's, so error
+/// headers match what `rei <file>` prints.
+fn run_script(location: &str) -> Output {
+    let location = location.to_string();
+    thread::Builder::new()
+        .name("main".to_string())
+        .spawn(move || {
+            let source = fs::read_to_string(&location).unwrap();
+            output::capture(|| Runner::run(&source, &location))
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+/// Drops color codes and thread ids, which differ between runs.
+fn normalize(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\x1b' {
+            let end = rest.find('m').map_or(rest.len(), |i| i + 1);
+            rest = &rest[end..];
+        } else if let Some(after) = rest.strip_prefix("ThreadId(") {
+            let end = after.find(')').map_or(after.len(), |i| i + 1);
+            result.push_str("ThreadId(N)");
+            rest = &after[end..];
+        } else {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    result
+}
+
+fn render(output: &Output) -> String {
+    format!(
+        "status: {}\n--- stdout\n{}--- stderr\n{}",
+        output.status,
+        normalize(&output.stdout),
+        normalize(&output.stderr)
+    )
+}
+
+fn scripts() -> Vec<String> {
+    let mut scripts: Vec<String> = fs::read_dir(SCRIPTS)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "reix"))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+#[test]
+pub fn scripts_match_expected_output() {
+    let bless = env::var_os(BLESS_VAR).is_some();
+    let mut failures = vec![];
+
+    for script in scripts() {
+        let source = fs::read_to_string(&script).unwrap();
+        if source.starts_with(SKIP_MARKER) {
+            continue;
+        }
+
+        let actual = render(&run_script(&script));
+        let expected_path = Path::new(&script).with_extension("expected");
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}\n=== expected\n{}=== actual\n{}",
+                script, expected, actual
+            )),
+            Err(_) => failures.push(format!(
+                "{}: missing {} (run with {}=1 to create it)",
+                script,
+                expected_path.display(),
+                BLESS_VAR
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} script(s) differ from their expected output:\n\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+pub fn captures_output_and_exit_status() {
+    let output = output::capture(|| {
+        Runner::run("println \"out\";\nlet x = 1 / 0;", "capture.reix");
+    });
+    assert_eq!(output.stdout, "out\n");
+    assert!(normalize(&output.stderr).contains("Divided By Zero"));
+    assert_eq!(output.status, 1);
+
+    let output = output::capture(|| Runner::run("let = 1;", "capture.reix"));
+    assert!(output.stdout.is_empty());
+    assert!(normalize(&output.stderr).contains("Expect variable name"));
+    assert_eq!(output.status, 65);
+}
@@ -1,105 +1,114 @@
-use crate::backend::stmt::Stmt;
 use crate::backend::interpreter::Interpreter;
-use crate::crux::token::{ Object, Token, TokenType };
-use crate::frontend::expr::Expr;
+use crate::backend::stmt::Stmt;
+use crate::crux::token::{Object, Token, TokenType};
+use crate::frontend::expr::{Expr, ExprId};
+
+fn token(token_type: TokenType, lexeme: &str, line: usize) -> Token {
+    Token::new(
+        token_type,
+        lexeme.to_string(),
+        Object::Null,
+        line,
+        0,
+        "interpreter_test.reix".to_string(),
+    )
+}
 
 #[test]
 pub fn test_binary_number_object() {
-
-    let caller = Interpreter::new();
+    let caller = Interpreter::new().unwrap();
 
     let left = Object::Number(10.0);
     let right = Object::Number(2.0);
 
-    let obj_plus = caller.binary_number_operation(
-        left.clone(),
-        right.clone(),
-        Token::new(TokenType::Plus, String::from("+"), Object::Null, 1),
-        |a, b| a + b
-    ).unwrap();
-    let obj_minus = caller.binary_number_operation(
-        left.clone(),
-        right.clone(),
-        Token::new(TokenType::Minus, String::from("-"), Object::Null, 2),
-        |a, b| a - b
-    ).unwrap();
-    let obj_multiply = caller.binary_number_operation(
-        left.clone(),
-        right.clone(),
-        Token::new(TokenType::Star, String::from("*"), Object::Null, 3),
-        |a, b| a * b
-    ).unwrap();
-    let obj_divide = caller.binary_number_operation(
-        left.clone(),
-        right.clone(),
-        Token::new(TokenType::Slash, String::from("/"), Object::Null, 4),
-        |a, b| a / b
-    ).unwrap();
-
-    println!("binary operations on Object::Number(10.0) and Object::Number(2.0)\nplus: {}, minus: {}, multiply: {}, divide: {}", obj_plus, obj_minus, obj_multiply, obj_divide);
-
+    let obj_plus = caller
+        .binary_number_operation(
+            left.clone(),
+            right.clone(),
+            token(TokenType::Plus, "+", 1),
+            |a, b| a + b,
+        )
+        .unwrap();
+    let obj_minus = caller
+        .binary_number_operation(
+            left.clone(),
+            right.clone(),
+            token(TokenType::Minus, "-", 2),
+            |a, b| a - b,
+        )
+        .unwrap();
+    let obj_multiply = caller
+        .binary_number_operation(
+            left.clone(),
+            right.clone(),
+            token(TokenType::Star, "*", 3),
+            |a, b| a * b,
+        )
+        .unwrap();
+    let obj_divide = caller
+        .binary_number_operation(left, right, token(TokenType::Slash, "/", 4), |a, b| a / b)
+        .unwrap();
+
+    assert!(caller.is_equal(obj_plus, Object::Number(12.0)));
+    assert!(caller.is_equal(obj_minus, Object::Number(8.0)));
+    assert!(caller.is_equal(obj_multiply, Object::Number(20.0)));
+    assert!(caller.is_equal(obj_divide, Object::Number(5.0)));
 }
 
 #[test]
 pub fn test_string_concat() {
-
     let left = Object::Str(String::from("Hi "));
     let right = Object::Str(String::from("Mate"));
 
     let concated = match (left, right) {
-        (Object::Str(v), Object::Str(b)) => { v + &b },
-        _ => String::from("Im actually ")
+        (Object::Str(v), Object::Str(b)) => v + &b,
+        _ => String::from("Im actually "),
     };
 
-    println!("two strings concated: {}", concated);
-
+    assert_eq!(concated, "Hi Mate");
 }
 
 #[test]
 pub fn comparison_test() {
-
-    let caller = Interpreter::new();
+    let caller = Interpreter::new().unwrap();
 
     let com_1 = caller.is_equal(Object::Bool(true), Object::Bool(true));
     let com_2 = caller.is_equal(Object::Bool(true), Object::Bool(false));
     let com_3 = caller.is_equal(Object::Str(String::from("Hi")), Object::Bool(true));
     let com_4 = caller.is_equal(Object::Number(1.0), Object::Number(1.0));
 
-    println!("true == true ? {}\ntrue == false ? {}\nHi == true ? {}\n1.0 == 1.0 ? {}", com_1, com_2, com_3, com_4);
-
-    assert_eq!(true, com_1);
-    assert_eq!(false, com_2);
-    assert_eq!(false, com_3);
-    assert_eq!(true, com_4);
-
-
+    assert!(com_1);
+    assert!(!com_2);
+    assert!(!com_3);
+    assert!(com_4);
 }
 
 #[test]
-pub fn environment_test() -> Result<(), Box<dyn std::error::Error>> {
-
+pub fn environment_test() {
     let expr = Expr::Binary {
-        left: Box::new(
-            Expr::Unary {
-                operator: Token::new(TokenType::Minus, "-".to_string(), Object::Null, 1),
-                right: Box::new(Expr::Literal { value: Object::Number(6.9) })
-            }
-        ),
-        operator: Token::new(
-            TokenType::Star, "*".to_string(), Object::Null, 1
-        ),
-        right: Box::new(
-            Expr::Grouping {
-                expression: Box::new( Expr::Literal { value: Object::Number(232.0) } )
-            }
-        ),
+        id: ExprId(0),
+        left: Box::new(Expr::Unary {
+            id: ExprId(1),
+            operator: token(TokenType::Minus, "-", 1),
+            right: Box::new(Expr::Literal {
+                id: ExprId(2),
+                value: Object::Number(6.9),
+            }),
+        }),
+        operator: token(TokenType::Star, "*", 1),
+        right: Box::new(Expr::Grouping {
+            id: ExprId(3),
+            expression: Box::new(Expr::Literal {
+                id: ExprId(4),
+                value: Object::Number(232.0),
+            }),
+        }),
     };
 
     let statement = Stmt::Expression {
-        expression: Box::new(expr)
+        expression: Box::new(expr),
     };
 
-    let mut i = Interpreter::new();
-    i.interpret(vec![statement])?;
-
+    let mut i = Interpreter::new().unwrap();
+    assert!(i.execute(&statement).is_ok());
 }
@@ -0,0 +1,205 @@
+use std::io::Cursor;
+
+use serde_json::{json, Value};
+
+use crate::tools::lsp::{read_message, write_message, Server};
+
+const URI: &str = "file:///tmp/lsp_test.reix";
+
+const SOURCE: &str = r#"class Shape {
+    area() {
+        return 0;
+    }
+}
+
+class Square < Shape {
+    init(side) {
+        this.side = side;
+    }
+
+    static unit() {
+        return Square(1);
+    }
+}
+
+fn double(x) {
+    return x * 2;
+}
+
+let sq = Square.unit();
+println double(sq.area());
+println missing;
+"#;
+
+/// Plays a scripted session against the server and returns every message it sent.
+fn session(requests: Vec<Value>) -> Vec<Value> {
+    session_on(SOURCE, requests)
+}
+
+fn session_on(source: &str, requests: Vec<Value>) -> Vec<Value> {
+    let mut input = vec![];
+    let mut id = 0;
+    let open = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": URI, "languageId": "rei", "version": 1, "text": source } },
+    });
+    for message in [
+        json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} }),
+        open,
+    ]
+    .into_iter()
+    .chain(requests.into_iter().map(|mut request| {
+        id += 1;
+        request["jsonrpc"] = json!("2.0");
+        request["id"] = json!(id);
+        request
+    }))
+    .chain([
+        json!({ "jsonrpc": "2.0", "id": 99, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]) {
+        write_message(&mut input, &message).unwrap();
+    }
+
+    let mut sent = vec![];
+    Server::new(Cursor::new(input), &mut sent).run().unwrap();
+
+    let mut output = Cursor::new(sent);
+    let mut messages = vec![];
+    while let Some(message) = read_message(&mut output).unwrap() {
+        messages.push(message);
+    }
+    messages
+}
+
+fn at(method: &str, line: usize, character: usize) -> Value {
+    json!({
+        "method": method,
+        "params": {
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        },
+    })
+}
+
+fn result(messages: &[Value], id: usize) -> Value {
+    messages
+        .iter()
+        .find(|m| m["id"] == json!(id))
+        .map(|m| m["result"].clone())
+        .unwrap()
+}
+
+#[test]
+pub fn publishes_diagnostics_on_open() {
+    let messages = session(vec![]);
+    let published = messages
+        .iter()
+        .find(|m| m["method"] == "textDocument/publishDiagnostics")
+        .unwrap();
+
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["message"], "Undefined variable 'missing'");
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 22, "character": 8 })
+    );
+    assert!(result(&messages, 0)["capabilities"]["hoverProvider"] == true);
+}
+
+#[test]
+pub fn finds_definitions() {
+    let messages = session(vec![
+        // `double` in `println double(...)`
+        at("textDocument/definition", 21, 10),
+        // `unit` in `Square.unit()`
+        at("textDocument/definition", 20, 17),
+        // `side` parameter used in `this.side = side`
+        at("textDocument/definition", 8, 22),
+    ]);
+
+    assert_eq!(
+        result(&messages, 1)["range"]["start"],
+        json!({ "line": 16, "character": 3 })
+    );
+    assert_eq!(
+        result(&messages, 2)["range"]["start"],
+        json!({ "line": 11, "character": 11 })
+    );
+    assert_eq!(
+        result(&messages, 3)["range"]["start"],
+        json!({ "line": 7, "character": 9 })
+    );
+}
+
+#[test]
+pub fn hovers_show_arity_and_methods() {
+    let messages = session(vec![
+        at("textDocument/hover", 21, 10),
+        at("textDocument/hover", 20, 12),
+    ]);
+
+    let function = result(&messages, 1)["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(function.contains("fn double(x)"));
+    assert!(function.contains("Takes 1 argument(s)"));
+
+    let class = result(&messages, 2)["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(class.contains("class Square < Shape"));
+    assert!(class.contains("`area` (0 argument(s))"));
+    assert!(class.contains("`init` (1 argument(s))"));
+    assert!(class.contains("`static unit` (0 argument(s))"));
+}
+
+#[test]
+pub fn completes_natives_and_lists_symbols() {
+    let messages = session(vec![
+        at("textDocument/completion", 22, 0),
+        json!({ "method": "textDocument/documentSymbol", "params": { "textDocument": { "uri": URI } } }),
+        json!({ "method": "textDocument/unknown", "params": {} }),
+    ]);
+
+    let labels: Vec<String> = result(&messages, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap().to_string())
+        .collect();
+    assert!(labels.iter().any(|l| l.starts_with("_Co_")));
+    assert!(labels.iter().any(|l| l.starts_with("_Ma_")));
+    assert!(labels.contains(&"double".to_string()));
+
+    let symbols = result(&messages, 2);
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["Shape", "Square", "double", "sq"]);
+    assert_eq!(symbols[1]["children"].as_array().unwrap().len(), 2);
+    assert_eq!(symbols[1]["range"]["end"]["line"], 14);
+
+    let unknown = messages.iter().find(|m| m["id"] == json!(3)).unwrap();
+    assert_eq!(unknown["error"]["code"], -32601);
+}
+
+#[test]
+pub fn opening_a_document_runs_none_of_it() {
+    let source = "class Base {\n    static let code = _Proc_exit(3);\n    tick() {}\n}\n\nclass Clock < Base {}\n";
+    let messages = session_on(source, vec![at("textDocument/hover", 5, 7)]);
+
+    let class = result(&messages, 1)["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(class.contains("class Clock < Base"));
+    assert!(class.contains("`tick` (0 argument(s))"));
+}
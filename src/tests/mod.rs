@@ -1,4 +1,9 @@
 // pub mod nested_expression;
-// pub mod interpreter_tests;
-//pub mod parser_test;
 pub mod api_test;
+pub mod formatter_tests;
+pub mod golden_tests;
+pub mod interpreter_tests;
+pub mod lsp_tests;
+pub mod parser_test;
+pub mod resolver_tests;
+pub mod tester_tests;
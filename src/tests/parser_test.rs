@@ -1,21 +1,34 @@
-use std::process;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
 
-use crate::frontend::{ parser, lexer };
 use crate::crux::runner;
+use crate::crux::token::TokenType;
+use crate::frontend::{lexer, parser};
 
 #[test]
 pub fn lexer_token_test() {
-
     let test_file_location = "./src/tests/code/1.reix";
-    let source = runner::Runner::read_file(test_file_location)
-        .unwrap_or_else(|_| {
-        process::exit(65);
-    });
+    let source = runner::Runner::read_file(test_file_location).unwrap();
 
-    let a = lexer::Lexer::new(&source);
+    let a = lexer::Lexer::new(&source, test_file_location.to_string());
     let tokens = a.scan_tokens();
-    for i in tokens {
-        println!("{}", i);
-    }
+    assert_eq!(tokens.first().unwrap().token_type, TokenType::Fn);
+    assert_eq!(tokens.last().unwrap().token_type, TokenType::Eof);
+}
+
+#[test]
+pub fn parser_test() {
+    let test_file_location = "./src/tests/code/12.reix";
+    let source = runner::Runner::read_file(test_file_location).unwrap();
+    let tokens = lexer::Lexer::new(&source, test_file_location.to_string()).scan_tokens();
+
+    let current_file = Some(PathBuf::from(test_file_location));
+    let path_tracker = Rc::new(RefCell::new(test_file_location.to_string()));
+    let mut id_counter = 0;
+    let mut parser = parser::Parser::new(tokens, &current_file, &mut id_counter, path_tracker);
+    let stmts = parser.parse();
 
+    assert!(!parser.is_error);
+    assert!(!stmts.is_empty());
 }
@@ -0,0 +1,268 @@
+use crate::crux::error::Diagnostic;
+use crate::crux::output;
+use crate::crux::runner::Runner;
+
+fn check(source: &str) -> Vec<Diagnostic> {
+    let (parse_failed, diagnostics) = Runner::analyze(source, "resolver_test.reix");
+    assert!(!parse_failed, "test source should parse");
+    diagnostics
+}
+
+fn messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+    diagnostics.iter().map(|d| d.message.clone()).collect()
+}
+
+#[test]
+pub fn clean_program_has_no_diagnostics() {
+    let source = r#"
+        fn add(a, b) {
+            return a + b;
+        }
+
+        class Point {
+            init(x, y) {
+                this.x = x;
+                this.y = y;
+            }
+
+            static origin() {
+                return Point(0, 0);
+            }
+        }
+
+        let p = Point.origin();
+        println add(p.x, 1);
+    "#;
+
+    assert!(check(source).is_empty());
+}
+
+#[test]
+pub fn reports_scope_errors() {
+    let source = r#"
+        println missing;
+        println this;
+        return 1;
+        break;
+    "#;
+
+    let diagnostics = check(source);
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.is_error() || d.message.contains("Unreachable")));
+    let messages = messages(&diagnostics);
+    assert!(messages.contains(&"Undefined variable 'missing'".to_string()));
+    assert!(messages.contains(&"Cannot use 'this' outside of a class.".to_string()));
+    assert!(messages.contains(&"Cannot return from top-level code.".to_string()));
+    assert!(messages.contains(&"Cannot use 'break' outside of a loop.".to_string()));
+}
+
+#[test]
+pub fn reports_lints() {
+    let source = r#"
+        fn f(a) {
+            let unused = 1;
+            return a;
+            println a;
+        }
+
+        class C {
+            static s() {
+                return this;
+            }
+
+            m() {
+                @nope();
+            }
+        }
+
+        f(1, 2);
+        C.s(1);
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        vec![
+            "Local variable 'unused' is never used",
+            "Unreachable code after 'return'",
+            "Cannot use 'this' in a static method.",
+            "Unknown meta method '@nope'",
+            "'f' expects 1 argument(s) but got 2",
+            "'C.s' expects 0 argument(s) but got 1",
+        ]
+    );
+}
+
+#[test]
+pub fn reports_base_misuse() {
+    let source = r#"
+        class A {
+            f() {
+                return base.f();
+            }
+        }
+
+        class B < A {
+            f() {
+                return base.f();
+            }
+
+            static g() {
+                return base.f();
+            }
+        }
+
+        println base.f;
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        [
+            "Cannot use 'base' in a class with no superclass.",
+            "Cannot use 'base' in a static method.",
+            "Cannot use 'base' outside of a class.",
+        ]
+    );
+}
+
+#[test]
+pub fn reports_this_in_field_initializers() {
+    let source = r#"
+        class A {
+            let a = 1;
+        }
+
+        class B < A {
+            let b = this.a;
+            static let c = base.a;
+        }
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        [
+            "Cannot use 'this' in a field initializer.",
+            "Cannot use 'base' in a field initializer.",
+        ]
+    );
+}
+
+#[test]
+pub fn reports_private_access_outside_the_class() {
+    let source = r#"
+        class A {
+            private let secret = 1;
+            private static let count = 0;
+
+            peek(other) {
+                return other.secret + A.count;
+            }
+        }
+
+        println A.count;
+        println A().secret;
+        A.count = 2;
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        [
+            "Cannot access private member 'count' outside of class 'A'.",
+            "Cannot access private member 'secret' outside of class 'A'.",
+            "Cannot access private member 'count' outside of class 'A'.",
+        ]
+    );
+}
+
+#[test]
+pub fn reports_misplaced_yield() {
+    let source = r#"
+        yield 1;
+
+        class A {
+            init() {
+                yield 2;
+            }
+        }
+
+        fn numbers() {
+            yield 3;
+            return 4;
+        }
+
+        fn nested() {
+            fn inner() {
+                return 5;
+            }
+            yield inner();
+        }
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        [
+            "Cannot yield from top-level code.",
+            "Cannot yield from an initializer.",
+            "Cannot return a value from a generator.",
+        ]
+    );
+}
+
+#[test]
+pub fn reports_misplaced_await() {
+    let source = r#"
+        println await 1;
+
+        fn plain() {
+            await 2;
+        }
+
+        async fn task() {
+            let a = await 3;
+            a = await 4;
+            println await 5;
+            yield a;
+            return await 6;
+        }
+
+        class B {
+            async init() {}
+        }
+    "#;
+
+    let messages = messages(&check(source));
+    assert_eq!(
+        messages,
+        [
+            "Cannot use 'await' outside of an async function.",
+            "'await' must be a whole statement, initializer, assignment or return value.",
+            "Cannot yield from an async function.",
+            "An initializer can't be async.",
+        ]
+    );
+}
+
+#[test]
+pub fn check_reports_through_output() {
+    let mut passed = true;
+    let output = output::capture(|| {
+        let source = "fn f() {\n    let unused = 1;\n}\nprintln missing;";
+        passed = Runner::check(source, "check_test.reix");
+    });
+
+    assert!(!passed);
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.contains("Undefined variable 'missing'"));
+    assert!(output.stderr.contains("1 error(s), 1 warning(s)"));
+
+    let output = output::capture(|| {
+        Runner::check("println 1;", "check_test.reix");
+    });
+    assert!(output.stderr.is_empty());
+    assert!(output.stdout.contains("0 error(s), 0 warning(s)"));
+}
@@ -0,0 +1,60 @@
+use std::fs;
+
+use crate::crux::tester;
+
+const FIXTURE: &str = "./src/tests/code/test_assertions.reix";
+
+fn outcomes(filter: Option<&str>) -> Vec<tester::TestOutcome> {
+    let source = fs::read_to_string(FIXTURE).unwrap();
+    tester::run_file(&source, FIXTURE, filter).expect("fixture should load")
+}
+
+#[test]
+pub fn runs_each_discovered_test() {
+    let results = outcomes(None);
+    let names: Vec<&str> = results.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "test_assert",
+            "test_assert_eq",
+            "test_assert_throws",
+            "test_isolated_state",
+            "test_isolated_state_again",
+            "test_failing_assert_eq",
+            "test_runtime_error",
+        ]
+    );
+
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|o| !o.passed())
+        .map(|o| o.name.as_str())
+        .collect();
+    assert_eq!(failed, ["test_failing_assert_eq", "test_runtime_error"]);
+}
+
+#[test]
+pub fn reports_failures_as_runtime_errors() {
+    let results = outcomes(Some("failing"));
+    assert_eq!(results.len(), 1);
+
+    let failure = results[0].failure.as_deref().unwrap();
+    assert!(failure.contains("expected 4, got 3"));
+    assert!(failure.contains("test_failing_assert_eq"));
+}
+
+#[test]
+pub fn filters_tests_by_name() {
+    let results = outcomes(Some("isolated"));
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|o| o.passed()));
+}
+
+#[test]
+pub fn collects_test_files_from_directories() {
+    let files = tester::collect_files(&["./src/tests/code".to_string()]);
+    assert!(files.iter().any(|f| f.ends_with("test_assertions.reix")));
+    assert!(files.iter().any(|f| f.ends_with("math_lib_test.reix")));
+    assert!(!files.iter().any(|f| f.ends_with("weather.reix")));
+}
@@ -0,0 +1,785 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use walkdir::WalkDir;
+
+use crate::backend::interpreter::Interpreter;
+use crate::backend::resolver::Resolver;
+use crate::backend::stmt::Stmt;
+use crate::crux::error::{Diagnostic, ParseError};
+use crate::crux::token::{Object, Token, TokenType, KEYWORDS};
+use crate::frontend::expr::Expr;
+use crate::frontend::lexer::Lexer;
+use crate::frontend::parser::Parser;
+
+/// Zero based `(line, character)` pair, the way LSP counts.
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Method,
+    StaticMethod,
+    Variable,
+    Parameter,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub token: Token,
+    pub params: Vec<String>,
+    /// Class or function the symbol was declared in
+    pub container: Option<String>,
+    /// Closing brace of the body, for classes and functions
+    pub end: Option<Token>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClassInfo {
+    pub parents: Vec<String>,
+    pub methods: Vec<(String, usize)>,
+    pub static_methods: Vec<(String, usize)>,
+}
+
+/// Everything the language server knows about one open document.
+pub struct Analysis {
+    pub path: String,
+    pub tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Vec<Symbol>,
+    pub classes: HashMap<String, ClassInfo>,
+    pub natives: Vec<(String, usize)>,
+    definitions: Vec<(Token, Token)>,
+}
+
+impl Analysis {
+    pub fn new(source: &str, path: &str) -> Self {
+        let (tokens, _, lex_errors) = Lexer::new(source, path.to_string()).scan_recovering();
+        let mut diagnostics: Vec<Diagnostic> = lex_errors.iter().map(parse_diagnostic).collect();
+
+        let current_file = Some(PathBuf::from(path));
+        let mut id_counter = 0;
+        let mut parser = Parser::new(
+            tokens.clone(),
+            &current_file,
+            &mut id_counter,
+            Rc::new(RefCell::new(path.to_string())),
+        );
+        let stmts = parser.parse();
+        diagnostics.extend(parser.errors.iter().map(parse_diagnostic));
+
+        let mut symbols = vec![];
+        collect_symbols(&stmts, None, &tokens, &mut symbols);
+
+        let mut interpreter = match Interpreter::new() {
+            Ok(interpreter) => interpreter,
+            Err(_) => {
+                return Analysis {
+                    path: path.to_string(),
+                    tokens,
+                    diagnostics,
+                    symbols,
+                    classes: HashMap::new(),
+                    natives: vec![],
+                    definitions: vec![],
+                }
+            }
+        };
+
+        let mut natives: Vec<(String, usize)> = interpreter
+            .environment
+            .borrow()
+            .values
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Object::Callable(c) => Some((name.clone(), c.arity())),
+                _ => None,
+            })
+            .collect();
+        natives.sort();
+
+        // Resolver diagnostics on a half parsed file are mostly noise
+        let mut definitions = vec![];
+        if !parser.is_error {
+            let mut resolver = Resolver::with_lints(&mut interpreter);
+            resolver.resolve(&stmts);
+            diagnostics.extend(resolver.diagnostics);
+            definitions = resolver.definitions;
+        }
+
+        let classes = class_infos(&stmts);
+
+        Analysis {
+            path: path.to_string(),
+            tokens,
+            diagnostics,
+            symbols,
+            classes,
+            natives,
+            definitions,
+        }
+    }
+
+    /// The identifier (or `this`) under the cursor.
+    pub fn token_at(&self, pos: Position) -> Option<(usize, &Token)> {
+        self.tokens.iter().enumerate().find(|(_, t)| {
+            let (line, start, end) = token_range(t);
+            matches!(t.token_type, TokenType::Identifier | TokenType::This)
+                && line == pos.0
+                && start <= pos.1
+                && pos.1 <= end
+        })
+    }
+
+    /// Finds where the name under the cursor was declared.
+    pub fn definition(&self, pos: Position) -> Option<&Symbol> {
+        let (index, token) = self.token_at(pos)?;
+
+        if let Some(symbol) = self.symbol_declared_at(token) {
+            return Some(symbol);
+        }
+
+        if let Some((_, declaration)) = self
+            .definitions
+            .iter()
+            .find(|(usage, _)| same_place(usage, token))
+        {
+            return self.symbol_declared_at(declaration);
+        }
+
+        let accessor = index.checked_sub(1).map(|i| &self.tokens[i]);
+        let object = index.checked_sub(2).map(|i| &self.tokens[i]);
+        if let (Some(true), Some(object)) = (accessor.map(is_accessor), object) {
+            let class = match object.token_type {
+                TokenType::This => self.enclosing_class(object).map(|c| c.name.clone()),
+                _ => self
+                    .symbols
+                    .iter()
+                    .find(|s| s.kind == SymbolKind::Class && s.name == object.lexeme)
+                    .map(|c| c.name.clone()),
+            };
+            return self.member(class.as_deref(), &token.lexeme);
+        }
+
+        self.symbols
+            .iter()
+            .find(|s| s.container.is_none() && s.name == token.lexeme)
+    }
+
+    pub fn hover(&self, pos: Position) -> Option<String> {
+        let (_, token) = self.token_at(pos)?;
+
+        if let Some(symbol) = self.definition(pos) {
+            return Some(self.describe(symbol));
+        }
+
+        let (name, arity) = self.natives.iter().find(|(n, _)| *n == token.lexeme)?;
+        Some(format!(
+            "```rei\n{}\n```\nNative function, takes {} argument(s)",
+            name, arity
+        ))
+    }
+
+    pub fn describe(&self, symbol: &Symbol) -> String {
+        let signature = format!("{}({})", symbol.name, symbol.params.join(", "));
+        let arity = format!("Takes {} argument(s)", symbol.params.len());
+        match symbol.kind {
+            SymbolKind::Function => format!("```rei\nfn {}\n```\n{}", signature, arity),
+            SymbolKind::Method | SymbolKind::StaticMethod => {
+                let prefix = match symbol.kind {
+                    SymbolKind::StaticMethod => "static ",
+                    _ => "",
+                };
+                let class = symbol.container.clone().unwrap_or_default();
+                format!("```rei\n{}{}.{}\n```\n{}", prefix, class, signature, arity)
+            }
+            SymbolKind::Variable => format!("```rei\nlet {}\n```", symbol.name),
+            SymbolKind::Parameter => {
+                let owner = symbol.container.clone().unwrap_or_default();
+                format!("```rei\n{}\n```\nParameter of `{}`", symbol.name, owner)
+            }
+            SymbolKind::Class => {
+                let Some(info) = self.classes.get(&symbol.name) else {
+                    return format!("```rei\nclass {}\n```", symbol.name);
+                };
+
+                let mut header = format!("class {}", symbol.name);
+                if !info.parents.is_empty() {
+                    header.push_str(&format!(" < {}", info.parents.join(", ")));
+                }
+
+                let mut text = format!("```rei\n{}\n```", header);
+                for (name, arity) in &info.methods {
+                    text.push_str(&format!("\n- `{}` ({} argument(s))", name, arity));
+                }
+                for (name, arity) in &info.static_methods {
+                    text.push_str(&format!("\n- `static {}` ({} argument(s))", name, arity));
+                }
+                text
+            }
+        }
+    }
+
+    /// Names worth offering at the cursor: members after `.`/`->`, std modules
+    /// after `use`, everything in scope otherwise.
+    pub fn completions(&self, line_prefix: &str) -> Vec<Completion> {
+        let trimmed = line_prefix.trim_start();
+        if let Some(partial) = trimmed.strip_prefix("use ") {
+            return std_modules(Path::new(&self.path))
+                .into_iter()
+                .filter(|(module, _)| module.starts_with(partial.trim()))
+                .map(|(module, class)| Completion {
+                    label: module.clone(),
+                    kind: CompletionKind::Module,
+                    detail: format!("use {} as {};", module, class),
+                })
+                .collect();
+        }
+
+        let word_start = line_prefix
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let before_word = &line_prefix[..word_start];
+        if before_word.ends_with('.') || before_word.ends_with("->") {
+            let mut items = vec![];
+            for (class, info) in &self.classes {
+                for (name, arity) in info.methods.iter().chain(&info.static_methods) {
+                    items.push(Completion {
+                        label: name.clone(),
+                        kind: CompletionKind::Method,
+                        detail: format!("{}.{} ({} argument(s))", class, name, arity),
+                    });
+                }
+            }
+            items.sort_by(|a, b| a.label.cmp(&b.label));
+            items.dedup_by(|a, b| a.label == b.label);
+            return items;
+        }
+
+        let mut items = vec![];
+        let mut keywords: Vec<&&str> = KEYWORDS.keys().collect();
+        keywords.sort();
+        for keyword in keywords {
+            items.push(Completion {
+                label: keyword.to_string(),
+                kind: CompletionKind::Keyword,
+                detail: String::new(),
+            });
+        }
+
+        for (name, arity) in &self.natives {
+            items.push(Completion {
+                label: name.clone(),
+                kind: CompletionKind::Function,
+                detail: format!("native, {} argument(s)", arity),
+            });
+        }
+
+        for symbol in &self.symbols {
+            let kind = match symbol.kind {
+                SymbolKind::Function => CompletionKind::Function,
+                SymbolKind::Class => CompletionKind::Class,
+                SymbolKind::Variable | SymbolKind::Parameter => CompletionKind::Variable,
+                SymbolKind::Method | SymbolKind::StaticMethod => continue,
+            };
+            if symbol.token.path != self.path {
+                continue;
+            }
+            items.push(Completion {
+                label: symbol.name.clone(),
+                kind,
+                detail: symbol.container.clone().unwrap_or_default(),
+            });
+        }
+
+        for (module, class) in std_modules(Path::new(&self.path)) {
+            items.push(Completion {
+                label: class.clone(),
+                kind: CompletionKind::Class,
+                detail: format!("use {} as {};", module, class),
+            });
+        }
+
+        items.dedup_by(|a, b| a.label == b.label && a.kind == b.kind);
+        items
+    }
+
+    fn symbol_declared_at(&self, token: &Token) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| same_place(&s.token, token))
+    }
+
+    fn enclosing_class(&self, token: &Token) -> Option<&Symbol> {
+        let here = (token.line, token.place);
+        self.symbols.iter().find(|s| {
+            s.kind == SymbolKind::Class
+                && s.token.path == token.path
+                && (s.token.line, s.token.place) < here
+                && s.end
+                    .as_ref()
+                    .is_some_and(|end| here < (end.line, end.place))
+        })
+    }
+
+    fn member(&self, class: Option<&str>, name: &str) -> Option<&Symbol> {
+        let is_member = |s: &&Symbol| {
+            matches!(s.kind, SymbolKind::Method | SymbolKind::StaticMethod) && s.name == name
+        };
+
+        if let Some(class) = class {
+            // Walk up the inheritance chain recorded from the runtime classes
+            let mut pending = vec![class.to_string()];
+            while let Some(current) = pending.pop() {
+                let found = self
+                    .symbols
+                    .iter()
+                    .filter(is_member)
+                    .find(|s| s.container.as_deref() == Some(current.as_str()));
+                if found.is_some() {
+                    return found;
+                }
+                if let Some(info) = self.classes.get(&current) {
+                    pending.extend(info.parents.iter().rev().cloned());
+                }
+            }
+        }
+
+        self.symbols.iter().find(is_member)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+    Keyword,
+    Function,
+    Method,
+    Class,
+    Variable,
+    Module,
+}
+
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: String,
+}
+
+/// Zero based line and the character span of a token.
+pub fn token_range(token: &Token) -> (usize, usize, usize) {
+    let length = token.lexeme.chars().count();
+    (
+        token.line.saturating_sub(1),
+        token.place.saturating_sub(length),
+        token.place,
+    )
+}
+
+fn same_place(a: &Token, b: &Token) -> bool {
+    a.line == b.line && a.place == b.place && a.path == b.path
+}
+
+fn is_accessor(token: &Token) -> bool {
+    matches!(
+        token.token_type,
+        TokenType::Dot | TokenType::Getter | TokenType::Setter
+    )
+}
+
+fn parse_diagnostic(error: &ParseError) -> Diagnostic {
+    match error {
+        ParseError::SyntaxError { token, message } => Diagnostic::error(token, message),
+    }
+}
+
+/// The `}` closing the body of the class or function declared by `name`.
+fn closing_brace(tokens: &[Token], name: &Token) -> Option<Token> {
+    let start = tokens.iter().position(|t| same_place(t, name))?;
+    let mut depth = 0;
+    for token in &tokens[start..] {
+        match token.token_type {
+            // Only a declaration header may come before the body
+            TokenType::Identifier
+            | TokenType::Comma
+            | TokenType::Less
+            | TokenType::LeftParen
+            | TokenType::RightParen
+                if depth == 0 => {}
+            TokenType::LeftBrace => depth += 1,
+            TokenType::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(token.clone());
+                }
+            }
+            _ if depth == 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn collect_symbols(
+    statements: &[Stmt],
+    container: Option<&str>,
+    tokens: &[Token],
+    symbols: &mut Vec<Symbol>,
+) {
+    for statement in statements {
+        match statement {
+            Stmt::Function {
+                name, params, body, ..
+            } => {
+                push_function(
+                    SymbolKind::Function,
+                    name,
+                    params,
+                    body,
+                    container,
+                    tokens,
+                    symbols,
+                );
+            }
+            Stmt::Class {
+                name,
+                superclass_refs: _,
+                methods,
+                static_methods,
+                fields: _,
+                accessors: _,
+                private_members: _,
+                traits: _,
+                expose: _,
+            } => {
+                symbols.push(Symbol {
+                    name: name.lexeme.clone(),
+                    kind: SymbolKind::Class,
+                    token: name.clone(),
+                    params: vec![],
+                    container: container.map(str::to_string),
+                    end: closing_brace(tokens, name),
+                });
+                for (kind, members) in [
+                    (SymbolKind::Method, methods),
+                    (SymbolKind::StaticMethod, static_methods),
+                ] {
+                    for member in members {
+                        if let Stmt::Function {
+                            name: method,
+                            params,
+                            body,
+                            ..
+                        } = member
+                        {
+                            push_function(
+                                kind,
+                                method,
+                                params,
+                                body,
+                                Some(&name.lexeme),
+                                tokens,
+                                symbols,
+                            );
+                        }
+                    }
+                }
+            }
+            Stmt::Enum { name, .. } => symbols.push(Symbol {
+                name: name.lexeme.clone(),
+                kind: SymbolKind::Class,
+                token: name.clone(),
+                params: vec![],
+                container: container.map(str::to_string),
+                end: closing_brace(tokens, name),
+            }),
+            Stmt::Trait { name, methods, .. } => {
+                symbols.push(Symbol {
+                    name: name.lexeme.clone(),
+                    kind: SymbolKind::Class,
+                    token: name.clone(),
+                    params: vec![],
+                    container: container.map(str::to_string),
+                    end: closing_brace(tokens, name),
+                });
+                for method in methods {
+                    if let Stmt::Function {
+                        name: method,
+                        params,
+                        body,
+                        ..
+                    } = method
+                    {
+                        push_function(
+                            SymbolKind::Method,
+                            method,
+                            params,
+                            body,
+                            Some(&name.lexeme),
+                            tokens,
+                            symbols,
+                        );
+                    }
+                }
+            }
+            Stmt::Use {
+                class: Some(class), ..
+            } => collect_symbols(std::slice::from_ref(class), container, tokens, symbols),
+            Stmt::Let { name, .. } => symbols.push(variable(name, container)),
+            Stmt::Loop { name, body, .. } | Stmt::Each { name, body, .. } => {
+                symbols.push(variable(name, container));
+                collect_symbols(std::slice::from_ref(body), container, tokens, symbols);
+            }
+            Stmt::Block { statements } => collect_symbols(statements, container, tokens, symbols),
+            Stmt::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                collect_symbols(
+                    std::slice::from_ref(then_branch),
+                    container,
+                    tokens,
+                    symbols,
+                );
+                if let Some(else_branch) = else_branch {
+                    collect_symbols(
+                        std::slice::from_ref(else_branch),
+                        container,
+                        tokens,
+                        symbols,
+                    );
+                }
+            }
+            Stmt::While { body, .. } => {
+                collect_symbols(std::slice::from_ref(body), container, tokens, symbols);
+            }
+            Stmt::For {
+                initializer, body, ..
+            } => {
+                if let Some(initializer) = initializer {
+                    collect_symbols(
+                        std::slice::from_ref(initializer),
+                        container,
+                        tokens,
+                        symbols,
+                    );
+                }
+                collect_symbols(std::slice::from_ref(body), container, tokens, symbols);
+            }
+            Stmt::Exception {
+                do_stmts,
+                fail_stmts,
+                fail_binding,
+                finish_stmts,
+                ..
+            } => {
+                let mut parts = vec![do_stmts.as_ref()];
+                parts.extend(fail_binding.as_deref());
+                parts.push(fail_stmts.as_ref());
+                parts.extend(finish_stmts.as_deref());
+                for part in parts {
+                    collect_symbols(std::slice::from_ref(part), container, tokens, symbols);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn push_function(
+    kind: SymbolKind,
+    name: &Token,
+    params: &[Token],
+    body: &[Stmt],
+    container: Option<&str>,
+    tokens: &[Token],
+    symbols: &mut Vec<Symbol>,
+) {
+    symbols.push(Symbol {
+        name: name.lexeme.clone(),
+        kind,
+        token: name.clone(),
+        params: params.iter().map(|p| p.lexeme.clone()).collect(),
+        container: container.map(str::to_string),
+        end: closing_brace(tokens, name),
+    });
+
+    for param in params {
+        symbols.push(Symbol {
+            name: param.lexeme.clone(),
+            kind: SymbolKind::Parameter,
+            token: param.clone(),
+            params: vec![],
+            container: Some(name.lexeme.clone()),
+            end: None,
+        });
+    }
+
+    collect_symbols(body, Some(&name.lexeme), tokens, symbols);
+}
+
+fn variable(name: &Token, container: Option<&str>) -> Symbol {
+    Symbol {
+        name: name.lexeme.clone(),
+        kind: SymbolKind::Variable,
+        token: name.clone(),
+        params: vec![],
+        container: container.map(str::to_string),
+        end: None,
+    }
+}
+
+/// Methods of the top-level and imported classes, read from their
+/// declarations. Nothing is evaluated: opening a document must not run it.
+/// Inherited and trait default methods show up too when the parent or
+/// trait was declared before.
+fn class_infos(statements: &[Stmt]) -> HashMap<String, ClassInfo> {
+    let mut infos = HashMap::new();
+    let mut traits: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+
+    for statement in statements {
+        let (name, class) = match statement {
+            Stmt::Class { name, .. } => (name, statement),
+            Stmt::Use {
+                alias,
+                class: Some(class),
+                ..
+            } => (alias, class.as_ref()),
+            Stmt::Trait { name, methods, .. } => {
+                traits.insert(name.lexeme.clone(), signatures(methods));
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(info) = class_info(class, &infos, &traits) {
+            infos.insert(name.lexeme.clone(), info);
+        }
+    }
+
+    infos
+}
+
+fn class_info(
+    class: &Stmt,
+    infos: &HashMap<String, ClassInfo>,
+    traits: &HashMap<String, Vec<(String, usize)>>,
+) -> Option<ClassInfo> {
+    let Stmt::Class {
+        superclass_refs,
+        methods: own_methods,
+        static_methods: own_static_methods,
+        traits: implemented,
+        ..
+    } = class
+    else {
+        return None;
+    };
+    let parents = names(superclass_refs);
+
+    // Own methods win over inherited ones, the way resolution goes
+    let mut methods = signatures(own_methods);
+    let mut static_methods = signatures(own_static_methods);
+    let inherited = parents.iter().filter_map(|parent| infos.get(parent));
+    for parent in inherited {
+        add_missing(&mut methods, &parent.methods);
+        add_missing(&mut static_methods, &parent.static_methods);
+    }
+    for name in names(implemented) {
+        if let Some(defaults) = traits.get(&name) {
+            add_missing(&mut methods, defaults);
+        }
+    }
+
+    methods.sort();
+    static_methods.sort();
+    Some(ClassInfo {
+        parents,
+        methods,
+        static_methods,
+    })
+}
+
+/// Names of the classes or traits a declaration lists.
+fn names(exprs: &[Expr]) -> Vec<String> {
+    exprs
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Variable { name, .. } => Some(name.lexeme.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `(name, arity)` of each function declaration.
+fn signatures(functions: &[Stmt]) -> Vec<(String, usize)> {
+    functions
+        .iter()
+        .filter_map(|function| match function {
+            Stmt::Function { name, params, .. } => Some((name.lexeme.clone(), params.len())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn add_missing(methods: &mut Vec<(String, usize)>, more: &[(String, usize)]) {
+    for (name, arity) in more {
+        if !methods.iter().any(|(n, _)| n == name) {
+            methods.push((name.clone(), *arity));
+        }
+    }
+}
+
+/// `(module path, exposed class)` for every std module reachable from `file`.
+fn std_modules(file: &Path) -> Vec<(String, String)> {
+    let mut roots = vec![PathBuf::from("./lib")];
+    if let Some(dir) = file.parent() {
+        roots.insert(0, dir.join("lib"));
+    }
+
+    let mut modules = vec![];
+    for root in roots {
+        for entry in WalkDir::new(root.join("std")).into_iter().flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "reix") {
+                continue;
+            }
+            let Ok(source) = fs::read_to_string(path) else {
+                continue;
+            };
+            let Ok(relative) = path
+                .with_extension("")
+                .strip_prefix(&root)
+                .map(Path::to_path_buf)
+            else {
+                continue;
+            };
+            let module = relative.to_string_lossy().replace('\\', "/");
+            if let Some(class) = exposed_class(&source) {
+                if !modules.iter().any(|(m, _)| *m == module) {
+                    modules.push((module, class));
+                }
+            }
+        }
+    }
+
+    modules.sort();
+    modules
+}
+
+fn exposed_class(source: &str) -> Option<String> {
+    let (tokens, _, _) = Lexer::new(source, String::new()).scan_recovering();
+    tokens.windows(3).find_map(
+        |w| match (&w[0].token_type, &w[1].token_type, &w[2].token_type) {
+            (TokenType::Expose, TokenType::Class, TokenType::Identifier) => {
+                Some(w[2].lexeme.clone())
+            }
+            _ => None,
+        },
+    )
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::crux::error::Severity;
+use crate::crux::token::Token;
+
+pub mod analysis;
+
+use analysis::{token_range, Analysis, CompletionKind, Symbol, SymbolKind};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+/// Language server speaking LSP (JSON-RPC with `Content-Length` framing).
+/// Generic over its streams so tests can drive it with an in-memory client.
+pub struct Server<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    documents: HashMap<String, (String, Analysis)>,
+    shutdown: bool,
+}
+
+pub fn serve_stdio() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    Server::new(stdin.lock(), stdout.lock()).run()
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Server {
+            reader,
+            writer,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Serves requests until the client sends `exit` or closes the stream.
+    pub fn run(&mut self) -> io::Result<()> {
+        while let Some(message) = read_message(&mut self.reader)? {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            if method == "exit" {
+                break;
+            }
+
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            match message.get("id").cloned() {
+                Some(id) => {
+                    let response = match self.request(&method, &params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, text)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": text },
+                        }),
+                    };
+                    write_message(&mut self.writer, &response)?;
+                }
+                None => self.notification(&method, &params)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, "Server is shutting down".to_string()));
+        }
+
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": [".", ">", "/"] },
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "rei-lsp", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => {
+                let Some((analysis, pos)) = self.locate(params) else {
+                    return Ok(Value::Null);
+                };
+                Ok(analysis
+                    .definition(pos)
+                    .map(|symbol| location(&symbol.token))
+                    .unwrap_or(Value::Null))
+            }
+            "textDocument/hover" => {
+                let Some((analysis, pos)) = self.locate(params) else {
+                    return Ok(Value::Null);
+                };
+                let Some(text) = analysis.hover(pos) else {
+                    return Ok(Value::Null);
+                };
+                let range = analysis.token_at(pos).map(|(_, t)| range(t));
+                Ok(json!({
+                    "contents": { "kind": "markdown", "value": text },
+                    "range": range,
+                }))
+            }
+            "textDocument/completion" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let Some((text, analysis)) = self.documents.get(uri) else {
+                    return Ok(json!([]));
+                };
+                let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+                let character = params["position"]["character"].as_u64().unwrap_or(0) as usize;
+                let prefix: String = text
+                    .lines()
+                    .nth(line)
+                    .unwrap_or_default()
+                    .chars()
+                    .take(character)
+                    .collect();
+
+                let items: Vec<Value> = analysis
+                    .completions(&prefix)
+                    .into_iter()
+                    .map(|item| {
+                        json!({
+                            "label": item.label,
+                            "kind": completion_kind(item.kind),
+                            "detail": item.detail,
+                        })
+                    })
+                    .collect();
+                Ok(Value::Array(items))
+            }
+            "textDocument/documentSymbol" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let Some((_, analysis)) = self.documents.get(uri) else {
+                    return Ok(json!([]));
+                };
+                Ok(Value::Array(document_symbols(analysis, None)))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str(),
+            // Full sync: the last change holds the whole document
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str()),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return self.publish(&uri, vec![]);
+            }
+            _ => return Ok(()),
+        };
+
+        let Some(text) = text else {
+            return Ok(());
+        };
+        let analysis = Analysis::new(text, &uri_to_path(&uri));
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .filter(|d| d.token.path == analysis.path)
+            .map(|d| {
+                json!({
+                    "range": range(&d.token),
+                    "severity": if d.severity == Severity::Error { 1 } else { 2 },
+                    "source": "rei",
+                    "message": d.message,
+                })
+            })
+            .collect();
+        self.documents
+            .insert(uri.clone(), (text.to_string(), analysis));
+        self.publish(&uri, diagnostics)
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        });
+        write_message(&mut self.writer, &message)
+    }
+
+    fn locate(&self, params: &Value) -> Option<(&Analysis, (usize, usize))> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let (_, analysis) = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        Some((analysis, (line, character)))
+    }
+}
+
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header",
+        ));
+    };
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn range(token: &Token) -> Value {
+    let (line, start, end) = token_range(token);
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn location(token: &Token) -> Value {
+    json!({ "uri": path_to_uri(&token.path), "range": range(token) })
+}
+
+fn document_symbols(analysis: &Analysis, container: Option<&str>) -> Vec<Value> {
+    analysis
+        .symbols
+        .iter()
+        .filter(|s| s.token.path == analysis.path && s.container.as_deref() == container)
+        .filter_map(|symbol| {
+            let kind = match symbol.kind {
+                SymbolKind::Class => 5,
+                SymbolKind::Method | SymbolKind::StaticMethod => 6,
+                SymbolKind::Function => 12,
+                SymbolKind::Variable => 13,
+                SymbolKind::Parameter => return None,
+            };
+            let children = match symbol.kind {
+                SymbolKind::Class => document_symbols(analysis, Some(&symbol.name)),
+                _ => vec![],
+            };
+            Some(json!({
+                "name": symbol.name,
+                "detail": detail(symbol),
+                "kind": kind,
+                "range": full_range(symbol),
+                "selectionRange": range(&symbol.token),
+                "children": children,
+            }))
+        })
+        .collect()
+}
+
+fn detail(symbol: &Symbol) -> String {
+    match symbol.kind {
+        SymbolKind::Function | SymbolKind::Method | SymbolKind::StaticMethod => {
+            format!("({})", symbol.params.join(", "))
+        }
+        _ => String::new(),
+    }
+}
+
+/// From the declared name to the closing brace of the body, when there is one.
+fn full_range(symbol: &Symbol) -> Value {
+    let (line, start, _) = token_range(&symbol.token);
+    let (end_line, end) = match &symbol.end {
+        Some(brace) => {
+            let (end_line, _, end) = token_range(brace);
+            (end_line, end)
+        }
+        None => {
+            let (_, _, end) = token_range(&symbol.token);
+            (line, end)
+        }
+    };
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": end_line, "character": end },
+    })
+}
+
+fn completion_kind(kind: CompletionKind) -> u8 {
+    match kind {
+        CompletionKind::Method => 2,
+        CompletionKind::Function => 3,
+        CompletionKind::Variable => 6,
+        CompletionKind::Class => 7,
+        CompletionKind::Module => 9,
+        CompletionKind::Keyword => 14,
+    }
+}
+
+fn uri_to_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    path.replace("%20", " ")
+}
+
+fn path_to_uri(path: &str) -> String {
+    let absolute = Path::new(path)
+        .canonicalize()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    format!("file://{}", absolute.replace(' ', "%20"))
+}
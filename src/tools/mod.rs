@@ -1 +1,2 @@
 pub mod ast_generator;
+pub mod lsp;